@@ -0,0 +1,82 @@
+//! Opens a `MessageBox` from within a window's event handler, which is only possible on windows.
+//!
+//! The message box runs its own message loop, so the window keeps getting messages
+//! while its handler is still running. The window methods called around it
+//! send messages of their own, which come back through the window procedure right away.
+
+#[cfg(target_os = "windows")]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Weak};
+    use waywin::{
+        event::{Key, LogicalKey, WaywinEvent, WindowEvent},
+        ControlFlow, Fullscreen, Waywin,
+    };
+    use windows::{
+        core::w,
+        Win32::{
+            Foundation::HWND,
+            UI::WindowsAndMessaging::{MessageBoxW, MB_OK},
+        },
+    };
+
+    env_logger::init();
+
+    let mut waywin = Waywin::init("message-box")?;
+    let window = Arc::new(waywin.create_window("Press space")?);
+    let proxy = waywin.create_proxy::<()>();
+
+    // weak so the handler doesn't keep its own window alive
+    let handler_window = Arc::downgrade(&window);
+    let mut presses = 0;
+    window.set_event_handler(move |event| {
+        let Some(window) = Weak::upgrade(&handler_window) else {
+            return;
+        };
+        match event {
+            WindowEvent::CloseRequested => {
+                let _ = proxy.send_event(());
+            }
+            WindowEvent::Key {
+                down: true,
+                repeat: false,
+                logical_key: LogicalKey::Key(Key::Space),
+                ..
+            } => {
+                presses += 1;
+                window.set_title(&format!("Pressed space {presses} times"));
+                // resizes the window, sending `WM_SIZE` while this handler runs
+                let fullscreen = window.get_fullscreen();
+                window.set_fullscreen((!fullscreen).then_some(Fullscreen::Borderless(None)));
+
+                unsafe {
+                    MessageBoxW(
+                        Some(HWND(window.hwnd() as *mut _)),
+                        w!("The window keeps getting messages while this is open."),
+                        w!("waywin"),
+                        MB_OK,
+                    )
+                };
+
+                // the data is still there after the message box's loop
+                println!("title after the message box: {}", window.get_title());
+                window.request_redraw();
+            }
+            event => println!("{event:?}"),
+        }
+    });
+
+    waywin.run(move |event, control_flow| {
+        if let WaywinEvent::User(_) = event {
+            *control_flow = ControlFlow::Exit;
+        }
+        // keeps the window alive until the loop exits
+        let _ = &window;
+    });
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn main() {
+    eprintln!("this example only runs on windows");
+}
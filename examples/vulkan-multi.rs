@@ -58,7 +58,7 @@ use vulkano::{
 };
 use waywin::{
     event::{Key, LogicalKey, WaywinEvent, WindowEvent},
-    Waywin, Window,
+    ControlFlow, CursorGrabMode, Fullscreen, Waywin, Window,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -74,19 +74,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     let window2 = Arc::new(waywin.create_window("Vulkan window 2")?);
     let mut app2 = App::new(vk_ctx, window2);
 
-    waywin.run(move |event, running| {
+    waywin.run(move |event, control_flow| {
         if !matches!(
             event,
             WaywinEvent::WindowEvent {
-                event: WindowEvent::Paint,
+                event: WindowEvent::Paint { .. } | WindowEvent::PresentationFeedback { .. },
                 ..
-            }
+            } | WaywinEvent::AboutToWait
         ) {
             println!("{event:#?}");
         }
 
-        app.handle_event(&event, running);
-        app2.handle_event(&event, running);
+        app.handle_event(&event, control_flow);
+        app2.handle_event(&event, control_flow);
     });
 
     Ok(())
@@ -390,17 +390,17 @@ impl App {
             rcx,
         }
     }
-    fn handle_event(&mut self, event: &WaywinEvent, running: &mut bool) {
+    fn handle_event(&mut self, event: &WaywinEvent, control_flow: &mut ControlFlow) {
         match event {
             WaywinEvent::WindowEvent { event, window_id } if *window_id == self.rcx.window.id() => {
                 match event {
-                    WindowEvent::Close => {
-                        *running = false;
+                    WindowEvent::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
                     }
-                    WindowEvent::Resized => {
+                    WindowEvent::Resized { .. } => {
                         self.rcx.recreate_swapchain = true;
                     }
-                    WindowEvent::Paint => {
+                    WindowEvent::Paint { .. } => {
                         let window_size = self.rcx.window.get_physical_size();
 
                         if window_size.0 == 0 || window_size.1 == 0 {
@@ -478,6 +478,7 @@ impl App {
 
                         let command_buffer = builder.build().unwrap();
 
+                        self.rcx.window.pre_present();
                         let future = self
                             .rcx
                             .previous_frame_end
@@ -520,30 +521,37 @@ impl App {
                     }
                     WindowEvent::Key {
                         down: true,
+                        repeat: false,
                         logical_key: LogicalKey::Key(Key::F11),
                         ..
                     } => {
                         let fullscreen = self.rcx.window.get_fullscreen();
-                        self.rcx.window.set_fullscreen(!fullscreen);
+                        self.rcx
+                            .window
+                            .set_fullscreen((!fullscreen).then_some(Fullscreen::Borderless(None)));
                     }
                     WindowEvent::Key {
                         down: true,
+                        repeat: false,
                         logical_key_unmodified,
                         ..
                     } => match logical_key_unmodified.as_ref() {
                         LogicalKey::Character("l") => {
-                            if self.rcx.window.is_pointer_locked() {
-                                self.rcx.window.unlock_pointer();
+                            let grab = if self.rcx.window.cursor_grab() == CursorGrabMode::Locked {
+                                CursorGrabMode::None
                             } else {
-                                self.rcx.window.lock_pointer();
-                            }
+                                CursorGrabMode::Locked
+                            };
+                            self.rcx.window.set_cursor_grab(grab);
                         }
                         LogicalKey::Character("c") => {
-                            if self.rcx.window.is_pointer_confined() {
-                                self.rcx.window.unconfine_pointer();
+                            let grab = if self.rcx.window.cursor_grab() == CursorGrabMode::Confined
+                            {
+                                CursorGrabMode::None
                             } else {
-                                self.rcx.window.confine_pointer();
-                            }
+                                CursorGrabMode::Confined
+                            };
+                            self.rcx.window.set_cursor_grab(grab);
                         }
                         _ => {}
                     },
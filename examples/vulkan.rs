@@ -57,7 +57,7 @@ use vulkano::{
 };
 use waywin::{
     event::{Key, LogicalKey, WaywinEvent, WindowEvent},
-    Waywin, Window,
+    ControlFlow, CursorGrabMode, Fullscreen, Waywin, Window,
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -67,18 +67,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let window = Arc::new(waywin.create_window("Vulkan Example")?);
     let mut app = App::new(window);
 
-    waywin.run(move |event, running| {
+    waywin.run(move |event, control_flow| {
         if !matches!(
             event,
             WaywinEvent::WindowEvent {
-                event: WindowEvent::Paint,
+                event: WindowEvent::Paint { .. } | WindowEvent::PresentationFeedback { .. },
                 ..
-            }
+            } | WaywinEvent::AboutToWait
         ) {
             println!("{event:#?}");
         }
 
-        app.handle_event(&event, running);
+        app.handle_event(&event, control_flow);
     });
 
     Ok(())
@@ -367,20 +367,20 @@ impl App {
             rcx,
         }
     }
-    fn handle_event(&mut self, event: &WaywinEvent, running: &mut bool) {
+    fn handle_event(&mut self, event: &WaywinEvent, control_flow: &mut ControlFlow) {
         match event {
             WaywinEvent::WindowEvent {
                 event,
                 window_id: _,
             } => {
                 match event {
-                    WindowEvent::Close => {
-                        *running = false;
+                    WindowEvent::CloseRequested => {
+                        *control_flow = ControlFlow::Exit;
                     }
-                    WindowEvent::Resized => {
+                    WindowEvent::Resized { .. } => {
                         self.rcx.recreate_swapchain = true;
                     }
-                    WindowEvent::Paint => {
+                    WindowEvent::Paint { .. } => {
                         let window_size = self.rcx.window.get_physical_size();
 
                         if window_size.0 == 0 || window_size.1 == 0 {
@@ -458,6 +458,7 @@ impl App {
 
                         let command_buffer = builder.build().unwrap();
 
+                        self.rcx.window.pre_present();
                         let future = self
                             .rcx
                             .previous_frame_end
@@ -500,37 +501,44 @@ impl App {
                     }
                     WindowEvent::Key {
                         down: true,
+                        repeat: false,
                         logical_key: LogicalKey::Key(Key::F11),
                         ..
                     } => {
                         let fullscreen = self.rcx.window.get_fullscreen();
-                        self.rcx.window.set_fullscreen(!fullscreen);
+                        self.rcx
+                            .window
+                            .set_fullscreen((!fullscreen).then_some(Fullscreen::Borderless(None)));
                     }
                     WindowEvent::Key {
                         down: true,
+                        repeat: false,
                         logical_key_unmodified,
                         ..
                     } => match logical_key_unmodified.as_ref() {
                         LogicalKey::Character("l") => {
-                            if self.rcx.window.is_pointer_locked() {
-                                self.rcx.window.unlock_pointer();
+                            let grab = if self.rcx.window.cursor_grab() == CursorGrabMode::Locked {
+                                CursorGrabMode::None
                             } else {
-                                self.rcx.window.lock_pointer();
-                            }
+                                CursorGrabMode::Locked
+                            };
+                            self.rcx.window.set_cursor_grab(grab);
                         }
                         LogicalKey::Character("c") => {
-                            if self.rcx.window.is_pointer_confined() {
-                                self.rcx.window.unconfine_pointer();
+                            let grab = if self.rcx.window.cursor_grab() == CursorGrabMode::Confined
+                            {
+                                CursorGrabMode::None
                             } else {
-                                self.rcx.window.confine_pointer();
-                            }
+                                CursorGrabMode::Confined
+                            };
+                            self.rcx.window.set_cursor_grab(grab);
                         }
                         _ => {}
                     },
                     _ => {}
                 }
             }
-            WaywinEvent::DeviceEvent(_) => {}
+            _ => {}
         }
     }
 }
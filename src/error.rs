@@ -0,0 +1,50 @@
+use std::{fmt, ops::RangeInclusive};
+
+/// Errors returned when initializing waywin, creating windows or changing their cursor.
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to connect to the display server or lost the connection.
+    ///
+    /// On wayland this usually means no compositor is running or `WAYLAND_DISPLAY` isn't set.
+    Connection(String),
+    /// The display server doesn't provide a required global at one of `versions`.
+    GlobalBind {
+        interface: &'static str,
+        versions: RangeInclusive<u32>,
+    },
+    /// The display server has no seat, so there's no input.
+    NoSeat,
+    /// Failed to set up the event loop.
+    EventLoop(String),
+    /// Failed to create a window or register its class.
+    WindowCreation(String),
+    /// The operation isn't supported on this platform.
+    Unsupported(&'static str),
+    /// The cursor image is invalid or a cursor couldn't be created from it.
+    Cursor(String),
+    /// A software buffer couldn't be shown, see `Window::present_buffer`.
+    Present(String),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connection(err) => write!(f, "display server connection failed: {err}"),
+            Error::GlobalBind {
+                interface,
+                versions,
+            } => write!(
+                f,
+                "failed to bind {interface} (versions {}..={})",
+                versions.start(),
+                versions.end()
+            ),
+            Error::NoSeat => write!(f, "the display server has no seat"),
+            Error::EventLoop(err) => write!(f, "failed to create event loop: {err}"),
+            Error::WindowCreation(err) => write!(f, "failed to create window: {err}"),
+            Error::Unsupported(what) => write!(f, "{what} is not supported on this platform"),
+            Error::Cursor(err) => write!(f, "failed to create cursor: {err}"),
+            Error::Present(err) => write!(f, "failed to present buffer: {err}"),
+        }
+    }
+}
+impl std::error::Error for Error {}
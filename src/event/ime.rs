@@ -0,0 +1,19 @@
+/// Input method events, only sent while allowed with
+/// [`Window::set_ime_allowed`](crate::Window::set_ime_allowed).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Ime {
+    /// The input method is active and may start composing text.
+    Enabled,
+    /// Text that's still being composed, to be drawn in place of the cursor.
+    /// `cursor` is a byte range into `text`, `None` hides the cursor.
+    /// An empty `text` removes the preedit.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    /// Finished text to insert at the cursor.
+    Commit(String),
+    Disabled,
+}
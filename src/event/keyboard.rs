@@ -1,6 +1,47 @@
 use smol_str::SmolStr;
+use std::{fmt, str::FromStr};
 
-#[derive(Debug, Clone, Copy)]
+/// Implements `Display` and `FromStr` with the variant names,
+/// which unlike `Debug` are kept stable so they can be stored in config files.
+macro_rules! impl_names {
+    ($name:ident { $($variant:ident),* $(,)? }) => {
+        impl $name {
+            /// Every variant, in the order they are declared.
+            pub const ALL: &'static [Self] = &[$(Self::$variant),*];
+        }
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(match self {
+                    $(Self::$variant => stringify!($variant),)*
+                })
+            }
+        }
+        impl FromStr for $name {
+            type Err = ParseKeyError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $(stringify!($variant) => Ok(Self::$variant),)*
+                    _ => Err(ParseKeyError(s.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+/// Returned when parsing a [`KeyCode`] or [`Key`] from an unknown name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyError(String);
+impl fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key name: {:?}", self.0)
+    }
+}
+impl std::error::Error for ParseKeyError {}
+
+/// A key by its position on the keyboard, named after the US layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum KeyCode {
     Tab,
     LeftArrow,
@@ -90,6 +131,18 @@ pub enum KeyCode {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
     Quote,
     Comma,
     Minus,
@@ -106,15 +159,325 @@ pub enum KeyCode {
     NumLock,
     PrintScreen,
     Pause,
+    /// The `<>` key left of Z on ISO keyboards.
+    IntlBackslash,
+    /// The `\ろ` key right of the slash on Japanese keyboards.
+    IntlRo,
+    /// The `¥` key left of backspace on Japanese keyboards.
+    IntlYen,
+    MediaPlayPause,
+    MediaStop,
+    MediaNextTrack,
+    MediaPrevTrack,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
 }
-#[derive(Debug, Clone, Copy)]
+impl_names!(KeyCode {
+    Tab,
+    LeftArrow,
+    RightArrow,
+    UpArrow,
+    DownArrow,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Insert,
+    Delete,
+    Backspace,
+    Space,
+    Enter,
+    Escape,
+    LCtrl,
+    LShift,
+    LAlt,
+    LSuper,
+    RCtrl,
+    RShift,
+    RAlt,
+    RSuper,
+    Menu,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    Numpad0,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadMultiply,
+    NumpadSubtract,
+    NumpadAdd,
+    NumpadEnter,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    Quote,
+    Comma,
+    Minus,
+    Period,
+    Slash,
+    Backslash,
+    Semicolon,
+    Equal,
+    LBracket,
+    RBracket,
+    Grave,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    MediaPlayPause,
+    MediaStop,
+    MediaNextTrack,
+    MediaPrevTrack,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PhysicalKey {
     KeyCode(KeyCode),
-    /// OS scancode
+    /// Scancode, numbered like linux evdev key codes on every platform.
     Unknown(u32),
 }
+impl PhysicalKey {
+    /// Maps a linux evdev key code, other platforms convert their scancodes to these first.
+    pub(crate) fn from_evdev(code: u32) -> Self {
+        Self::KeyCode(match code {
+            15 => KeyCode::Tab,
+            105 => KeyCode::LeftArrow,
+            106 => KeyCode::RightArrow,
+            103 => KeyCode::UpArrow,
+            108 => KeyCode::DownArrow,
+            104 => KeyCode::PageUp,
+            109 => KeyCode::PageDown,
+            102 => KeyCode::Home,
+            107 => KeyCode::End,
+            110 => KeyCode::Insert,
+            111 => KeyCode::Delete,
+            14 => KeyCode::Backspace,
+            57 => KeyCode::Space,
+            28 => KeyCode::Enter,
+            1 => KeyCode::Escape,
+            29 => KeyCode::LCtrl,
+            42 => KeyCode::LShift,
+            56 => KeyCode::LAlt,
+            125 => KeyCode::LSuper,
+            97 => KeyCode::RCtrl,
+            54 => KeyCode::RShift,
+            100 => KeyCode::RAlt,
+            126 => KeyCode::RSuper,
+            127 => KeyCode::Menu,
+            2 => KeyCode::Key1,
+            3 => KeyCode::Key2,
+            4 => KeyCode::Key3,
+            5 => KeyCode::Key4,
+            6 => KeyCode::Key5,
+            7 => KeyCode::Key6,
+            8 => KeyCode::Key7,
+            9 => KeyCode::Key8,
+            10 => KeyCode::Key9,
+            11 => KeyCode::Key0,
+            79 => KeyCode::Numpad1,
+            80 => KeyCode::Numpad2,
+            81 => KeyCode::Numpad3,
+            75 => KeyCode::Numpad4,
+            76 => KeyCode::Numpad5,
+            77 => KeyCode::Numpad6,
+            71 => KeyCode::Numpad7,
+            72 => KeyCode::Numpad8,
+            73 => KeyCode::Numpad9,
+            82 => KeyCode::Numpad0,
+            83 => KeyCode::NumpadDecimal,
+            98 => KeyCode::NumpadDivide,
+            55 => KeyCode::NumpadMultiply,
+            74 => KeyCode::NumpadSubtract,
+            78 => KeyCode::NumpadAdd,
+            96 => KeyCode::NumpadEnter,
+            30 => KeyCode::A,
+            48 => KeyCode::B,
+            46 => KeyCode::C,
+            32 => KeyCode::D,
+            18 => KeyCode::E,
+            33 => KeyCode::F,
+            34 => KeyCode::G,
+            35 => KeyCode::H,
+            23 => KeyCode::I,
+            36 => KeyCode::J,
+            37 => KeyCode::K,
+            38 => KeyCode::L,
+            50 => KeyCode::M,
+            49 => KeyCode::N,
+            24 => KeyCode::O,
+            25 => KeyCode::P,
+            16 => KeyCode::Q,
+            19 => KeyCode::R,
+            31 => KeyCode::S,
+            20 => KeyCode::T,
+            22 => KeyCode::U,
+            47 => KeyCode::V,
+            17 => KeyCode::W,
+            45 => KeyCode::X,
+            21 => KeyCode::Y,
+            44 => KeyCode::Z,
+            59 => KeyCode::F1,
+            60 => KeyCode::F2,
+            61 => KeyCode::F3,
+            62 => KeyCode::F4,
+            63 => KeyCode::F5,
+            64 => KeyCode::F6,
+            65 => KeyCode::F7,
+            66 => KeyCode::F8,
+            67 => KeyCode::F9,
+            68 => KeyCode::F10,
+            87 => KeyCode::F11,
+            88 => KeyCode::F12,
+            183 => KeyCode::F13,
+            184 => KeyCode::F14,
+            185 => KeyCode::F15,
+            186 => KeyCode::F16,
+            187 => KeyCode::F17,
+            188 => KeyCode::F18,
+            189 => KeyCode::F19,
+            190 => KeyCode::F20,
+            191 => KeyCode::F21,
+            192 => KeyCode::F22,
+            193 => KeyCode::F23,
+            194 => KeyCode::F24,
+
+            39 => KeyCode::Semicolon,
+            40 => KeyCode::Quote,
+
+            51 => KeyCode::Comma,
+            52 => KeyCode::Period,
+            53 => KeyCode::Slash,
+
+            12 => KeyCode::Minus,
+            13 => KeyCode::Equal,
+
+            26 => KeyCode::LBracket,
+            27 => KeyCode::RBracket,
+            43 => KeyCode::Backslash,
+
+            41 => KeyCode::Grave,
+            58 => KeyCode::CapsLock,
+            70 => KeyCode::ScrollLock,
+            69 => KeyCode::NumLock,
+
+            99 => KeyCode::PrintScreen,
+            119 => KeyCode::Pause,
+
+            86 => KeyCode::IntlBackslash,
+            89 => KeyCode::IntlRo,
+            124 => KeyCode::IntlYen,
+
+            164 => KeyCode::MediaPlayPause,
+            166 => KeyCode::MediaStop,
+            163 => KeyCode::MediaNextTrack,
+            165 => KeyCode::MediaPrevTrack,
+            115 => KeyCode::VolumeUp,
+            114 => KeyCode::VolumeDown,
+            113 => KeyCode::VolumeMute,
+
+            158 => KeyCode::BrowserBack,
+            159 => KeyCode::BrowserForward,
+            173 => KeyCode::BrowserRefresh,
+            128 => KeyCode::BrowserStop,
+            217 => KeyCode::BrowserSearch,
+            156 => KeyCode::BrowserFavorites,
+            172 => KeyCode::BrowserHome,
+
+            _ => return Self::Unknown(code),
+        })
+    }
+}
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Key {
     Tab,
     Enter,
@@ -220,8 +583,157 @@ pub enum Key {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+
+    /// Usually the play/pause key.
+    MediaPlayPause,
+    MediaPause,
+    MediaStop,
+    MediaNextTrack,
+    MediaPrevTrack,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
 }
-#[derive(Debug, Clone)]
+impl_names!(Key {
+    Tab,
+    Enter,
+    Space,
+    Period,
+    Shift,
+    LShift,
+    RShift,
+    Ctrl,
+    LCtrl,
+    RCtrl,
+    Super,
+    LSuper,
+    RSuper,
+    Alt,
+    LAlt,
+    RAlt,
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    PrintScreen,
+    Backspace,
+    Escape,
+    Pause,
+    Menu,
+    PageUp,
+    PageDown,
+    End,
+    Home,
+    Delete,
+    Insert,
+    LeftArrow,
+    RightArrow,
+    UpArrow,
+    DownArrow,
+    Plus,
+    Minus,
+    Asterisk,
+    Slash,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadLeftArrow,
+    NumpadRightArrow,
+    NumpadUpArrow,
+    NumpadDownArrow,
+    NumpadPageUp,
+    NumpadPageDown,
+    NumpadEnd,
+    NumpadHome,
+    NumpadDelete,
+    NumpadInsert,
+    NumpadBegin,
+    NumpadEnter,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    Numpad0,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    MediaPlayPause,
+    MediaPause,
+    MediaStop,
+    MediaNextTrack,
+    MediaPrevTrack,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
+});
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum LogicalKey<Str = SmolStr> {
     Key(Key),
     Character(Str),
@@ -238,12 +750,105 @@ impl LogicalKey<SmolStr> {
     }
 }
 
-// bitflags::bitflags! {
-//     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-//     pub struct KeyModifiers: u8 {
-//         const SHIFT = 1 << 0;
-//         const CTRL = 1 << 1;
-//         const ALT = 1 << 2;
-//         const SUPER = 1 << 3;
-//     }
-// }
+/// Tells apart keys that exist more than once on a keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyLocation {
+    #[default]
+    Standard,
+    Left,
+    Right,
+    Numpad,
+}
+
+/// Which lock keys are on, as shown by the keyboard's LEDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LockState {
+    pub caps: bool,
+    pub num: bool,
+    pub scroll: bool,
+}
+
+bitflags::bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct KeyModifiers: u8 {
+        const SHIFT = 1 << 0;
+        const CTRL = 1 << 1;
+        const ALT = 1 << 2;
+        const SUPER = 1 << 3;
+        const CAPS_LOCK = 1 << 4;
+        const NUM_LOCK = 1 << 5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // `ALL` comes from the same list as the names, which can't miss a variant
+    // since `Display` matches on every one of them
+    #[test]
+    fn key_code_names_round_trip() {
+        for &code in KeyCode::ALL {
+            assert_eq!(code.to_string().parse(), Ok(code));
+        }
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for &key in Key::ALL {
+            assert_eq!(key.to_string().parse(), Ok(key));
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Action {
+        Save,
+        Quit,
+    }
+
+    #[test]
+    fn key_code_bindings() {
+        let bindings = HashMap::from([(KeyCode::S, Action::Save), (KeyCode::Q, Action::Quit)]);
+        assert_eq!(bindings.get(&KeyCode::S), Some(&Action::Save));
+        assert_eq!(bindings.get(&KeyCode::W), None);
+    }
+
+    #[test]
+    fn logical_key_bindings() {
+        let bindings: HashMap<LogicalKey, Action> = HashMap::from([
+            (LogicalKey::Character(SmolStr::new("s")), Action::Save),
+            (LogicalKey::Key(Key::Escape), Action::Quit),
+        ]);
+        // built separately, like the key of an event would be
+        let typed = LogicalKey::Character(SmolStr::from(String::from("s")));
+        assert_eq!(bindings.get(&typed), Some(&Action::Save));
+        assert_eq!(
+            bindings.get(&LogicalKey::Key(Key::Escape)),
+            Some(&Action::Quit)
+        );
+        assert_eq!(
+            bindings.get(&LogicalKey::Character(SmolStr::new("S"))),
+            None
+        );
+
+        let borrowed: HashMap<LogicalKey<&str>, Action> =
+            HashMap::from([(typed.as_ref(), Action::Save)]);
+        assert_eq!(
+            borrowed.get(&LogicalKey::Character("s")),
+            Some(&Action::Save)
+        );
+    }
+
+    #[test]
+    fn unknown_key_name() {
+        assert_eq!(
+            "Keya".parse::<KeyCode>(),
+            Err(ParseKeyError("Keya".to_owned()))
+        );
+        assert!("".parse::<Key>().is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::{MonitorId, Rect, SeatId, Theme, WindowId};
 use smol_str::SmolStr;
+use std::{any::Any, path::PathBuf, time::Duration};
 
 mod keyboard;
 pub use keyboard::*;
@@ -6,49 +8,221 @@ pub use keyboard::*;
 mod pointer;
 pub use pointer::*;
 
+mod ime;
+pub use ime::*;
+
+mod touch;
+pub use touch::*;
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum WindowEvent {
-    Paint,
-    Close,
-    Resized,
-    NewScaleFactor,
+    Paint {
+        /// The part of the window to redraw, `None` for all of it.
+        /// Several requests are combined into the rect containing all of them.
+        damage: Option<Rect>,
+    },
+    /// The frame drawn for a `Paint` was shown on screen.
+    ///
+    /// Sent on wayland if the compositor supports `wp_presentation`.
+    /// Never sent on windows, which can't tell when the app's frame was shown.
+    PresentationFeedback {
+        /// When the frame turned visible, since an unspecified point in time.
+        presented_at: Duration,
+        /// Time between refreshes, zero if it varies.
+        refresh: Duration,
+        /// Counts refreshes, zero if the display doesn't.
+        seq: u64,
+    },
+    /// The user asked to close the window, e.g. with the close button.
+    /// Nothing happens unless the app drops the window.
+    CloseRequested,
+    /// The window was dropped and is gone, nothing else is sent for it after this.
+    ///
+    /// Can arrive a loop iteration after the drop.
+    Destroyed,
+    /// The new size in physical pixels, same as [`Window::get_physical_size`](crate::Window::get_physical_size).
+    Resized {
+        width: u32,
+        height: u32,
+    },
+    /// The new top-left of the window's content in physical screen pixels.
+    ///
+    /// Wayland doesn't expose window positions, so this is never sent there.
+    Moved(i32, i32),
+    /// The window moved to a monitor with a different scale or the monitor's scale changed,
+    /// same as [`Window::get_scale`](crate::Window::get_scale).
+    ScaleFactorChanged {
+        scale: f64,
+        /// The physical size the window is getting at the new scale.
+        /// A `Resized` follows if it differs from the current size.
+        suggested_size: (u32, u32),
+    },
+    /// The monitor the window is mostly on changed, see [`Window::current_monitor`](crate::Window::current_monitor).
+    ///
+    /// Wayland doesn't say how much of a window is on each monitor,
+    /// so there it's the earliest entered monitor the window is still on.
+    MonitorChanged(MonitorId),
     Focus(bool),
+    /// The window can't be seen, e.g. it's fully covered, minimized or on another workspace.
+    /// Rendering can pause until it's `false` again.
+    ///
+    /// On Windows minimizing and cloaking count, e.g. being on another virtual desktop,
+    /// but being covered by other windows doesn't.
+    Occluded(bool),
+    /// Either the system's theme changed or [`Window::set_theme`](crate::Window::set_theme) was called.
+    ///
+    /// Only sent on windows.
+    ThemeChanged(Theme),
     Key {
         down: bool,
+        /// Sent by holding the key down rather than a fresh press.
+        /// Always `false` for releases.
+        repeat: bool,
         physical_key: PhysicalKey,
         logical_key: LogicalKey,
+        /// Empty for dead keys, the key finishing the sequence has the composed text.
         text: SmolStr,
         text_raw: SmolStr,
         logical_key_unmodified: LogicalKey,
+        location: KeyLocation,
+        /// See [`WindowEvent::PointerMoved::timestamp`].
+        timestamp: Duration,
+        seat: SeatId,
     },
     PointerEntered,
     PointerLeft,
-    /// In logical pixels.
-    PointerMoved(f64, f64),
+    /// Only the latest position is sent when the pointer moves several times at once.
+    PointerMoved {
+        /// In logical pixels.
+        x: f64,
+        y: f64,
+        /// When the input happened, in milliseconds since an unspecified point in time.
+        /// Only useful for comparing it with other input timestamps, it wraps around after ~49 days.
+        timestamp: Duration,
+        /// Each seat has its own pointer, they move independently.
+        seat: SeatId,
+    },
     PointerButton {
         down: bool,
         button: PointerButton,
+        /// How many presses of this button in quick succession this is part of,
+        /// `1` for a single click, `2` for a double click and so on.
+        /// Moving too far between presses starts from `1` again.
+        /// Releases have the count of the press they end.
+        click_count: u32,
+        /// See [`WindowEvent::PointerMoved::timestamp`].
+        timestamp: Duration,
+        seat: SeatId,
+    },
+    /// `id` identifies the contact on its `seat` until it ends. In logical pixels.
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f64,
+        y: f64,
+        seat: SeatId,
     },
+    /// Everything scrolled during one pointer frame.
+    ///
+    /// A vertical wheel scrolls horizontally while shift is held, up becoming left,
+    /// so apps don't need to handle that themselves.
     Scroll {
-        direction: ScrollDirection,
-        value: f64,
+        delta: ScrollDelta,
+        /// `None` if the platform didn't say.
+        source: Option<ScrollSource>,
+        /// The scroll stopped, e.g. the fingers were lifted off the touchpad.
+        /// This is the moment to start kinetic scrolling.
+        ///
+        /// A stop carries no motion, its delta is zero and its `source`
+        /// is the one of the scrolling it ends.
+        /// Windows doesn't report lifting the fingers,
+        /// there it's sent once touchpad scrolling pauses for a moment.
+        stop: bool,
+        /// See [`WindowEvent::PointerMoved::timestamp`].
+        timestamp: Duration,
+        seat: SeatId,
+    },
+    /// A touchpad pinch.
+    ///
+    /// Windows reports pinches as zoom gestures, they never rotate there.
+    PinchGesture {
+        /// Movement of the center of the fingers since the last update, in logical pixels.
+        delta: (f64, f64),
+        /// Relative to the fingers' distance at `Begin`.
+        scale: f64,
+        /// Since the last update, in degrees clockwise.
+        rotation: f64,
+        phase: GesturePhase,
+        seat: SeatId,
     },
-    // KeyModifiers(KeyModifiers),
+    /// A touchpad swipe.
+    /// Touchscreens always send [`WindowEvent::Touch`] instead.
+    ///
+    /// Windows reports two finger pan gestures, swipes with more fingers aren't sent there.
+    SwipeGesture {
+        /// Movement since the last update, in logical pixels.
+        dx: f64,
+        dy: f64,
+        fingers: u32,
+        phase: GesturePhase,
+        seat: SeatId,
+    },
+    /// The pointer grab was changed by waywin itself rather than by the app,
+    /// see [`Window::set_grab_escape_key`](crate::Window::set_grab_escape_key).
+    CursorGrabChanged(bool),
+    ModifiersChanged(KeyModifiers),
+    /// Sent to the focused window, including once when it gains focus.
+    LockStateChanged(LockState),
+    /// Sent to the focused window, including once for the initial layout.
+    ///
+    /// The name is human readable on wayland, like `English (US)`,
+    /// but a layout id on windows, like `00000409`.
+    KeyboardLayoutChanged {
+        name: String,
+    },
+    Ime(Ime),
+    /// A file is dragged over the window. Sent once per file.
+    HoveredFile(PathBuf),
+    /// The files from `HoveredFile` were dragged away without dropping them.
+    HoveredFileCancelled,
+    /// A file was dropped onto the window. Sent once per file.
+    DroppedFile(PathBuf),
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum DeviceEvent {
+    /// Relative motion, also sent while the pointer is locked.
+    ///
+    /// Windows doesn't report accelerated motion so both are the raw delta there.
     PointerMoved {
         delta: (f64, f64),
         delta_unaccel: (f64, f64),
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum WaywinEvent {
     WindowEvent {
         event: WindowEvent,
-        window_id: usize,
+        window_id: WindowId,
     },
     DeviceEvent(DeviceEvent),
+    /// Sent through an [`EventLoopProxy`](crate::EventLoopProxy).
+    /// Downcast it to the proxy's type.
+    User(Box<dyn Any + Send>),
+    /// All events of the current loop iteration have been delivered
+    /// and the loop is about to wait according to the [`ControlFlow`](crate::ControlFlow).
+    AboutToWait,
+    /// The connection to the display server is gone, e.g. because the compositor crashed.
+    /// This is the last event, `run` returns right after it.
+    ///
+    /// Windows and other handles of this `Waywin` are useless from then on,
+    /// reconnecting means initializing a new one.
+    /// Only sent on wayland.
+    Disconnected,
 }
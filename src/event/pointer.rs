@@ -1,23 +1,169 @@
-#[derive(Debug, Clone, Copy)]
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum PointerButton {
     Left,
     Right,
     Middle,
+    /// The front thumb button. `BTN_EXTRA` on linux and `XBUTTON2` on windows,
+    /// as well as `BTN_FORWARD` which some mice use instead.
     Forward,
+    /// The rear thumb button. `BTN_SIDE` on linux and `XBUTTON1` on windows,
+    /// as well as `BTN_BACK` which some mice use instead.
     Back,
-    Unknown(u32),
+    /// Any other button, by its linux evdev code (`BTN_*`) on every platform.
+    Other(u16),
+}
+impl PointerButton {
+    /// Maps a linux evdev button code.
+    #[cfg(any(target_os = "linux", test))]
+    pub(crate) fn from_evdev(code: u32) -> Self {
+        // thumb buttons are usually side and extra, some mice use forward and back
+        match code {
+            0x110 => Self::Left,
+            0x111 => Self::Right,
+            0x112 => Self::Middle,
+            0x114 | 0x115 => Self::Forward,
+            0x113 | 0x116 => Self::Back,
+            _ => Self::Other(code as u16),
+        }
+    }
+    /// Maps a windows x button, `XBUTTON1` being 1.
+    #[cfg(any(target_os = "windows", test))]
+    pub(crate) fn from_xbutton(xbutton: u16) -> Self {
+        match xbutton {
+            1 => Self::Back,
+            2 => Self::Forward,
+            // numbered on from `BTN_SIDE` and `BTN_EXTRA`, which the first two are
+            _ => Self::Other(0x112u16.saturating_add(xbutton)),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum ScrollDirection {
-    Vertical,
-    Horizontal,
+/// Counts presses of the same button in quick succession, for double and triple clicks.
+#[derive(Default)]
+pub(crate) struct ClickCounter {
+    /// Button, timestamp and logical position of the latest press.
+    last: Option<(PointerButton, Duration, (f64, f64))>,
+    count: u32,
 }
-impl ScrollDirection {
-    pub fn is_vertical(&self) -> bool {
-        matches!(self, Self::Vertical)
+impl ClickCounter {
+    /// `interval` is the most time and `distance` the most logical pixels
+    /// on either axis allowed since the previous press for it to count.
+    pub fn press(
+        &mut self,
+        button: PointerButton,
+        timestamp: Duration,
+        position: (f64, f64),
+        interval: Duration,
+        distance: (f64, f64),
+    ) -> u32 {
+        let repeated = self
+            .last
+            .is_some_and(|(last_button, last_time, last_position)| {
+                last_button == button
+                    && timestamp.saturating_sub(last_time) <= interval
+                    && (position.0 - last_position.0).abs() <= distance.0
+                    && (position.1 - last_position.1).abs() <= distance.1
+            });
+        self.count = if repeated { self.count + 1 } else { 1 };
+        self.last = Some((button, timestamp, position));
+        self.count
+    }
+    /// The count of the press that `button` being released ends.
+    pub fn release(&self, button: PointerButton) -> u32 {
+        match self.last {
+            Some((last_button, ..)) if last_button == button => self.count,
+            _ => 1,
+        }
+    }
+    /// Starts counting from the next press again, e.g. when the pointer leaves.
+    pub fn reset(&mut self) {
+        self.last = None;
     }
-    pub fn is_horizontal(&self) -> bool {
-        matches!(self, Self::Horizontal)
+}
+
+/// How far to scroll, as `(x, y)`.
+/// Positive values scroll left and up, the content moves right and down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ScrollDelta {
+    /// Wheel notches, one per detent. High resolution wheels can scroll fractions of one.
+    /// How many lines a notch scrolls is up to the app,
+    /// see [`Waywin::wheel_scroll_lines`](crate::Waywin::wheel_scroll_lines).
+    Lines(f64, f64),
+    /// Logical pixels from smooth scrolling, e.g. on a touchpad.
+    Pixels(f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ScrollSource {
+    /// A mouse wheel, scrolling in notches.
+    Wheel,
+    /// Fingers on a touchpad or touchscreen.
+    Finger,
+    /// Continuous motion without a clear end, e.g. button based scrolling.
+    Continuous,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum GesturePhase {
+    Begin,
+    Update,
+    End,
+    /// The gesture was interrupted, its effect should be undone.
+    Cancel,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn thumb_buttons_agree() {
+        // `BTN_SIDE` and `XBUTTON1`
+        assert_eq!(PointerButton::from_evdev(0x113), PointerButton::Back);
+        assert_eq!(PointerButton::from_xbutton(1), PointerButton::Back);
+        // `BTN_EXTRA` and `XBUTTON2`
+        assert_eq!(PointerButton::from_evdev(0x114), PointerButton::Forward);
+        assert_eq!(PointerButton::from_xbutton(2), PointerButton::Forward);
+        // `BTN_FORWARD` and `BTN_BACK`
+        assert_eq!(PointerButton::from_evdev(0x115), PointerButton::Forward);
+        assert_eq!(PointerButton::from_evdev(0x116), PointerButton::Back);
+    }
+
+    #[test]
+    fn other_buttons_are_numbered_alike() {
+        assert_eq!(
+            PointerButton::from_evdev(0x117),
+            PointerButton::Other(0x117)
+        );
+        assert_eq!(PointerButton::from_xbutton(5), PointerButton::Other(0x117));
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn xbutton_numbers() {
+        use windows::Win32::UI::WindowsAndMessaging::{XBUTTON1, XBUTTON2};
+        assert_eq!((XBUTTON1, XBUTTON2), (1, 2));
+    }
+
+    #[test]
+    fn pointer_button_bindings() {
+        let bindings = HashMap::from([
+            (PointerButton::Back, "back"),
+            (PointerButton::Other(0x117), "task"),
+        ]);
+        assert_eq!(bindings.get(&PointerButton::Back), Some(&"back"));
+        assert_eq!(bindings.get(&PointerButton::Other(0x117)), Some(&"task"));
+        assert_eq!(bindings.get(&PointerButton::Other(0x118)), None);
     }
 }
@@ -0,0 +1,10 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The touch was taken over by the system, e.g. for a gesture.
+    Cancelled,
+}
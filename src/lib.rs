@@ -3,21 +3,34 @@ compile_error!("waywin only supports 64-bit targets");
 #[cfg(not(any(target_os = "linux", target_os = "windows")))]
 compile_error!("waywin only supports Linux and Windows");
 
-use crate::event::WaywinEvent;
+use crate::event::{WaywinEvent, WindowEvent};
 use raw_window_handle as rwh;
-use std::marker::PhantomData;
+use std::{
+    any::Any,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
 pub mod event;
 
+mod error;
+pub use error::Error;
+
 #[cfg(target_os = "windows")]
 mod windows_impl;
 #[cfg(target_os = "windows")]
 use windows_impl as backend_impl;
+#[cfg(target_os = "windows")]
+pub use windows_impl::RawMessage;
 
 #[cfg(target_os = "linux")]
 mod wayland_impl;
 #[cfg(target_os = "linux")]
 use wayland_impl as backend_impl;
+// for tests/event_allocations.rs
+#[cfg(target_os = "linux")]
+#[doc(hidden)]
+pub use wayland_impl::take_events;
 
 /// Used to create windows and run the event runner.
 pub struct Waywin {
@@ -25,24 +38,610 @@ pub struct Waywin {
     _marker: PhantomData<*const ()>, // not `Send` or `Sync`
 }
 impl Waywin {
-    pub fn init(class_name: &str) -> Result<Self, String> {
+    pub fn init(class_name: &str) -> Result<Self, Error> {
         backend_impl::Waywin::init(class_name).map(|backend_impl| Self {
             backend_impl,
             _marker: PhantomData,
         })
     }
-    pub fn create_window(&mut self, title: &str) -> Result<Window, String> {
-        backend_impl::Window::new(&mut self.backend_impl, title)
-            .map(|backend_impl| Window { backend_impl })
+    /// Shares an existing wayland connection instead of opening a new one,
+    /// so waywin can live alongside other wayland code.
+    ///
+    /// Only available on linux.
+    #[cfg(target_os = "linux")]
+    pub fn init_with_connection(
+        class_name: &str,
+        connection: wayland_client::Connection,
+    ) -> Result<Self, Error> {
+        backend_impl::Waywin::init_with_connection(class_name, connection).map(|backend_impl| {
+            Self {
+                backend_impl,
+                _marker: PhantomData,
+            }
+        })
+    }
+    /// Creates a handle that can send [`WaywinEvent::User`] events from other threads.
+    pub fn create_proxy<T: Send + 'static>(&self) -> EventLoopProxy<T> {
+        EventLoopProxy {
+            backend_impl: self.backend_impl.create_proxy(),
+            _marker: PhantomData,
+        }
+    }
+    /// All currently connected monitors.
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.backend_impl
+            .monitors()
+            .into_iter()
+            .map(|backend_impl| Monitor { backend_impl })
+            .collect()
+    }
+    /// How many lines the user wants a wheel notch to scroll, `None` for a whole page.
+    ///
+    /// Wayland has no such setting, there it's always 3.
+    pub fn wheel_scroll_lines(&self) -> Option<u32> {
+        self.backend_impl.wheel_scroll_lines()
+    }
+    /// A handle to the system clipboard.
+    pub fn clipboard(&self) -> Clipboard {
+        Clipboard {
+            backend_impl: self.backend_impl.clipboard(),
+        }
     }
-    pub fn run(mut self, event_hook: impl FnMut(WaywinEvent, &mut bool) + 'static) {
+    /// Calls `callback` every `interval` on the event loop thread.
+    /// Events pushed by the callback are delivered like any other.
+    pub fn add_timer(
+        &self,
+        interval: Duration,
+        callback: impl FnMut(&mut Vec<WaywinEvent>) + 'static,
+    ) -> Result<TimerId, Error> {
+        self.backend_impl.add_timer(interval, callback).map(TimerId)
+    }
+    pub fn remove_timer(&self, timer: TimerId) {
+        self.backend_impl.remove_timer(timer.0)
+    }
+    /// Registers a calloop event source, like an fd or a channel, on the event loop.
+    /// Events pushed by the callback are delivered like any other.
+    ///
+    /// Only available on linux.
+    #[cfg(target_os = "linux")]
+    pub fn insert_source<S: calloop::EventSource + 'static>(
+        &self,
+        source: S,
+        callback: impl FnMut(S::Event, &mut S::Metadata, &mut Vec<WaywinEvent>) -> S::Ret + 'static,
+    ) -> Result<calloop::RegistrationToken, Error> {
+        self.backend_impl.insert_source(source, callback)
+    }
+    /// Only available on linux.
+    #[cfg(target_os = "linux")]
+    pub fn remove_source(&self, token: calloop::RegistrationToken) {
+        self.backend_impl.remove_source(token)
+    }
+    /// Sends all requests made so far to the compositor without waiting for `run` to do it.
+    ///
+    /// A no-op on windows.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.backend_impl.flush()
+    }
+    /// Blocks until the compositor has handled all requests made so far.
+    /// Events it sent in the meantime are delivered by `run` as usual.
+    ///
+    /// A no-op on windows.
+    pub fn roundtrip(&self) -> Result<(), Error> {
+        self.backend_impl.roundtrip()
+    }
+    /// The `wl_display` pointer of the connection, for C libraries that need it.
+    /// Only valid as long as this `Waywin` is alive.
+    ///
+    /// Only available on linux.
+    #[cfg(target_os = "linux")]
+    pub fn wayland_display_ptr(&self) -> *mut std::ffi::c_void {
+        self.backend_impl.display_ptr()
+    }
+    /// Overrides the system's key repeat settings, `None` turns repeats off entirely.
+    ///
+    /// Windows repeats keys by itself so there only turning them off has an effect.
+    pub fn set_key_repeat(&mut self, repeat: Option<RepeatInfo>) {
+        self.backend_impl.set_key_repeat(repeat)
+    }
+    pub fn create_window(&mut self, title: &str) -> Result<Window, Error> {
+        WindowBuilder::new(title).build(self)
+    }
+    /// A handle for creating windows while [`Waywin::run`] is running, e.g. from an event hook.
+    pub fn window_creator(&self) -> WindowCreator {
+        WindowCreator {
+            backend_impl: self.backend_impl.window_creator(),
+            _marker: PhantomData,
+        }
+    }
+    /// Called for every message a window receives, before waywin handles it.
+    /// Returning `true` consumes the message so waywin never sees it.
+    ///
+    /// Only available on windows. Wayland events are dispatched per protocol object,
+    /// so there is no single stream of raw events to hook into.
+    #[cfg(target_os = "windows")]
+    pub fn set_raw_message_hook(&mut self, hook: impl FnMut(RawMessage) -> bool + 'static) {
+        self.backend_impl.set_raw_message_hook(hook)
+    }
+    /// Runs the event loop, passing every event to `event_hook`.
+    /// The [`ControlFlow`] decides how the loop waits after [`WaywinEvent::AboutToWait`].
+    pub fn run(mut self, event_hook: impl FnMut(WaywinEvent, &mut ControlFlow) + 'static) {
         self.backend_impl.run(event_hook)
     }
+    /// Like [`Waywin::run`] but hands the events over in batches,
+    /// e.g. to handle all pointer motion since the last frame at once.
+    ///
+    /// A batch ends with [`WaywinEvent::AboutToWait`], [`WaywinEvent::Disconnected`]
+    /// or a [`WindowEvent::Paint`], so a window is drawn after everything before it was handled.
+    /// That also keeps windows drawing while Windows runs its own loop, e.g. during a resize.
+    pub fn run_batched(
+        self,
+        mut event_hook: impl FnMut(&[WaywinEvent], &mut ControlFlow) + 'static,
+    ) {
+        let mut batch = Vec::new();
+        self.run(move |event, control_flow| {
+            let last = matches!(
+                event,
+                WaywinEvent::AboutToWait
+                    | WaywinEvent::Disconnected
+                    | WaywinEvent::WindowEvent {
+                        event: WindowEvent::Paint { .. },
+                        ..
+                    }
+            );
+            batch.push(event);
+            if last {
+                event_hook(&batch, control_flow);
+                // the capacity is kept for the next batch
+                batch.clear();
+            }
+        })
+    }
+}
+
+/// Creates windows without needing the [`Waywin`], which [`Waywin::run`] holds on to.
+/// Only usable on the thread of the event loop.
+#[derive(Clone)]
+pub struct WindowCreator {
+    backend_impl: backend_impl::WindowCreator,
+    _marker: PhantomData<*const ()>, // not `Send` or `Sync`
+}
+impl WindowCreator {
+    pub fn create_window(&self, title: &str) -> Result<Window, Error> {
+        WindowBuilder::new(title).build_with(self)
+    }
+}
+
+/// Wakes up the event loop and delivers a [`WaywinEvent::User`] event.
+pub struct EventLoopProxy<T> {
+    backend_impl: backend_impl::EventLoopProxy,
+    _marker: PhantomData<fn(T)>,
+}
+impl<T: Send + 'static> EventLoopProxy<T> {
+    /// Returns the event back if the event loop no longer exists.
+    pub fn send_event(&self, event: T) -> Result<(), T> {
+        self.backend_impl
+            .send_event(Box::new(event))
+            .map_err(|event| *(event as Box<dyn Any>).downcast::<T>().unwrap())
+    }
+}
+impl<T> Clone for EventLoopProxy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            backend_impl: self.backend_impl.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Edges of a window.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct EdgeFlags: u8 {
+        const LEFT = 1 << 0;
+        const RIGHT = 1 << 1;
+        const TOP = 1 << 2;
+        const BOTTOM = 1 << 3;
+    }
+}
+
+/// How the event loop should wait for new events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlFlow {
+    /// Don't wait, start the next iteration right away.
+    Poll,
+    /// Wait until a new event arrives.
+    #[default]
+    Wait,
+    /// Wait until a new event arrives or the deadline is reached.
+    WaitUntil(Instant),
+    /// Exit the event loop. No more events are delivered.
+    Exit,
+}
+
+/// How held down keys repeat, see [`Waywin::set_key_repeat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepeatInfo {
+    /// Before the first repeat.
+    pub delay: Duration,
+    /// Between repeats.
+    pub repeat: Duration,
+}
+
+/// Identifies a timer added with [`Waywin::add_timer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerId(backend_impl::TimerId);
+
+/// Identifies a window in [`WaywinEvent::WindowEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowId(pub(crate) usize);
+
+/// Reads and writes text on the system clipboard.
+pub struct Clipboard {
+    backend_impl: backend_impl::Clipboard,
+}
+impl Clipboard {
+    /// `None` if the clipboard is empty or doesn't hold text.
+    ///
+    /// On wayland this blocks until the app that owns the clipboard has sent all of the text.
+    /// Events aren't dispatched in the meantime.
+    pub fn get_text(&self) -> Option<String> {
+        self.backend_impl.get_text()
+    }
+    /// On wayland the clipboard can only be set in response to input,
+    /// so this does nothing until a window got a key press, click or touch.
+    pub fn set_text(&self, text: &str) {
+        self.backend_impl.set_text(text)
+    }
+    /// The text selected last, pasted with a middle click.
+    /// Blocks like [`Clipboard::get_text`].
+    ///
+    /// Windows has no primary selection so this is always `None` there.
+    pub fn get_primary_text(&self) -> Option<String> {
+        self.backend_impl.get_primary_text()
+    }
+    /// Call this when the user selects text. Needs input like [`Clipboard::set_text`].
+    ///
+    /// Does nothing on windows.
+    pub fn set_primary_text(&self, text: &str) {
+        self.backend_impl.set_primary_text(text)
+    }
+}
+
+/// Identifies a monitor in [`WindowEvent::MonitorChanged`](event::WindowEvent::MonitorChanged).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorId(pub(crate) usize);
+
+/// Identifies the seat, a group of input devices used by one person, an input event came from.
+///
+/// Wayland can have several seats, e.g. one per user of a shared machine.
+/// Windows only has one, so all events there have the same id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeatId(pub(crate) u32);
+
+/// A snapshot of a monitor as it was when it was queried.
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    backend_impl: backend_impl::Monitor,
+}
+impl Monitor {
+    pub fn id(&self) -> MonitorId {
+        self.backend_impl.id()
+    }
+    /// The name the platform uses for the monitor, like `DP-1` or `\\.\DISPLAY1`.
+    pub fn name(&self) -> Option<String> {
+        self.backend_impl.name()
+    }
+    /// The top-left corner in the desktop's coordinate space.
+    pub fn position(&self) -> (i32, i32) {
+        self.backend_impl.position()
+    }
+    /// In physical pixels.
+    pub fn physical_size(&self) -> (u32, u32) {
+        self.backend_impl.physical_size()
+    }
+    /// Always a whole number on wayland, fractional scales are only known per window there.
+    pub fn scale(&self) -> f64 {
+        self.backend_impl.scale()
+    }
+    /// The modes the monitor supports, the one in use is marked [`VideoMode::is_current`].
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.backend_impl.video_modes()
+    }
+}
+
+/// A resolution and refresh rate a [`Monitor`] can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    pub(crate) size: (u32, u32),
+    pub(crate) bit_depth: Option<u16>,
+    pub(crate) refresh_rate_millihertz: u32,
+    pub(crate) current: bool,
+    pub(crate) monitor: MonitorId,
+}
+impl VideoMode {
+    /// In physical pixels.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+    /// Bits per pixel. Wayland doesn't report it so it's always `None` there.
+    pub fn bit_depth(&self) -> Option<u16> {
+        self.bit_depth
+    }
+    pub fn refresh_rate_millihertz(&self) -> u32 {
+        self.refresh_rate_millihertz
+    }
+    pub fn monitor_id(&self) -> MonitorId {
+        self.monitor
+    }
+    /// Whether the monitor is currently using this mode.
+    pub fn is_current(&self) -> bool {
+        self.current
+    }
+}
+
+/// See [`Window::set_fullscreen`].
+#[derive(Debug, Clone)]
+pub enum Fullscreen {
+    /// Covers a monitor without changing its mode.
+    /// `None` picks the monitor the window is on.
+    Borderless(Option<Monitor>),
+    /// Switches the mode's monitor to it for as long as the window is fullscreen.
+    ///
+    /// Only windows lets clients change modes, on wayland this is the same as
+    /// borderless fullscreen on the mode's monitor.
+    Exclusive(VideoMode),
+}
+
+/// An image for the pointer, see [`Window::set_custom_cursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    pub width: u32,
+    pub height: u32,
+    /// Row by row, 4 bytes per pixel with straight alpha.
+    pub rgba: Vec<u8>,
+    /// The pixel that points.
+    pub hotspot_x: u32,
+    pub hotspot_y: u32,
+}
+impl CursorImage {
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::Cursor("the image is empty".to_owned()));
+        }
+        let len = self.width as usize * self.height as usize * 4;
+        if self.rgba.len() != len {
+            return Err(Error::Cursor(format!(
+                "expected {len} bytes of rgba for a {}x{} image but got {}",
+                self.width,
+                self.height,
+                self.rgba.len()
+            )));
+        }
+        if self.hotspot_x >= self.width || self.hotspot_y >= self.height {
+            return Err(Error::Cursor(
+                "the hotspot is outside of the image".to_owned(),
+            ));
+        }
+        Ok(())
+    }
 }
 
+/// How a window holds on to the pointer, see [`Window::set_cursor_grab`].
+///
+/// Compositors only grab the pointer while it's over the window and windows only while focused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorGrabMode {
+    #[default]
+    None,
+    /// The pointer can't leave the window.
+    Confined,
+    /// The pointer is hidden and stays in place,
+    /// motion is only reported by [`DeviceEvent::PointerMoved`](event::DeviceEvent::PointerMoved).
+    Locked,
+}
+
+/// In logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+impl Rect {
+    /// The smallest rect containing both.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn union(self, other: Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+/// Light or dark, for window decorations and what the system prefers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+/// What a point of the window acts as, see [`Window::set_hittest_handler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HitTestResult {
+    /// Handled by the app like the rest of the window.
+    Client,
+    /// Moves the window like a title bar.
+    Caption,
+    /// Resizes the window from that edge or corner.
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Shown on a window's taskbar button.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Progress {
+    pub state: ProgressState,
+    /// From `0.0` to `1.0`, ignored while [`ProgressState::Indeterminate`].
+    pub value: f64,
+}
+
+/// Colors the taskbar progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProgressState {
+    Normal,
+    Paused,
+    Error,
+    /// Keeps moving without a known value.
+    Indeterminate,
+}
+
+/// How the corners of a window are rounded on Windows 11.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CornerPreference {
+    /// Whatever the system decides for the window.
+    #[default]
+    Default,
+    Round,
+    RoundSmall,
+    DoNotRound,
+}
+
+/// The material Windows 11 draws behind a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backdrop {
+    None,
+    /// For the main window of an app.
+    Mica,
+    /// For short lived windows like popups.
+    Acrylic,
+    /// For windows with tabs in the title bar.
+    Tabbed,
+}
+
+/// Window management actions the compositor supports,
+/// e.g. to leave out titlebar buttons that wouldn't do anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WmCapabilities {
+    pub maximize: bool,
+    pub minimize: bool,
+    pub fullscreen: bool,
+    /// Showing a menu of these actions, usually on a right click on the titlebar.
+    pub window_menu: bool,
+}
+impl WmCapabilities {
+    /// What's assumed until told otherwise.
+    pub(crate) const ALL: Self = Self {
+        maximize: true,
+        minimize: true,
+        fullscreen: true,
+        window_menu: true,
+    };
+}
+
+/// Used to configure a window before it is created.
+#[derive(Debug, Clone)]
+pub struct WindowBuilder {
+    pub(crate) title: String,
+    pub(crate) visible: bool,
+    pub(crate) transparent: bool,
+    pub(crate) fractional_scaling: bool,
+    pub(crate) parent: Option<backend_impl::WindowParent>,
+    pub(crate) modal: bool,
+}
+impl WindowBuilder {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_owned(),
+            visible: true,
+            transparent: false,
+            fractional_scaling: true,
+            parent: None,
+            modal: false,
+        }
+    }
+    /// Windows created with `false` stay hidden until [`Window::show`] is called.
+    /// This gives the app a chance to set up its renderer before anything appears on screen.
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+    /// Allows the alpha channel of the surface to show what's behind the window.
+    /// Opaque windows tell the compositor that their whole surface is opaque.
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+    /// With `false` the scale is always a whole number, for pixel perfect rendering.
+    /// The compositor scales the window up or down to fractional scales instead.
+    ///
+    /// Only has an effect on wayland.
+    pub fn with_fractional_scaling(mut self, fractional_scaling: bool) -> Self {
+        self.fractional_scaling = fractional_scaling;
+        self
+    }
+    /// Makes the window a dialog of `parent`, kept above it and minimized along with it.
+    /// On wayland popups can't be parents and are ignored.
+    pub fn with_parent(mut self, parent: &Window) -> Self {
+        self.parent = parent.backend_impl.as_parent();
+        self
+    }
+    /// Blocks input to the parent until the window is dropped, like a "Save changes?" dialog.
+    /// Only has an effect together with [`WindowBuilder::with_parent`].
+    ///
+    /// On wayland this is up to the compositor, which may not support it.
+    pub fn with_modal(mut self, modal: bool) -> Self {
+        self.modal = modal;
+        self
+    }
+    pub fn build(self, waywin: &mut Waywin) -> Result<Window, Error> {
+        backend_impl::Window::new(&mut waywin.backend_impl, &self)
+            .map(|backend_impl| Window { backend_impl })
+    }
+    /// Same as [`WindowBuilder::build`], usable while the event loop is running.
+    pub fn build_with(self, creator: &WindowCreator) -> Result<Window, Error> {
+        backend_impl::Window::from_context(&creator.backend_impl, &self)
+            .map(|backend_impl| Window { backend_impl })
+    }
+}
+
+/// Can be shared with a render thread while the event loop keeps running.
+///
+/// On Windows, methods that read or change waywin's own window state only work on the
+/// thread that created the window and log an error otherwise.
 pub struct Window {
     backend_impl: backend_impl::Window,
 }
+impl std::fmt::Debug for Window {
+    /// Shows the id and basic state, not the platform's handles.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.backend_impl.fmt(f)
+    }
+}
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Window>();
+};
 impl Window {
     pub fn get_physical_size(&self) -> (u32, u32) {
         self.backend_impl.get_physical_size()
@@ -50,42 +649,344 @@ impl Window {
     pub fn get_logical_size(&self) -> (f64, f64) {
         self.backend_impl.get_logical_size()
     }
+    /// The logical size as of the last [`WindowEvent::Resized`](event::WindowEvent::Resized).
+    ///
+    /// Unlike [`Window::get_logical_size`] this never races ahead of the event being processed.
+    pub fn size_at_last_event(&self) -> (f64, f64) {
+        self.backend_impl.size_at_last_event()
+    }
+    /// The modifiers as of the last [`WindowEvent::ModifiersChanged`](event::WindowEvent::ModifiersChanged),
+    /// so like [`Window::size_at_last_event`] it matches the event being processed.
+    pub fn modifiers(&self) -> event::KeyModifiers {
+        self.backend_impl.modifiers()
+    }
+    /// The lock keys as of the last [`WindowEvent::LockStateChanged`](event::WindowEvent::LockStateChanged).
+    pub fn lock_state(&self) -> event::LockState {
+        self.backend_impl.lock_state()
+    }
     pub fn get_scale(&self) -> f64 {
         self.backend_impl.get_scale()
     }
+    /// On Wayland the resulting paint is throttled to what the compositor can show,
+    /// so requesting another one from every paint drives an animation.
+    /// That needs [`Window::pre_present`] before every present, otherwise paints aren't held back.
     pub fn request_redraw(&self) {
         self.backend_impl.request_redraw()
     }
+    /// Like [`Window::request_redraw`] but only `rect` needs to be redrawn,
+    /// it's passed on in [`WindowEvent::Paint`](event::WindowEvent::Paint).
+    ///
+    /// On wayland the compositor is told about the damage with the commit
+    /// that follows [`Window::pre_present`].
+    pub fn request_redraw_region(&self, rect: Rect) {
+        self.backend_impl.request_redraw_region(rect)
+    }
+    /// Call right before presenting what was drawn for a `Paint`,
+    /// e.g. before queueing the swapchain image.
+    ///
+    /// On wayland it sends the paint's damage along with the commit, so only that part
+    /// of the window is updated on screen, and asks the compositor when it's ready
+    /// for the next frame. [`Window::present_buffer`] already does this.
+    /// Does nothing on windows.
+    pub fn pre_present(&self) {
+        self.backend_impl.pre_present()
+    }
+    /// Schedules a single redraw at or before `deadline`.
+    /// Multiple calls collapse to the earliest deadline.
+    pub fn request_redraw_at(&self, deadline: Instant) {
+        self.backend_impl.request_redraw_at(deadline)
+    }
+    /// In logical pixels. Kept consistent in physical pixels when the scale changes.
+    ///
+    /// On wayland this takes effect with the present that follows the next paint.
+    pub fn set_min_size(&self, size: Option<(f64, f64)>) {
+        self.backend_impl.set_min_size(size)
+    }
+    /// In logical pixels. Kept consistent in physical pixels when the scale changes.
+    ///
+    /// On wayland this takes effect with the present that follows the next paint.
+    pub fn set_max_size(&self, size: Option<(f64, f64)>) {
+        self.backend_impl.set_max_size(size)
+    }
+    /// In logical pixels. Interactive resizes snap to multiples of these,
+    /// e.g. the cell size of a terminal. Doesn't apply while maximized or fullscreen.
+    ///
+    /// On wayland this is best effort, there sizes the compositor asks for are rounded down
+    /// but the compositor stays in control of the window's geometry.
+    pub fn set_resize_increments(&self, increments: Option<(u32, u32)>) {
+        self.backend_impl.set_resize_increments(increments)
+    }
+    /// Width to height, e.g. `(16, 9)`, kept while the window is resized interactively.
+    /// Doesn't apply while maximized or fullscreen.
+    ///
+    /// On wayland this is best effort like [`Window::set_resize_increments`].
+    pub fn set_aspect_ratio(&self, ratio: Option<(u32, u32)>) {
+        self.backend_impl.set_aspect_ratio(ratio)
+    }
+    /// Keeps the window out of screenshots and screen recordings, e.g. for a password manager.
+    /// Returns whether the requested protection is in effect, so the app can warn if it isn't.
+    ///
+    /// Only supported on Windows 10 version 2004 and later, on wayland there is no protocol for it.
+    pub fn set_content_protected(&self, protected: bool) -> bool {
+        self.backend_impl.set_content_protected(protected)
+    }
+    /// `None` follows the system. Only the title bar is changed, the app draws everything else.
+    ///
+    /// Wayland has no way to change server side decorations,
+    /// there the theme is only remembered for [`Window::theme`].
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        self.backend_impl.set_theme(theme)
+    }
+    /// The theme the window uses, `None` if it's unknown.
+    ///
+    /// On wayland the system's preference isn't known, only the theme set with [`Window::set_theme`].
+    pub fn theme(&self) -> Option<Theme> {
+        self.backend_impl.theme()
+    }
+    /// Only has an effect on Windows 11 and logs a warning on older versions.
+    pub fn set_corner_preference(&self, preference: CornerPreference) {
+        self.backend_impl.set_corner_preference(preference)
+    }
+    /// Only shows through where the window is transparent, see [`WindowBuilder::with_transparent`].
+    ///
+    /// Only has an effect on Windows 11 and logs a warning on older versions.
+    pub fn set_backdrop(&self, backdrop: Backdrop) {
+        self.backend_impl.set_backdrop(backdrop)
+    }
+    /// `None` removes the progress.
+    ///
+    /// Only has an effect on Windows.
+    pub fn set_taskbar_progress(&self, progress: Option<Progress>) {
+        self.backend_impl.set_taskbar_progress(progress)
+    }
     pub fn set_title(&self, title: &str) {
         self.backend_impl.set_title(title)
     }
-    pub fn set_fullscreen(&self, fullscreen: bool) {
+    pub fn get_title(&self) -> String {
+        self.backend_impl.get_title()
+    }
+    /// Matches the window with a desktop file, which decides its icon and taskbar grouping.
+    /// Starts out as the class name given to [`Waywin::init`].
+    ///
+    /// Only has an effect on wayland.
+    pub fn set_app_id(&self, app_id: &str) {
+        self.backend_impl.set_app_id(app_id)
+    }
+    pub fn show(&self) {
+        self.backend_impl.set_visible(true)
+    }
+    pub fn set_visible(&self, visible: bool) {
+        self.backend_impl.set_visible(visible)
+    }
+    /// Sends keys that would otherwise be system shortcuts to the window while it has focus,
+    /// e.g. for games and remote desktop clients.
+    ///
+    /// On wayland the compositor decides which shortcuts can be inhibited and may ask the user first.
+    /// On Windows only the window menu shortcuts like alt+f4, alt+space and f10 are affected,
+    /// alt+tab and the windows key need a global keyboard hook which is out of scope.
+    pub fn set_keyboard_grab(&self, grab: bool) {
+        self.backend_impl.set_keyboard_grab(grab)
+    }
+    /// Keeps the screen from dimming or locking while `true`.
+    ///
+    /// On wayland this only applies while the window is visible.
+    pub fn set_idle_inhibited(&self, inhibited: bool) {
+        self.backend_impl.set_idle_inhibited(inhibited)
+    }
+    pub fn is_visible(&self) -> bool {
+        self.backend_impl.is_visible()
+    }
+    /// Marks parts of the window as opaque so the compositor can skip blending behind them.
+    /// `None` marks the whole window as possibly transparent.
+    ///
+    /// Only has an effect on wayland.
+    pub fn set_opaque_region(&self, region: Option<&[Rect]>) {
+        self.backend_impl.set_opaque_region(region)
+    }
+    /// The part of the window that is its content, in logical pixels, for windows
+    /// that draw their own shadows or resize borders around it. Tiling and maximizing
+    /// use these bounds instead of the whole surface, and sizes the compositor asks for
+    /// are then meant for this part.
+    ///
+    /// Applies with the next present. Only has an effect on wayland.
+    pub fn set_content_geometry(&self, geometry: Rect) {
+        self.backend_impl.set_content_geometry(geometry)
+    }
+    /// `None` leaves fullscreen.
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
         self.backend_impl.set_fullscreen(fullscreen)
     }
     pub fn get_fullscreen(&self) -> bool {
         self.backend_impl.get_fullscreen()
     }
-    pub fn lock_pointer(&self) {
-        self.backend_impl.lock_pointer()
+    /// The largest size, in logical pixels, the window should have to fit on screen.
+    /// `None` if it's unknown.
+    ///
+    /// On wayland it's what the compositor suggests, on Windows the work area of the monitor.
+    pub fn suggested_max_size(&self) -> Option<(i32, i32)> {
+        self.backend_impl.suggested_max_size()
+    }
+    /// On Windows everything is supported.
+    pub fn wm_capabilities(&self) -> WmCapabilities {
+        self.backend_impl.wm_capabilities()
+    }
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        self.backend_impl.set_cursor_grab(mode)
     }
-    pub fn unlock_pointer(&self) {
-        self.backend_impl.unlock_pointer()
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.backend_impl.cursor_grab()
     }
-    pub fn is_pointer_locked(&self) -> bool {
-        self.backend_impl.is_pointer_locked()
+    /// Pressing `key` while the pointer is locked or confined releases it
+    /// and emits [`WindowEvent::CursorGrabChanged(false)`](event::WindowEvent::CursorGrabChanged).
+    /// The key event itself is still delivered. `None` (the default) disables this.
+    ///
+    /// Compositors may deactivate a grab on their own, e.g. when the window loses focus.
+    /// The grab stays requested and resumes when the window gets the pointer back,
+    /// so the key still releases it for good while it's deactivated.
+    pub fn set_grab_escape_key(&self, key: Option<event::PhysicalKey>) {
+        self.backend_impl.set_grab_escape_key(key)
     }
-    pub fn confine_pointer(&self) {
-        self.backend_impl.confine_pointer()
+    /// Lets an input method compose text for this window, see [`event::Ime`].
+    /// Disallowed by default.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        self.backend_impl.set_ime_allowed(allowed)
     }
-    pub fn unconfine_pointer(&self) {
-        self.backend_impl.unconfine_pointer()
+    /// Where the text cursor is, so the input method can place its popups next to it.
+    pub fn set_ime_cursor_area(&self, area: Rect) {
+        self.backend_impl.set_ime_cursor_area(area)
     }
-    pub fn is_pointer_confined(&self) -> bool {
-        self.backend_impl.is_pointer_confined()
+    /// Shows `cursor` while the pointer is over this window.
+    pub fn set_custom_cursor(&self, cursor: CursorImage) -> Result<(), Error> {
+        cursor.validate()?;
+        self.backend_impl.set_custom_cursor(&cursor)
     }
-    pub fn id(&self) -> usize {
+    /// Shows `pixels` as the window's contents, for software rendering without a graphics api.
+    ///
+    /// Pixels are `0x00RRGGBB`, row by row from the top left.
+    /// `width` and `height` should be the physical size, on windows the buffer is stretched to fit.
+    ///
+    /// Only available with the `present` feature.
+    #[cfg(feature = "present")]
+    pub fn present_buffer(&self, width: u32, height: u32, pixels: &[u32]) -> Result<(), Error> {
+        if pixels.len() < width as usize * height as usize {
+            return Err(Error::Present(format!(
+                "{} pixels are too few for {width}x{height}",
+                pixels.len()
+            )));
+        }
+        self.backend_impl.present_buffer((width, height), pixels)
+    }
+    /// In logical pixels. `None` if the pointer isn't over this window.
+    pub fn pointer_position(&self) -> Option<(f64, f64)> {
+        self.backend_impl.pointer_position()
+    }
+    /// Which edges the pointer is within `margin` logical pixels of.
+    ///
+    /// Combined with [`CursorGrabMode::Confined`] this is all that's needed for edge scrolling.
+    pub fn edge_proximity(&self, margin: f64) -> EdgeFlags {
+        let mut flags = EdgeFlags::empty();
+        let Some((x, y)) = self.pointer_position() else {
+            return flags;
+        };
+        let (width, height) = self.get_logical_size();
+        flags.set(EdgeFlags::LEFT, x < margin);
+        flags.set(EdgeFlags::RIGHT, x >= width - margin);
+        flags.set(EdgeFlags::TOP, y < margin);
+        flags.set(EdgeFlags::BOTTOM, y >= height - margin);
+        flags
+    }
+    /// The monitor the window is mostly on, `None` until the window is shown.
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        self.backend_impl
+            .current_monitor()
+            .map(|backend_impl| Monitor { backend_impl })
+    }
+    pub fn id(&self) -> WindowId {
         self.backend_impl.id()
     }
+    /// Puts the window above the others without focusing it, see [`Window::focus`] for that.
+    /// Returns whether it was done.
+    ///
+    /// Always `false` on wayland, xdg-shell has no control over stacking.
+    pub fn raise(&self) -> bool {
+        self.backend_impl.raise()
+    }
+    /// Puts the window below the others without changing focus.
+    /// Returns whether it was done.
+    ///
+    /// Always `false` on wayland, like [`Window::raise`].
+    pub fn lower(&self) -> bool {
+        self.backend_impl.lower()
+    }
+    /// Raises the window and gives it keyboard focus, restoring it if it's minimized.
+    /// Returns whether the request was made, the system can still refuse it
+    /// if the user is busy with another app.
+    ///
+    /// On Windows it's refused unless the app is allowed to take the foreground,
+    /// e.g. while handling input. On wayland it needs `xdg_activation_v1`.
+    pub fn focus(&self) -> bool {
+        self.backend_impl.focus()
+    }
+    /// Gives this window's events to `handler` instead of the event hook passed to [`Waywin::run`],
+    /// replacing any handler set before. It's called on the event loop thread, up to and including `Destroyed`.
+    pub fn set_event_handler(&self, handler: impl FnMut(WindowEvent) + Send + 'static) {
+        self.backend_impl.set_event_handler(handler)
+    }
+    /// Asks `handler` what a point in logical pixels acts as, for windows that draw their own title bar.
+    /// Replaces any handler set before.
+    ///
+    /// On Windows it's asked whenever the pointer moves over the window,
+    /// so snapping, double clicking to maximize and resizing from the edges all work.
+    /// On wayland it's only asked on button presses: a left press moves or resizes the window
+    /// and a right press on a [`HitTestResult::Caption`] shows the window menu.
+    /// Those presses aren't sent as `PointerButton`.
+    pub fn set_hittest_handler(
+        &self,
+        handler: impl Fn(f64, f64) -> HitTestResult + Send + 'static,
+    ) {
+        self.backend_impl.set_hittest_handler(handler)
+    }
+    /// The `wl_surface` pointer of the window, for C libraries that need it.
+    /// Only valid as long as this window is alive.
+    ///
+    /// Only available on linux.
+    #[cfg(target_os = "linux")]
+    pub fn wayland_surface_ptr(&self) -> *mut std::ffi::c_void {
+        self.backend_impl.surface_ptr()
+    }
+    /// The `HWND` of the window, for C libraries that need it.
+    /// Only valid as long as this window is alive.
+    ///
+    /// Only available on windows.
+    #[cfg(target_os = "windows")]
+    pub fn hwnd(&self) -> isize {
+        self.backend_impl.raw_hwnd()
+    }
+    /// The `HINSTANCE` of the module that created the window.
+    ///
+    /// Only available on windows.
+    #[cfg(target_os = "windows")]
+    pub fn hinstance(&self) -> isize {
+        self.backend_impl.raw_hinstance()
+    }
+    /// Creates a popup of `size`, like a context menu or a dropdown, below `anchor`.
+    /// `anchor` is relative to this window and `offset` moves the popup away from it,
+    /// all in logical pixels. Where supported it flips or slides to stay on screen.
+    ///
+    /// The popup gets its own input events until a click outside of it dismisses it,
+    /// which hides it and sends it `CloseRequested`. A hidden popup can't be shown again.
+    ///
+    /// Create it in response to a button or key press, wayland compositors dismiss it right away otherwise.
+    pub fn create_popup(
+        &self,
+        anchor: Rect,
+        offset: (i32, i32),
+        size: (f64, f64),
+    ) -> Result<Window, Error> {
+        self.backend_impl
+            .create_popup(anchor, offset, size)
+            .map(|backend_impl| Window { backend_impl })
+    }
 }
 
 impl rwh::HasDisplayHandle for Waywin {
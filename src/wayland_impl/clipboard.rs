@@ -0,0 +1,121 @@
+use super::{
+    state::{
+        data_device::{receive, DataOfferData, SelectionState, TEXT_MIME_TYPES},
+        primary_selection::PrimarySelection,
+        seat::InputSerials,
+    },
+    WaywinState,
+};
+use std::sync::{Arc, Mutex};
+use wayland_client::{
+    protocol::{wl_data_device::WlDataDevice, wl_data_device_manager::WlDataDeviceManager},
+    Connection, Proxy, QueueHandle,
+};
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+    zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+};
+
+pub struct Clipboard {
+    data_device_manager: Option<WlDataDeviceManager>,
+    data_device: Option<WlDataDevice>,
+    selection: Arc<Mutex<SelectionState>>,
+    primary_selection_device_manager: Option<ZwpPrimarySelectionDeviceManagerV1>,
+    primary_selection_device: Option<ZwpPrimarySelectionDeviceV1>,
+    primary_selection: Arc<Mutex<PrimarySelection>>,
+    input_serials: Arc<Mutex<InputSerials>>,
+    /// The devices belong to it, so the serial has to be from it too.
+    seat: u32,
+
+    connection: Connection,
+    qhandle: QueueHandle<WaywinState>,
+}
+impl Clipboard {
+    pub fn new(state: &WaywinState) -> Self {
+        Self {
+            data_device_manager: state.data_device_state.data_device_manager.clone(),
+            data_device: state.data_device_state.data_device.clone(),
+            selection: state.data_device_state.selection.clone(),
+            primary_selection_device_manager: state.primary_selection_state.manager.clone(),
+            primary_selection_device: state.primary_selection_state.device.clone(),
+            primary_selection: state.primary_selection_state.selection.clone(),
+            input_serials: state.input_serials.clone(),
+            seat: state.clipboard_seat,
+            connection: state.connection.clone(),
+            qhandle: state.qhandle.clone(),
+        }
+    }
+    /// `None` once the seat is gone.
+    fn serial(&self) -> Option<u32> {
+        self.input_serials.lock().unwrap().get(self.seat)
+    }
+    pub fn get_text(&self) -> Option<String> {
+        let selection = self.selection.lock().unwrap();
+        // the event loop can't answer this while it's stuck reading
+        if let Some((_, text)) = &selection.source {
+            return Some(text.clone());
+        }
+        let offer = selection.offer.as_ref()?;
+        let mime_type = offer.data::<DataOfferData>()?.text_mime_type()?;
+        let text = receive(&self.connection, |fd| {
+            offer.receive(mime_type.to_owned(), fd)
+        })?;
+        Some(String::from_utf8_lossy(&text).into_owned())
+    }
+    pub fn set_text(&self, text: &str) {
+        let Some((manager, data_device)) = self
+            .data_device_manager
+            .as_ref()
+            .zip(self.data_device.as_ref())
+        else {
+            return;
+        };
+        let Some(serial) = self.serial() else {
+            return;
+        };
+        let source = manager.create_data_source(&self.qhandle, ());
+        for mime_type in TEXT_MIME_TYPES {
+            source.offer(mime_type.to_owned());
+        }
+        data_device.set_selection(Some(&source), serial);
+
+        let mut selection = self.selection.lock().unwrap();
+        if let Some((source, _)) = selection.source.replace((source, text.to_owned())) {
+            source.destroy();
+        }
+    }
+    pub fn get_primary_text(&self) -> Option<String> {
+        let selection = self.primary_selection.lock().unwrap();
+        if let Some((_, text)) = &selection.source {
+            return Some(text.clone());
+        }
+        let offer = selection.offer.as_ref()?;
+        let mime_type = offer.data::<DataOfferData>()?.text_mime_type()?;
+        let text = receive(&self.connection, |fd| {
+            offer.receive(mime_type.to_owned(), fd)
+        })?;
+        Some(String::from_utf8_lossy(&text).into_owned())
+    }
+    pub fn set_primary_text(&self, text: &str) {
+        let Some((manager, device)) = self
+            .primary_selection_device_manager
+            .as_ref()
+            .zip(self.primary_selection_device.as_ref())
+        else {
+            return;
+        };
+        let Some(serial) = self.serial() else {
+            return;
+        };
+        let source = manager.create_source(&self.qhandle, ());
+        for mime_type in TEXT_MIME_TYPES {
+            source.offer(mime_type.to_owned());
+        }
+        device.set_selection(Some(&source), serial);
+
+        let mut selection = self.primary_selection.lock().unwrap();
+        if let Some((source, _)) = selection.source.replace((source, text.to_owned())) {
+            source.destroy();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use super::WaywinState;
+use crate::{CursorImage, Error};
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    os::fd::AsFd,
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use wayland_client::{
+    protocol::{
+        wl_buffer::WlBuffer,
+        wl_compositor::WlCompositor,
+        wl_pointer::WlPointer,
+        wl_shm::{Format, WlShm},
+        wl_surface::WlSurface,
+    },
+    Proxy, QueueHandle,
+};
+
+/// A cursor image kept attached to its own surface.
+pub struct CustomCursor {
+    surface: WlSurface,
+    buffer: WlBuffer,
+    hotspot: (i32, i32),
+}
+impl CustomCursor {
+    pub fn new(
+        image: &CursorImage,
+        compositor: &WlCompositor,
+        shm: &WlShm,
+        qhandle: &QueueHandle<WaywinState>,
+    ) -> Result<Self, Error> {
+        // premultiplied little endian argb
+        let pixels: Vec<u8> = image
+            .rgba
+            .chunks_exact(4)
+            .flat_map(|rgba| {
+                let premultiply = |c: u8| (c as u32 * rgba[3] as u32 / 255) as u8;
+                [
+                    premultiply(rgba[2]),
+                    premultiply(rgba[1]),
+                    premultiply(rgba[0]),
+                    rgba[3],
+                ]
+            })
+            .collect();
+        let file = shm_file(&pixels)
+            .map_err(|err| Error::Cursor(format!("failed to create shared memory: {err}")))?;
+
+        let (width, height) = (image.width as i32, image.height as i32);
+        let pool = shm.create_pool(file.as_fd(), pixels.len() as i32, qhandle, ());
+        let buffer = pool.create_buffer(0, width, height, width * 4, Format::Argb8888, qhandle, ());
+        // the buffer keeps the memory alive
+        pool.destroy();
+
+        let surface = compositor.create_surface(qhandle, ());
+        surface.attach(Some(&buffer), 0, 0);
+        if surface.version() >= 4 {
+            surface.damage_buffer(0, 0, width, height);
+        } else {
+            surface.damage(0, 0, width, height);
+        }
+        surface.commit();
+
+        Ok(Self {
+            surface,
+            buffer,
+            hotspot: (image.hotspot_x as i32, image.hotspot_y as i32),
+        })
+    }
+    /// `serial` has to be the one of the pointer's latest enter.
+    pub fn apply(&self, pointer: &WlPointer, serial: u32) {
+        pointer.set_cursor(serial, Some(&self.surface), self.hotspot.0, self.hotspot.1);
+    }
+}
+impl Drop for CustomCursor {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.surface.destroy();
+    }
+}
+
+/// A file that only lives in memory, to be shared with the compositor.
+pub fn shm_file(data: &[u8]) -> std::io::Result<File> {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!(
+        "waywin-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    // the open file keeps the memory around
+    std::fs::remove_file(&path)?;
+    file.write_all(data)?;
+    Ok(file)
+}
@@ -1,89 +1,378 @@
 use crate::{
-    event::{WaywinEvent, WindowEvent},
-    wayland_impl::state::WaywinState,
+    event::{Ime, WaywinEvent, WindowEvent},
+    wayland_impl::state::{output::monitor_id, EventHandlers, WaywinState, EVENTS_CAPACITY},
+    ControlFlow, Error, RepeatInfo, WindowId,
 };
+pub use clipboard::Clipboard;
+pub use monitor::Monitor;
 use raw_window_handle as rwh;
-use std::ptr::NonNull;
-use wayland_client::Proxy;
-pub use window::Window;
+use std::{
+    any::Any,
+    ptr::NonNull,
+    time::{Duration, Instant},
+};
+use wayland_client::{Connection, Proxy};
+use window::WindowContext;
+
+/// Windows only need shared state, not the whole `Waywin`.
+pub type WindowCreator = WindowContext;
+pub use window::{Window, WindowParent};
 
+mod clipboard;
+mod cursor;
+mod monitor;
+#[cfg(feature = "present")]
+mod present;
 mod state;
 mod window;
 
+pub type TimerId = calloop::RegistrationToken;
+
 pub struct Waywin {
     state: WaywinState,
+    window_context: WindowContext,
 
     event_loop: calloop::EventLoop<'static, WaywinState>,
+    user_events: calloop::channel::Sender<Box<dyn Any + Send>>,
+    /// Swapped with `WaywinState::events` to be delivered from,
+    /// both keep their capacity so a steady stream of events doesn't allocate.
+    delivered_events: Vec<WaywinEvent>,
 }
 impl Waywin {
-    pub fn init(instance: &str) -> Result<Self, String> {
-        let event_loop = calloop::EventLoop::try_new()
-            .map_err(|err| format!("failed to create polling system: {err}"))?;
+    pub fn init(instance: &str) -> Result<Self, Error> {
+        let connection =
+            Connection::connect_to_env().map_err(|err| Error::Connection(err.to_string()))?;
+        Self::init_with_connection(instance, connection)
+    }
+    pub fn init_with_connection(instance: &str, connection: Connection) -> Result<Self, Error> {
+        let event_loop =
+            calloop::EventLoop::try_new().map_err(|err| Error::EventLoop(err.to_string()))?;
 
-        let (state, event_queue) = WaywinState::new(instance, event_loop.handle())?;
+        let (mut state, mut event_queue) =
+            WaywinState::new(instance, connection, event_loop.handle())?;
+        // so outputs and seat capabilities are known right away
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|err| Error::Connection(err.to_string()))?;
 
         calloop_wayland_source::WaylandSource::new(state.connection.clone(), event_queue)
             .insert(event_loop.handle())
-            .map_err(|err| err.to_string())?;
+            .map_err(|err| Error::EventLoop(err.to_string()))?;
 
-        Ok(Self { state, event_loop })
-    }
-    pub fn run(&mut self, mut event_hook: impl FnMut(WaywinEvent, &mut bool) + 'static) {
-        let mut running = true;
-        let signal = self.event_loop.get_signal();
+        let (new_windows, channel) = calloop::channel::channel();
+        event_loop
+            .handle()
+            .insert_source(channel, |event, _, state| {
+                if let calloop::channel::Event::Msg(window) = event {
+                    state.windows.push(window);
+                }
+            })
+            .map_err(|err| Error::EventLoop(err.to_string()))?;
+        let window_context = WindowContext::new(&state, event_loop.get_signal(), new_windows);
 
+        let (user_events, channel) = calloop::channel::channel();
+        event_loop
+            .handle()
+            .insert_source(channel, |event, _, state| {
+                if let calloop::channel::Event::Msg(event) = event {
+                    state.events.push(WaywinEvent::User(event));
+                }
+            })
+            .map_err(|err| Error::EventLoop(err.to_string()))?;
+
+        Ok(Self {
+            state,
+            window_context,
+            event_loop,
+            user_events,
+            delivered_events: Vec::with_capacity(EVENTS_CAPACITY),
+        })
+    }
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.state.output_state.lock().unwrap().monitors()
+    }
+    pub fn wheel_scroll_lines(&self) -> Option<u32> {
+        Some(3)
+    }
+    pub fn clipboard(&self) -> Clipboard {
+        Clipboard::new(&self.state)
+    }
+    pub fn window_creator(&self) -> WindowCreator {
+        self.window_context.clone()
+    }
+    pub fn create_proxy(&self) -> EventLoopProxy {
+        EventLoopProxy {
+            sender: self.user_events.clone(),
+        }
+    }
+    pub fn insert_source<S: calloop::EventSource + 'static>(
+        &self,
+        source: S,
+        mut callback: impl FnMut(S::Event, &mut S::Metadata, &mut Vec<WaywinEvent>) -> S::Ret + 'static,
+    ) -> Result<calloop::RegistrationToken, Error> {
         self.event_loop
-            .run(None, &mut self.state, |state| {
-                state.windows.retain(|window| {
-                    if let Some(window) = window.upgrade() {
-                        let mut window = window.lock().unwrap();
+            .handle()
+            .insert_source(source, move |event, metadata, state| {
+                callback(event, metadata, &mut state.events)
+            })
+            .map_err(|err| Error::EventLoop(err.error.to_string()))
+    }
+    pub fn remove_source(&self, token: calloop::RegistrationToken) {
+        self.event_loop.handle().remove(token)
+    }
+    pub fn add_timer(
+        &self,
+        interval: Duration,
+        mut callback: impl FnMut(&mut Vec<WaywinEvent>) + 'static,
+    ) -> Result<TimerId, Error> {
+        let timer = calloop::timer::Timer::from_duration(interval);
+        self.insert_source(timer, move |_, _, events| {
+            callback(events);
+            calloop::timer::TimeoutAction::ToDuration(interval)
+        })
+    }
+    pub fn remove_timer(&self, timer: TimerId) {
+        self.remove_source(timer)
+    }
+    pub fn flush(&self) -> Result<(), Error> {
+        self.state
+            .connection
+            .flush()
+            .map_err(|err| Error::Connection(err.to_string()))
+    }
+    pub fn roundtrip(&self) -> Result<(), Error> {
+        // the event queue belongs to the event loop, the events wait there for `run`
+        self.state
+            .connection
+            .roundtrip()
+            .map(|_| ())
+            .map_err(|err| Error::Connection(err.to_string()))
+    }
+    pub fn display_ptr(&self) -> *mut std::ffi::c_void {
+        self.state.connection.display().id().as_ptr() as *mut _
+    }
+    pub fn set_key_repeat(&mut self, repeat: Option<RepeatInfo>) {
+        // a key that's already repeating picks this up on its next repeat
+        self.state.repeat_override = Some(repeat);
+    }
+    pub fn run(&mut self, mut event_hook: impl FnMut(WaywinEvent, &mut ControlFlow) + 'static) {
+        let mut control_flow = ControlFlow::default();
+        // the earliest redraw deadline whose timer couldn't be inserted
+        let mut redraw_wakeup: Option<Instant> = None;
+
+        loop {
+            let deadline = match control_flow {
+                ControlFlow::Poll => Some(Instant::now()),
+                ControlFlow::Wait => None,
+                ControlFlow::WaitUntil(deadline) => Some(deadline),
+                ControlFlow::Exit => return,
+            };
+            let timeout = deadline
+                .into_iter()
+                .chain(redraw_wakeup.take())
+                .min()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            // the only fallible source is the wayland connection
+            if let Err(err) = self.event_loop.dispatch(timeout, &mut self.state) {
+                log::error!("lost connection to compositor: {err}");
+                event_hook(WaywinEvent::Disconnected, &mut control_flow);
+                return;
+            }
 
-                        let scaled = window.prev_state.scale != window.state.scale;
-                        let resized = window.prev_state.size != window.state.size;
-                        window.prev_state = window.state;
+            let state = &mut self.state;
+            let now = Instant::now();
+            let ime_focus = state.text_input_state.lock().unwrap().focused_window;
+            state.windows.retain(|(id, weak)| {
+                if let Some(window) = weak.upgrade() {
+                    let mut window = window.lock().unwrap();
 
-                        if scaled {
-                            state.events.push(WaywinEvent::WindowEvent {
-                                event: WindowEvent::NewScaleFactor,
-                                window_id: window.id(),
-                            });
+                    window.check_redraw_deadline(now);
+                    let timer = state.redraw_timers.get(id).map(|&(deadline, _)| deadline);
+                    if window.redraw_deadline != timer {
+                        // moved or gone, a timer that already fired is removed as well
+                        if let Some((_, token)) = state.redraw_timers.remove(id) {
+                            state.handle.remove(token);
                         }
-                        if resized || scaled {
-                            state.events.push(WaywinEvent::WindowEvent {
-                                event: WindowEvent::Resized,
-                                window_id: window.id(),
-                            });
+                        if let Some(deadline) = window.redraw_deadline {
+                            // the timer only wakes up the loop, the deadline is checked above
+                            let timer = calloop::timer::Timer::from_deadline(deadline);
+                            match state
+                                .handle
+                                .insert_source(timer, |_, _, _| calloop::timer::TimeoutAction::Drop)
+                            {
+                                Ok(token) => {
+                                    state.redraw_timers.insert(*id, (deadline, token));
+                                }
+                                Err(err) => {
+                                    log::error!("failed to insert redraw timer: {err}");
+                                    redraw_wakeup =
+                                        Some(redraw_wakeup.map_or(deadline, |d| d.min(deadline)));
+                                }
+                            }
                         }
+                    }
 
-                        if window.reset_redraw() || resized || scaled {
-                            state.events.push(WaywinEvent::WindowEvent {
-                                event: WindowEvent::Paint,
-                                window_id: window.id(),
-                            });
+                    // however many configures came in since, only the end result is sent
+                    let [scaled, resized] = window.state.changes_since(&window.prev_state);
+                    window.prev_state = window.state;
+                    let redraw = scaled.is_some() || resized.is_some();
+
+                    if let Some(event) = scaled {
+                        window.apply_size_constraints();
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event,
+                            window_id: window.id(),
+                        });
+                    }
+                    if let Some(event) = resized {
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event,
+                            window_id: window.id(),
+                        });
+                    }
+
+                    if let Some(output) = window
+                        .current_output()
+                        .filter(|&output| window.prev_output.as_ref() != Some(output))
+                        .cloned()
+                    {
+                        let monitor = monitor_id(&output);
+                        window.prev_output = Some(output);
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::MonitorChanged(monitor),
+                            window_id: window.id(),
+                        });
+                    }
+
+                    let ime_enabled = window.ime_allowed && ime_focus == Some(window.id());
+                    if ime_enabled != window.ime_enabled {
+                        window.ime_enabled = ime_enabled;
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::Ime(if ime_enabled {
+                                Ime::Enabled
+                            } else {
+                                Ime::Disabled
+                            }),
+                            window_id: window.id(),
+                        });
+                    }
+
+                    if redraw {
+                        window.request_redraw();
+                    }
+                    // keep the redraw request around until the window can be drawn to
+                    // and the compositor is ready for a new frame
+                    if window.is_mapped() && !window.awaiting_frame() && window.reset_redraw() {
+                        let damage = window.take_damage();
+                        // goes along with the next commit, the one drawn for this paint
+                        if let Some(presentation) = &state.presentation {
+                            window.request_presentation_feedback(presentation, &state.qhandle);
                         }
-                        true
-                    } else {
-                        false
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::Paint { damage },
+                            window_id: window.id(),
+                        });
                     }
-                });
-
-                for event in state.events.drain(..) {
-                    event_hook(event, &mut running);
-                    if !running {
-                        signal.stop();
-                        signal.wakeup();
-                        return;
+                    true
+                } else {
+                    if let Some((_, token)) = state.redraw_timers.remove(id) {
+                        state.handle.remove(token);
                     }
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: WindowEvent::Destroyed,
+                        window_id: *id,
+                    });
+                    false
                 }
-            })
-            .unwrap();
+            });
+
+            state.events.push(WaywinEvent::AboutToWait);
+            for event in take_events(&mut state.events, &mut self.delivered_events) {
+                // set on delivery so they can't race ahead of the events before them
+                if let WaywinEvent::WindowEvent {
+                    event:
+                        event @ (WindowEvent::ModifiersChanged(_) | WindowEvent::LockStateChanged(_)),
+                    window_id,
+                } = &event
+                {
+                    if let Some(window) = state
+                        .windows
+                        .iter()
+                        .find(|(id, _)| id == window_id)
+                        .and_then(|(_, window)| window.upgrade())
+                    {
+                        let mut window = window.lock().unwrap();
+                        match *event {
+                            WindowEvent::ModifiersChanged(modifiers) => {
+                                window.modifiers = modifiers
+                            }
+                            WindowEvent::LockStateChanged(lock_state) => {
+                                window.lock_state = lock_state
+                            }
+                            _ => unreachable!(),
+                        }
+                    }
+                }
+                let event = match event {
+                    WaywinEvent::WindowEvent { event, window_id } => {
+                        match call_event_handler(&state.event_handlers, window_id, event) {
+                            Some(event) => WaywinEvent::WindowEvent { event, window_id },
+                            None => continue,
+                        }
+                    }
+                    event => event,
+                };
+                event_hook(event, &mut control_flow);
+                if control_flow == ControlFlow::Exit {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Gives the event to the window's own handler, or back if it doesn't have one.
+fn call_event_handler(
+    handlers: &EventHandlers,
+    window_id: WindowId,
+    event: WindowEvent,
+) -> Option<WindowEvent> {
+    // taken out while it runs so it can set handlers itself
+    let Some(mut handler) = handlers.lock().unwrap().remove(&window_id) else {
+        return Some(event);
+    };
+    let destroyed = matches!(event, WindowEvent::Destroyed);
+    handler(event);
+    if !destroyed {
+        // a handler set while it ran replaces it
+        handlers.lock().unwrap().entry(window_id).or_insert(handler);
+    }
+    None
+}
+
+/// Swaps the events pushed since the last delivery with the emptied buffer of that delivery
+/// and drains them. Both keep their capacity, so once grown to the usual load they don't allocate.
+pub fn take_events<'a>(
+    pushed: &mut Vec<WaywinEvent>,
+    delivered: &'a mut Vec<WaywinEvent>,
+) -> std::vec::Drain<'a, WaywinEvent> {
+    std::mem::swap(pushed, delivered);
+    delivered.drain(..)
+}
+
+#[derive(Clone)]
+pub struct EventLoopProxy {
+    sender: calloop::channel::Sender<Box<dyn Any + Send>>,
+}
+impl EventLoopProxy {
+    pub fn send_event(&self, event: Box<dyn Any + Send>) -> Result<(), Box<dyn Any + Send>> {
+        self.sender.send(event).map_err(|err| err.0)
     }
 }
 
 impl rwh::HasDisplayHandle for Waywin {
     fn display_handle(&self) -> std::result::Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
-        let ptr = self.state.connection.display().id().as_ptr();
-        let handle = rwh::WaylandDisplayHandle::new(NonNull::new(ptr as *mut _).unwrap());
+        let handle = rwh::WaylandDisplayHandle::new(NonNull::new(self.display_ptr()).unwrap());
         unsafe { Ok(rwh::DisplayHandle::borrow_raw(handle.into())) }
     }
 }
@@ -0,0 +1,38 @@
+use super::state::output::{monitor_id, Output, OutputInfo};
+use crate::{MonitorId, VideoMode};
+use wayland_client::protocol::wl_output::WlOutput;
+
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    output: WlOutput,
+    info: OutputInfo,
+}
+impl Monitor {
+    pub(super) fn new(output: &Output) -> Self {
+        Self {
+            output: output.output.clone(),
+            info: output.info.clone(),
+        }
+    }
+    pub(super) fn output(&self) -> &WlOutput {
+        &self.output
+    }
+    pub fn id(&self) -> MonitorId {
+        monitor_id(&self.output)
+    }
+    pub fn name(&self) -> Option<String> {
+        self.info.name.clone()
+    }
+    pub fn position(&self) -> (i32, i32) {
+        self.info.position
+    }
+    pub fn physical_size(&self) -> (u32, u32) {
+        self.info.size
+    }
+    pub fn scale(&self) -> f64 {
+        self.info.scale as f64
+    }
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.info.modes.clone()
+    }
+}
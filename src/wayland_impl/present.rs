@@ -0,0 +1,45 @@
+use super::{cursor::shm_file, WaywinState};
+use crate::Error;
+use std::os::fd::AsFd;
+use wayland_client::{
+    protocol::{
+        wl_shm::{Format, WlShm},
+        wl_surface::WlSurface,
+    },
+    Proxy, QueueHandle,
+};
+
+/// Every frame gets its own memory that's never written to again,
+/// so the buffer can be destroyed right away without waiting for the compositor to release it.
+pub fn present_buffer(
+    surface: &WlSurface,
+    shm: &WlShm,
+    qhandle: &QueueHandle<WaywinState>,
+    (width, height): (u32, u32),
+    pixels: &[u32],
+    damage_all: bool,
+) -> Result<(), Error> {
+    let bytes: Vec<u8> = pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_le_bytes())
+        .collect();
+    let file = shm_file(&bytes)
+        .map_err(|err| Error::Present(format!("failed to create shared memory: {err}")))?;
+
+    let (width, height) = (width as i32, height as i32);
+    let pool = shm.create_pool(file.as_fd(), bytes.len() as i32, qhandle, ());
+    // 0x00RRGGBB in native endianness is xrgb8888 on little endian
+    let buffer = pool.create_buffer(0, width, height, width * 4, Format::Xrgb8888, qhandle, ());
+    pool.destroy();
+
+    surface.attach(Some(&buffer), 0, 0);
+    // otherwise only the damage of the last paint was sent
+    if damage_all && surface.version() >= 4 {
+        surface.damage_buffer(0, 0, width, height);
+    } else if damage_all {
+        surface.damage(0, 0, i32::MAX, i32::MAX);
+    }
+    surface.commit();
+    buffer.destroy();
+    Ok(())
+}
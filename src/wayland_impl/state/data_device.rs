@@ -0,0 +1,421 @@
+use super::WaywinState;
+use crate::{
+    event::{WaywinEvent, WindowEvent},
+    WindowId,
+};
+use calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{ErrorKind, PipeReader, Read, Write},
+    os::{
+        fd::{AsFd, BorrowedFd, OwnedFd},
+        unix::ffi::OsStringExt,
+    },
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use wayland_client::{
+    protocol::{
+        wl_data_device::{self, WlDataDevice},
+        wl_data_device_manager::{DndAction, WlDataDeviceManager},
+        wl_data_offer::{self, WlDataOffer},
+        wl_data_source::{self, WlDataSource},
+    },
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+
+const URI_LIST: &str = "text/uri-list";
+/// In order of preference.
+pub const TEXT_MIME_TYPES: [&str; 3] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+
+pub struct DataDeviceState {
+    pub data_device_manager: Option<WlDataDeviceManager>,
+    pub data_device: Option<WlDataDevice>,
+    drag: Option<Drag>,
+    /// Shared with the clipboard handles.
+    pub selection: Arc<Mutex<SelectionState>>,
+}
+#[derive(Default)]
+pub struct SelectionState {
+    /// What another client put on the clipboard.
+    pub offer: Option<WlDataOffer>,
+    /// What this client put on the clipboard, read back without going through the compositor.
+    pub source: Option<(WlDataSource, String)>,
+}
+/// A drag currently hovering over one of the windows.
+struct Drag {
+    offer: WlDataOffer,
+    window_id: WindowId,
+    /// Empty if the offer has no files, `None` until its uri list has arrived.
+    paths: Option<Vec<PathBuf>>,
+    /// Reads the uri list without blocking the event loop.
+    reader: Option<RegistrationToken>,
+    /// Dropped before the uri list arrived, the files are dropped once it does.
+    dropped: bool,
+}
+impl Drag {
+    fn cancel(self, handle: &LoopHandle<'static, WaywinState>) {
+        if let Some(reader) = self.reader {
+            handle.remove(reader);
+        }
+        self.offer.destroy();
+    }
+    fn drop_files(self, events: &mut Vec<WaywinEvent>) {
+        let paths = self.paths.unwrap_or_default();
+        if !paths.is_empty() && self.offer.version() >= 3 {
+            self.offer.finish();
+        }
+        for path in paths {
+            events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::DroppedFile(path),
+                window_id: self.window_id,
+            });
+        }
+        self.offer.destroy();
+    }
+}
+impl DataDeviceState {
+    pub fn new(data_device_manager: Option<WlDataDeviceManager>) -> Self {
+        Self {
+            data_device_manager,
+            data_device: None,
+            drag: None,
+            selection: Arc::default(),
+        }
+    }
+}
+impl Drop for DataDeviceState {
+    fn drop(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            drag.offer.destroy()
+        }
+        let mut selection = self.selection.lock().unwrap();
+        if let Some(offer) = selection.offer.take() {
+            offer.destroy()
+        }
+        if let Some((source, _)) = selection.source.take() {
+            source.destroy()
+        }
+        if let Some(s) = self.data_device.take() {
+            if s.version() >= 2 {
+                s.release()
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DataOfferData {
+    mime_types: Mutex<Vec<String>>,
+}
+impl DataOfferData {
+    pub fn push_mime_type(&self, mime_type: String) {
+        self.mime_types.lock().unwrap().push(mime_type);
+    }
+    /// The most preferred text mime type that is offered.
+    pub fn text_mime_type(&self) -> Option<&'static str> {
+        let mime_types = self.mime_types.lock().unwrap();
+        TEXT_MIME_TYPES
+            .into_iter()
+            .find(|text| mime_types.iter().any(|m| m == text))
+    }
+}
+
+impl Dispatch<WlDataDevice, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &WlDataDevice,
+        event: <WlDataDevice as wayland_client::Proxy>::Event,
+        _data: &(),
+        conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_device::Event::DataOffer { id: _ } => {
+                // mime types arrive on the offer itself
+            }
+            wl_data_device::Event::Enter {
+                serial,
+                surface,
+                x: _,
+                y: _,
+                id,
+            } => {
+                if let Some(drag) = state.data_device_state.drag.take() {
+                    drag.cancel(&state.handle);
+                }
+                let Some(offer) = id else {
+                    return;
+                };
+                let window_id = WindowId(surface.id().as_ptr() as usize);
+
+                let has_files = offer.data::<DataOfferData>().is_some_and(|data| {
+                    data.mime_types
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .any(|m| m == URI_LIST)
+                });
+                let reader = if has_files {
+                    offer.accept(serial, Some(URI_LIST.to_owned()));
+                    if offer.version() >= 3 {
+                        offer.set_actions(DndAction::Copy, DndAction::Copy);
+                    }
+                    read_paths(conn, &state.handle, &offer)
+                } else {
+                    offer.accept(serial, None);
+                    None
+                };
+
+                state.data_device_state.drag = Some(Drag {
+                    offer,
+                    window_id,
+                    // the files are hovered once they've been read
+                    paths: reader.is_none().then(Vec::new),
+                    reader,
+                    dropped: false,
+                });
+            }
+            wl_data_device::Event::Leave => {
+                // a drop still waiting for its files isn't cancelled by leaving
+                if state
+                    .data_device_state
+                    .drag
+                    .as_ref()
+                    .is_none_or(|drag| drag.dropped)
+                {
+                    return;
+                }
+                let drag = state.data_device_state.drag.take().unwrap();
+                if drag.paths.as_ref().is_some_and(|paths| !paths.is_empty()) {
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: WindowEvent::HoveredFileCancelled,
+                        window_id: drag.window_id,
+                    });
+                }
+                drag.cancel(&state.handle);
+            }
+            wl_data_device::Event::Motion { .. } => {}
+            wl_data_device::Event::Drop => {
+                let Some(drag) = state.data_device_state.drag.as_mut() else {
+                    log::warn!("recieved a drop while nothing is being dragged");
+                    return;
+                };
+                if drag.paths.is_none() {
+                    drag.dropped = true;
+                    return;
+                }
+                let drag = state.data_device_state.drag.take().unwrap();
+                drag.drop_files(&mut state.events);
+            }
+            wl_data_device::Event::Selection { id } => {
+                let mut selection = state.data_device_state.selection.lock().unwrap();
+                if let Some(offer) = std::mem::replace(&mut selection.offer, id) {
+                    offer.destroy();
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    wayland_client::event_created_child!(WaywinState, WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (WlDataOffer, DataOfferData::default()),
+    ]);
+}
+
+impl Dispatch<WlDataOffer, DataOfferData> for WaywinState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlDataOffer,
+        event: <WlDataOffer as wayland_client::Proxy>::Event,
+        data: &DataOfferData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_offer::Event::Offer { mime_type } => {
+                data.push_mime_type(mime_type);
+            }
+            wl_data_offer::Event::SourceActions { .. } => {}
+            wl_data_offer::Event::Action { .. } => {}
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl Dispatch<WlDataSource, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlDataSource,
+        event: <WlDataSource as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let mut selection = state.data_device_state.selection.lock().unwrap();
+        match event {
+            wl_data_source::Event::Send { mime_type: _, fd } => {
+                let Some((_, text)) = selection.source.as_ref().filter(|(s, _)| s == proxy) else {
+                    return;
+                };
+                if let Err(err) = File::from(fd).write_all(text.as_bytes()) {
+                    log::error!("failed to send clipboard text: {err}");
+                }
+            }
+            wl_data_source::Event::Cancelled => {
+                // replaced by another selection
+                if selection.source.as_ref().is_some_and(|(s, _)| s == proxy) {
+                    selection.source = None;
+                }
+                proxy.destroy();
+            }
+            wl_data_source::Event::Target { .. } => {}
+            wl_data_source::Event::Action { .. } => {}
+            wl_data_source::Event::DndDropPerformed => {}
+            wl_data_source::Event::DndFinished => {}
+            _ => unimplemented!(),
+        }
+    }
+}
+
+/// Reads everything the source of an offer sends through the pipe given to `receive`.
+/// Blocks until the source closes its end.
+pub fn receive(conn: &Connection, receive: impl FnOnce(BorrowedFd)) -> Option<Vec<u8>> {
+    let mut reader = open_pipe(conn, receive)?;
+    let mut data = Vec::new();
+    if let Err(err) = reader.read_to_end(&mut data) {
+        log::error!("failed to receive data: {err}");
+        return None;
+    }
+    Some(data)
+}
+
+/// The read end of the pipe given to `receive`, which the source of an offer writes to.
+fn open_pipe(conn: &Connection, receive: impl FnOnce(BorrowedFd)) -> Option<PipeReader> {
+    let (reader, writer) = match std::io::pipe() {
+        Ok(pipe) => pipe,
+        Err(err) => {
+            log::error!("failed to create pipe to receive data: {err}");
+            return None;
+        }
+    };
+    receive(writer.as_fd());
+    // only the source may hold the write end or the read never finishes
+    drop(writer);
+    if let Err(err) = conn.flush() {
+        log::error!("failed to flush receive request: {err}");
+        return None;
+    }
+    Some(reader)
+}
+
+/// Reads the uri list of a drag while the event loop keeps running,
+/// its files are hovered, or dropped, once the source has sent all of it.
+fn read_paths(
+    conn: &Connection,
+    handle: &LoopHandle<'static, WaywinState>,
+    offer: &WlDataOffer,
+) -> Option<RegistrationToken> {
+    let reader = open_pipe(conn, |fd| offer.receive(URI_LIST.to_owned(), fd))?;
+    let reader = Generic::new(
+        File::from(OwnedFd::from(reader)),
+        Interest::READ,
+        Mode::Level,
+    );
+    let offer = offer.clone();
+    let mut uri_list = Vec::new();
+    let token = handle.insert_source(reader, move |_, reader, state| {
+        let mut chunk = [0; 4096];
+        // readable, so a single read doesn't block
+        match (&**reader).read(&mut chunk) {
+            Ok(0) => {}
+            Ok(read) => {
+                uri_list.extend_from_slice(&chunk[..read]);
+                return Ok(PostAction::Continue);
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => {
+                return Ok(PostAction::Continue);
+            }
+            Err(err) => {
+                log::error!("failed to receive dragged files: {err}");
+                uri_list.clear();
+            }
+        }
+        state.paths_received(&offer, parse_uri_list(&uri_list));
+        Ok(PostAction::Remove)
+    });
+    match token {
+        Ok(token) => Some(token),
+        Err(err) => {
+            log::error!("failed to read dragged files: {err}");
+            None
+        }
+    }
+}
+
+impl WaywinState {
+    /// The uri list of a drag has arrived.
+    fn paths_received(&mut self, offer: &WlDataOffer, paths: Vec<PathBuf>) {
+        let data_device_state = &mut self.data_device_state;
+        let Some(drag) = data_device_state
+            .drag
+            .as_mut()
+            .filter(|drag| drag.offer == *offer)
+        else {
+            return;
+        };
+        drag.reader = None;
+        if drag.dropped {
+            drag.paths = Some(paths);
+            data_device_state
+                .drag
+                .take()
+                .unwrap()
+                .drop_files(&mut self.events);
+            return;
+        }
+        for path in &paths {
+            self.events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::HoveredFile(path.clone()),
+                window_id: drag.window_id,
+            });
+        }
+        drag.paths = Some(paths);
+    }
+}
+
+fn parse_uri_list(uri_list: &[u8]) -> Vec<PathBuf> {
+    String::from_utf8_lossy(uri_list)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|uri| uri.strip_prefix("file://"))
+        // skip the host
+        .filter_map(|uri| uri.find('/').map(|i| &uri[i..]))
+        .map(|path| PathBuf::from(OsString::from_vec(percent_decode(path))))
+        .collect()
+}
+
+fn percent_decode(str: &str) -> Vec<u8> {
+    let bytes = str.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
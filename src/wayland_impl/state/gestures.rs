@@ -0,0 +1,193 @@
+use super::WaywinState;
+use crate::{
+    event::{GesturePhase, WaywinEvent, WindowEvent},
+    SeatId, WindowId,
+};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+    zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+};
+
+/// The gesture in progress, only one can happen at a time.
+pub struct Gesture {
+    window: WindowId,
+    fingers: u32,
+    /// Of the latest pinch update, the end doesn't have one.
+    scale: f64,
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, u32> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureSwipeV1,
+        event: <ZwpPointerGestureSwipeV1 as wayland_client::Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(pointer_state) = state.seat_pointer_state(*data) else {
+            return;
+        };
+        let mut pointer_state = pointer_state.lock().unwrap();
+        let (window, event) = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin {
+                serial: _,
+                time: _,
+                surface,
+                fingers,
+            } => {
+                let window = WindowId(surface.id().as_ptr() as usize);
+                pointer_state.gesture = Some(Gesture {
+                    window,
+                    fingers,
+                    scale: 1.0,
+                });
+                (
+                    window,
+                    WindowEvent::SwipeGesture {
+                        dx: 0.0,
+                        dy: 0.0,
+                        fingers,
+                        phase: GesturePhase::Begin,
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            zwp_pointer_gesture_swipe_v1::Event::Update { time: _, dx, dy } => {
+                let Some(gesture) = &pointer_state.gesture else {
+                    return;
+                };
+                (
+                    gesture.window,
+                    WindowEvent::SwipeGesture {
+                        dx,
+                        dy,
+                        fingers: gesture.fingers,
+                        phase: GesturePhase::Update,
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End {
+                serial: _,
+                time: _,
+                cancelled,
+            } => {
+                let Some(gesture) = pointer_state.gesture.take() else {
+                    return;
+                };
+                (
+                    gesture.window,
+                    WindowEvent::SwipeGesture {
+                        dx: 0.0,
+                        dy: 0.0,
+                        fingers: gesture.fingers,
+                        phase: if cancelled != 0 {
+                            GesturePhase::Cancel
+                        } else {
+                            GesturePhase::End
+                        },
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            _ => unimplemented!(),
+        };
+        state.events.push(WaywinEvent::WindowEvent {
+            event,
+            window_id: window,
+        });
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, u32> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGesturePinchV1,
+        event: <ZwpPointerGesturePinchV1 as wayland_client::Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(pointer_state) = state.seat_pointer_state(*data) else {
+            return;
+        };
+        let mut pointer_state = pointer_state.lock().unwrap();
+        let (window, event) = match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin {
+                serial: _,
+                time: _,
+                surface,
+                fingers,
+            } => {
+                let window = WindowId(surface.id().as_ptr() as usize);
+                pointer_state.gesture = Some(Gesture {
+                    window,
+                    fingers,
+                    scale: 1.0,
+                });
+                (
+                    window,
+                    WindowEvent::PinchGesture {
+                        delta: (0.0, 0.0),
+                        scale: 1.0,
+                        rotation: 0.0,
+                        phase: GesturePhase::Begin,
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                time: _,
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => {
+                let Some(gesture) = &mut pointer_state.gesture else {
+                    return;
+                };
+                gesture.scale = scale;
+                (
+                    gesture.window,
+                    WindowEvent::PinchGesture {
+                        delta: (dx, dy),
+                        scale,
+                        rotation,
+                        phase: GesturePhase::Update,
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End {
+                serial: _,
+                time: _,
+                cancelled,
+            } => {
+                let Some(gesture) = pointer_state.gesture.take() else {
+                    return;
+                };
+                (
+                    gesture.window,
+                    WindowEvent::PinchGesture {
+                        delta: (0.0, 0.0),
+                        scale: gesture.scale,
+                        rotation: 0.0,
+                        phase: if cancelled != 0 {
+                            GesturePhase::Cancel
+                        } else {
+                            GesturePhase::End
+                        },
+                        seat: SeatId(*data),
+                    },
+                )
+            }
+            _ => unimplemented!(),
+        };
+        state.events.push(WaywinEvent::WindowEvent {
+            event,
+            window_id: window,
+        });
+    }
+}
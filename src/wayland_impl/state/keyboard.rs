@@ -1,19 +1,19 @@
 use super::WaywinState;
-use crate::event::{Key, KeyCode, LogicalKey, PhysicalKey, WaywinEvent, WindowEvent};
+use crate::{
+    event::{
+        Key, KeyLocation, KeyModifiers, LockState, LogicalKey, PhysicalKey, WaywinEvent,
+        WindowEvent,
+    },
+    RepeatInfo, SeatId, WindowId,
+};
 use smol_str::SmolStr;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use wayland_client::{
     protocol::wl_keyboard::{self, KeyState, KeymapFormat, WlKeyboard},
     Connection, Dispatch, Proxy, QueueHandle, WEnum,
 };
 use xkbcommon::xkb;
 
-#[derive(Debug, Clone, Copy)]
-pub struct RepeatInfo {
-    pub delay: Duration,
-    pub repeat: Duration,
-}
-
 pub struct RepeatState {
     pub token: calloop::RegistrationToken,
     pub key: xkb::Keycode,
@@ -22,11 +22,32 @@ pub struct RepeatState {
 // members are released by `WaywinState`
 pub struct KeyboardState {
     pub keyboard: Option<WlKeyboard>,
+    /// As sent by the compositor.
     pub repeat_info: Option<RepeatInfo>,
     pub repeat_state: Option<RepeatState>,
-    pub focused_window: Option<usize>,
+    pub focused_window: Option<WindowId>,
     pub xkb_context: xkb::Context,
     pub xkb_state: Option<xkb::State>,
+    /// Last sent with `KeyboardLayoutChanged`.
+    pub layout: Option<xkb::LayoutIndex>,
+    /// Last sent with `ModifiersChanged`.
+    pub last_modifiers: Option<KeyModifiers>,
+    /// Last sent with `LockStateChanged`.
+    pub lock_state: Option<LockState>,
+    /// Dead keys and compose sequences, if the locale has a table.
+    pub compose_state: Option<xkb::compose::State>,
+}
+impl KeyboardState {
+    /// The ones currently held, none without a keymap.
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.xkb_state
+            .as_ref()
+            .map_or(KeyModifiers::empty(), key_modifiers)
+    }
+    /// `repeat_override` is the one set with `Waywin::set_key_repeat`.
+    pub fn repeat_info(&self, repeat_override: Option<Option<RepeatInfo>>) -> Option<RepeatInfo> {
+        repeat_override.unwrap_or(self.repeat_info)
+    }
 }
 impl Drop for KeyboardState {
     fn drop(&mut self) {
@@ -67,10 +88,51 @@ fn xkb_state_key_get_utf8_smol(xkb_state: &xkb::State, key: xkb::Keycode) -> Smo
     }
 }
 
+fn new_compose_state(context: &xkb::Context) -> Option<xkb::compose::State> {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_else(|| "C".into());
+    let Ok(table) =
+        xkb::compose::Table::new_from_locale(context, &locale, xkb::compose::COMPILE_NO_FLAGS)
+    else {
+        log::warn!("no compose table for locale {locale:?}");
+        return None;
+    };
+    Some(xkb::compose::State::new(
+        &table,
+        xkb::compose::STATE_NO_FLAGS,
+    ))
+}
+/// Returns the text a pressed key should have instead of its own,
+/// which is empty while a sequence is still being typed or got cancelled.
+fn compose(compose_state: &mut xkb::compose::State, keysym: xkb::Keysym) -> Option<SmolStr> {
+    if compose_state.feed(keysym) == xkb::compose::FeedResult::Ignored {
+        return None;
+    }
+    match compose_state.status() {
+        xkb::compose::Status::Nothing => None,
+        xkb::compose::Status::Composing => Some(SmolStr::default()),
+        xkb::compose::Status::Composed => {
+            let text = compose_state.utf8().unwrap_or_default();
+            compose_state.reset();
+            Some(text.into())
+        }
+        xkb::compose::Status::Cancelled => {
+            compose_state.reset();
+            Some(SmolStr::default())
+        }
+    }
+}
+
 fn generate_down_event(
     xkb_state: &xkb::State,
     wayland_key: xkb::Keycode,
     key: xkb::Keycode,
+    repeat: bool,
+    timestamp: Duration,
+    seat: SeatId,
 ) -> WindowEvent {
     let layout = xkb_state.key_get_layout(wayland_key);
     let keysym = xkb_state.key_get_one_sym(wayland_key);
@@ -90,11 +152,15 @@ fn generate_down_event(
 
     WindowEvent::Key {
         down: true,
+        repeat,
         physical_key,
         text,
         logical_key,
         text_raw,
         logical_key_unmodified,
+        location: key_location(keysym),
+        timestamp,
+        seat,
     }
 }
 
@@ -102,6 +168,8 @@ fn generate_up_event(
     xkb_state: &xkb::State,
     wayland_key: xkb::Keycode,
     key: xkb::Keycode,
+    timestamp: Duration,
+    seat: SeatId,
 ) -> WindowEvent {
     let layout = xkb_state.key_get_layout(wayland_key);
     let keysym = xkb_state.key_get_one_sym(wayland_key);
@@ -115,43 +183,91 @@ fn generate_up_event(
 
     WindowEvent::Key {
         down: false,
+        repeat: false,
         physical_key,
         text: SmolStr::new_static(""),
         logical_key,
         text_raw: SmolStr::new_static(""),
         logical_key_unmodified,
+        location: key_location(keysym),
+        timestamp,
+        seat,
+    }
+}
+
+fn lock_state(xkb_state: &xkb::State) -> LockState {
+    LockState {
+        caps: xkb_state.led_name_is_active(xkb::LED_NAME_CAPS),
+        num: xkb_state.led_name_is_active(xkb::LED_NAME_NUM),
+        scroll: xkb_state.led_name_is_active(xkb::LED_NAME_SCROLL),
     }
 }
+fn key_modifiers(xkb_state: &xkb::State) -> KeyModifiers {
+    let mut modifiers = KeyModifiers::empty();
+    for (name, modifier) in [
+        (xkb::MOD_NAME_SHIFT, KeyModifiers::SHIFT),
+        (xkb::MOD_NAME_CTRL, KeyModifiers::CTRL),
+        (xkb::MOD_NAME_ALT, KeyModifiers::ALT),
+        (xkb::MOD_NAME_LOGO, KeyModifiers::SUPER),
+        (xkb::MOD_NAME_CAPS, KeyModifiers::CAPS_LOCK),
+        (xkb::MOD_NAME_NUM, KeyModifiers::NUM_LOCK),
+    ] {
+        modifiers.set(
+            modifier,
+            xkb_state.mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE),
+        );
+    }
+    modifiers
+}
 
 impl Default for KeyboardState {
     fn default() -> Self {
+        let xkb_context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
         Self {
             keyboard: None,
             repeat_info: None,
             repeat_state: None,
             focused_window: None,
-            xkb_context: xkb::Context::new(xkb::CONTEXT_NO_FLAGS),
+            compose_state: new_compose_state(&xkb_context),
+            xkb_context,
             xkb_state: None,
+            layout: None,
+            last_modifiers: None,
+            lock_state: None,
         }
     }
 }
 
-impl Dispatch<WlKeyboard, ()> for WaywinState {
+impl Dispatch<WlKeyboard, u32> for WaywinState {
     fn event(
         state: &mut Self,
         _proxy: &WlKeyboard,
         event: <WlKeyboard as wayland_client::Proxy>::Event,
-        _data: &(),
+        data: &u32,
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
+        let seat_name = *data;
+        let Some(keyboard_state) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.global_name == seat_name)
+            .map(|seat| &mut seat.keyboard_state)
+        else {
+            return;
+        };
         match event {
             wl_keyboard::Event::Keymap { format, fd, size } => {
-                state.keyboard_state.xkb_state = None;
+                keyboard_state.xkb_state = None;
+                // the layouts could be entirely different now
+                keyboard_state.layout = None;
+                if let Some(compose_state) = &mut keyboard_state.compose_state {
+                    compose_state.reset();
+                }
                 if let WEnum::Value(KeymapFormat::XkbV1) = format {
                     let keymap = unsafe {
                         xkb::Keymap::new_from_fd(
-                            &state.keyboard_state.xkb_context,
+                            &keyboard_state.xkb_context,
                             fd,
                             size as usize,
                             xkb::KEYMAP_FORMAT_TEXT_V1,
@@ -161,18 +277,19 @@ impl Dispatch<WlKeyboard, ()> for WaywinState {
                         .unwrap()
                     };
                     let xkb_state = xkb::State::new(&keymap);
-                    state.keyboard_state.xkb_state = Some(xkb_state);
+                    keyboard_state.xkb_state = Some(xkb_state);
                 } else {
                     log::warn!("unkown keymap")
                 }
             }
             wl_keyboard::Event::Enter {
-                serial: _,
+                serial,
                 surface,
                 keys: _, // TODO
             } => {
+                state.input_serials.lock().unwrap().set(seat_name, serial);
                 // unfocus old window if it wasn't already
-                if let Some(focused_window) = state.keyboard_state.focused_window {
+                if let Some(focused_window) = keyboard_state.focused_window {
                     log::warn!("focusing new window before unfocusing previous window");
                     state.events.push(WaywinEvent::WindowEvent {
                         event: WindowEvent::Focus(false),
@@ -181,22 +298,29 @@ impl Dispatch<WlKeyboard, ()> for WaywinState {
                 }
 
                 // focus new window
-                let id = surface.id().as_ptr() as usize;
-                state.keyboard_state.focused_window = Some(id);
+                let id = WindowId(surface.id().as_ptr() as usize);
+                // resent to the new window with the next modifiers
+                keyboard_state.last_modifiers = None;
+                keyboard_state.lock_state = None;
+                keyboard_state.focused_window = Some(id);
                 state.events.push(WaywinEvent::WindowEvent {
                     event: WindowEvent::Focus(true),
                     window_id: id,
                 });
             }
             wl_keyboard::Event::Leave { serial: _, surface } => {
-                if let Some(token) = state.keyboard_state.repeat_state.take() {
+                if let Some(token) = keyboard_state.repeat_state.take() {
                     state.handle.remove(token.token);
                 }
-                let id = surface.id().as_ptr() as usize;
-                if Some(id) != state.keyboard_state.focused_window {
-                    log::warn!("unfocusing an unfocused window: {id}");
+                // a sequence shouldn't carry over to the next window
+                if let Some(compose_state) = &mut keyboard_state.compose_state {
+                    compose_state.reset();
+                }
+                let id = WindowId(surface.id().as_ptr() as usize);
+                if Some(id) != keyboard_state.focused_window {
+                    log::warn!("unfocusing an unfocused window: {id:?}");
                 } else {
-                    state.keyboard_state.focused_window = None;
+                    keyboard_state.focused_window = None;
                     state.events.push(WaywinEvent::WindowEvent {
                         event: WindowEvent::Focus(false),
                         window_id: id,
@@ -204,88 +328,161 @@ impl Dispatch<WlKeyboard, ()> for WaywinState {
                 }
             }
             wl_keyboard::Event::Key {
-                serial: _,
-                time: _,
+                serial,
+                time,
                 key,
                 state: WEnum::Value(KeyState::Pressed),
             } => {
+                let timestamp = Duration::from_millis(time as u64);
+                state.input_serials.lock().unwrap().set(seat_name, serial);
                 let wayland_key = xkb::Keycode::new(key + 8);
                 let key = xkb::Keycode::new(key);
 
-                if let Some(token) = state.keyboard_state.repeat_state.take() {
+                if let Some(token) = keyboard_state.repeat_state.take() {
                     state.handle.remove(token.token);
                 }
 
-                let Some(id) = state.keyboard_state.focused_window else {
+                let Some(id) = keyboard_state.focused_window else {
                     log::warn!("recieved a key down event while no window is focused");
                     return;
                 };
 
-                if let Some(xkb_state) = &state.keyboard_state.xkb_state {
-                    let event = generate_down_event(xkb_state, wayland_key, key);
-
+                if let Some(xkb_state) = &keyboard_state.xkb_state {
+                    let mut event = generate_down_event(
+                        xkb_state,
+                        wayland_key,
+                        key,
+                        false,
+                        timestamp,
+                        SeatId(seat_name),
+                    );
+                    if let Some(composed) =
+                        keyboard_state
+                            .compose_state
+                            .as_mut()
+                            .and_then(|compose_state| {
+                                compose(compose_state, xkb_state.key_get_one_sym(wayland_key))
+                            })
+                    {
+                        if let WindowEvent::Key { text, text_raw, .. } = &mut event {
+                            *text = composed.clone();
+                            *text_raw = composed;
+                        }
+                    }
                     state.events.push(WaywinEvent::WindowEvent {
-                        event: event.clone(),
+                        event,
                         window_id: id,
                     });
 
+                    // `state.window` would borrow the keyboard state's seat too
+                    let window = state
+                        .windows
+                        .iter()
+                        .find(|(window_id, _)| *window_id == id)
+                        .and_then(|(_, window)| window.upgrade());
+                    if window.is_some_and(|window| {
+                        window
+                            .lock()
+                            .unwrap()
+                            .release_grab_on(PhysicalKey::from(key))
+                    }) {
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::CursorGrabChanged(false),
+                            window_id: id,
+                        });
+                    }
+
                     if xkb_state.get_keymap().key_repeats(wayland_key) {
-                        if let Some(repeat_info) = &state.keyboard_state.repeat_info {
+                        let mut event = generate_down_event(
+                            xkb_state,
+                            wayland_key,
+                            key,
+                            true,
+                            timestamp,
+                            SeatId(seat_name),
+                        );
+                        if let Some(repeat_info) = keyboard_state.repeat_info(state.repeat_override)
+                        {
+                            // repeats are made up here so their time is too
+                            let pressed_at = Instant::now();
                             let timer = calloop::timer::Timer::from_duration(repeat_info.delay);
-                            let token = state
-                                .handle
-                                .insert_source(timer, move |_, _, state| {
-                                    let Some(id) = state.keyboard_state.focused_window else {
-                                        log::warn!(
-                                            "tried a key repeat event while no window is focused"
-                                        );
-                                        return calloop::timer::TimeoutAction::Drop;
-                                    };
-
-                                    if let Some(repeat_info) = state.keyboard_state.repeat_info {
-                                        state.events.push(WaywinEvent::WindowEvent {
-                                            event: event.clone(),
-                                            window_id: id,
-                                        });
-
-                                        calloop::timer::TimeoutAction::ToDuration(
-                                            repeat_info.repeat,
-                                        )
-                                    } else {
-                                        calloop::timer::TimeoutAction::Drop
+                            let token = state.handle.insert_source(timer, move |_, _, state| {
+                                let Some(keyboard_state) = state
+                                    .seats
+                                    .iter()
+                                    .find(|seat| seat.global_name == seat_name)
+                                    .map(|seat| &seat.keyboard_state)
+                                else {
+                                    return calloop::timer::TimeoutAction::Drop;
+                                };
+                                let Some(id) = keyboard_state.focused_window else {
+                                    log::warn!(
+                                        "tried a key repeat event while no window is focused"
+                                    );
+                                    return calloop::timer::TimeoutAction::Drop;
+                                };
+
+                                if let Some(repeat_info) =
+                                    keyboard_state.repeat_info(state.repeat_override)
+                                {
+                                    if let WindowEvent::Key {
+                                        timestamp: repeat_timestamp,
+                                        ..
+                                    } = &mut event
+                                    {
+                                        *repeat_timestamp = timestamp + pressed_at.elapsed();
                                     }
-                                })
-                                .unwrap();
-                            state.keyboard_state.repeat_state = Some(RepeatState { token, key });
+                                    state.events.push(WaywinEvent::WindowEvent {
+                                        event: event.clone(),
+                                        window_id: id,
+                                    });
+
+                                    calloop::timer::TimeoutAction::ToDuration(repeat_info.repeat)
+                                } else {
+                                    calloop::timer::TimeoutAction::Drop
+                                }
+                            });
+                            match token {
+                                Ok(token) => {
+                                    keyboard_state.repeat_state = Some(RepeatState { token, key });
+                                }
+                                // the key is still pressed once, it just doesn't repeat
+                                Err(err) => log::error!("failed to insert key repeat timer: {err}"),
+                            }
                         }
                     }
                 }
             }
             wl_keyboard::Event::Key {
                 serial: _,
-                time: _,
+                time,
                 key,
                 state: WEnum::Value(KeyState::Released),
             } => {
                 let wayland_key = xkb::Keycode::new(key + 8);
                 let key = xkb::Keycode::new(key);
 
-                let Some(id) = state.keyboard_state.focused_window else {
+                let Some(id) = keyboard_state.focused_window else {
                     log::warn!("recieved a key up event while no window is focused");
                     return;
                 };
 
                 // remove repeat callback if keycode is the same
-                if let Some(repeat_state) = state
-                    .keyboard_state
+                if let Some(repeat_state) = keyboard_state
                     .repeat_state
                     .take_if(|token| token.key == key)
                 {
                     state.handle.remove(repeat_state.token);
                 }
 
-                if let Some(xkb_state) = &state.keyboard_state.xkb_state {
-                    let kind = generate_up_event(xkb_state, wayland_key, key);
+                if let Some(xkb_state) = &keyboard_state.xkb_state {
+                    let kind = generate_up_event(
+                        xkb_state,
+                        wayland_key,
+                        key,
+                        Duration::from_millis(time as u64),
+                        SeatId(seat_name),
+                    );
 
                     state.events.push(WaywinEvent::WindowEvent {
                         event: kind.clone(),
@@ -308,48 +505,47 @@ impl Dispatch<WlKeyboard, ()> for WaywinState {
                 mods_locked,
                 group,
             } => {
-                if let Some(xkb_state) = &mut state.keyboard_state.xkb_state {
+                if let Some(xkb_state) = &mut keyboard_state.xkb_state {
                     xkb_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                    let layout = xkb_state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE);
+                    let layout_changed = keyboard_state.layout.replace(layout) != Some(layout);
 
-                    // let Some(id) = state.keyboard.focused_window else {
-                    //     log::warn!("recieved key modifiers event while no window is focused");
-                    //     return;
-                    // };
-
-                    // let key_modifiers = if xkb_state
-                    //     .mod_name_is_active(xkb::MOD_NAME_SHIFT, xkb::STATE_MODS_EFFECTIVE)
-                    // {
-                    //     KeyModifiers::SHIFT
-                    // } else {
-                    //     KeyModifiers::empty()
-                    // } | if xkb_state
-                    //     .mod_name_is_active(xkb::MOD_NAME_CTRL, xkb::STATE_MODS_EFFECTIVE)
-                    // {
-                    //     KeyModifiers::CTRL
-                    // } else {
-                    //     KeyModifiers::empty()
-                    // } | if xkb_state
-                    //     .mod_name_is_active(xkb::MOD_NAME_ALT, xkb::STATE_MODS_EFFECTIVE)
-                    // {
-                    //     KeyModifiers::ALT
-                    // } else {
-                    //     KeyModifiers::empty()
-                    // };
-
-                    // state.events.push(WindowEvent {
-                    //     kind: Event::KeyModifiers(key_modifiers),
-                    //     window_id: id,
-                    // });
+                    let Some(id) = keyboard_state.focused_window else {
+                        log::warn!("recieved key modifiers event while no window is focused");
+                        return;
+                    };
+
+                    let modifiers = key_modifiers(xkb_state);
+                    if keyboard_state.last_modifiers.replace(modifiers) != Some(modifiers) {
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::ModifiersChanged(modifiers),
+                            window_id: id,
+                        });
+                    }
+                    let lock_state = lock_state(xkb_state);
+                    if keyboard_state.lock_state.replace(lock_state) != Some(lock_state) {
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::LockStateChanged(lock_state),
+                            window_id: id,
+                        });
+                    }
+                    if layout_changed {
+                        let name = xkb_state.get_keymap().layout_get_name(layout).to_owned();
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: WindowEvent::KeyboardLayoutChanged { name },
+                            window_id: id,
+                        });
+                    }
                 }
             }
             wl_keyboard::Event::RepeatInfo { rate, delay } => {
                 if rate == 0 {
-                    state.keyboard_state.repeat_info = None;
-                    if let Some(repeat_state) = state.keyboard_state.repeat_state.take() {
+                    keyboard_state.repeat_info = None;
+                    if let Some(repeat_state) = keyboard_state.repeat_state.take() {
                         state.handle.remove(repeat_state.token);
                     }
                 } else {
-                    state.keyboard_state.repeat_info = Some(RepeatInfo {
+                    keyboard_state.repeat_info = Some(RepeatInfo {
                         delay: Duration::from_millis(delay as u64),
                         repeat: Duration::from_millis(1000 / rate as u64),
                     });
@@ -362,120 +558,28 @@ impl Dispatch<WlKeyboard, ()> for WaywinState {
 
 impl From<xkb::Keycode> for PhysicalKey {
     fn from(value: xkb::Keycode) -> Self {
-        Self::KeyCode(match value.raw() {
-            15 => KeyCode::Tab,
-            105 => KeyCode::LeftArrow,
-            106 => KeyCode::RightArrow,
-            103 => KeyCode::UpArrow,
-            108 => KeyCode::DownArrow,
-            104 => KeyCode::PageUp,
-            109 => KeyCode::PageDown,
-            102 => KeyCode::Home,
-            107 => KeyCode::End,
-            110 => KeyCode::Insert,
-            111 => KeyCode::Delete,
-            14 => KeyCode::Backspace,
-            57 => KeyCode::Space,
-            28 => KeyCode::Enter,
-            1 => KeyCode::Escape,
-            29 => KeyCode::LCtrl,
-            42 => KeyCode::LShift,
-            56 => KeyCode::LAlt,
-            125 => KeyCode::LSuper,
-            97 => KeyCode::RCtrl,
-            54 => KeyCode::RShift,
-            99 => KeyCode::RAlt,
-            126 => KeyCode::RSuper,
-            127 => KeyCode::Menu,
-            2 => KeyCode::Key1,
-            3 => KeyCode::Key2,
-            4 => KeyCode::Key3,
-            5 => KeyCode::Key4,
-            6 => KeyCode::Key5,
-            7 => KeyCode::Key6,
-            8 => KeyCode::Key7,
-            9 => KeyCode::Key8,
-            10 => KeyCode::Key9,
-            11 => KeyCode::Key0,
-            79 => KeyCode::Numpad1,
-            80 => KeyCode::Numpad2,
-            81 => KeyCode::Numpad3,
-            75 => KeyCode::Numpad4,
-            76 => KeyCode::Numpad5,
-            77 => KeyCode::Numpad6,
-            71 => KeyCode::Numpad7,
-            72 => KeyCode::Numpad8,
-            73 => KeyCode::Numpad9,
-            82 => KeyCode::Numpad0,
-            83 => KeyCode::NumpadDecimal,
-            98 => KeyCode::NumpadDivide,
-            55 => KeyCode::NumpadMultiply,
-            74 => KeyCode::NumpadSubtract,
-            78 => KeyCode::NumpadAdd,
-            96 => KeyCode::NumpadEnter,
-            30 => KeyCode::A,
-            48 => KeyCode::B,
-            46 => KeyCode::C,
-            32 => KeyCode::D,
-            18 => KeyCode::E,
-            33 => KeyCode::F,
-            34 => KeyCode::G,
-            35 => KeyCode::H,
-            23 => KeyCode::I,
-            36 => KeyCode::J,
-            37 => KeyCode::K,
-            38 => KeyCode::L,
-            50 => KeyCode::M,
-            49 => KeyCode::N,
-            24 => KeyCode::O,
-            25 => KeyCode::P,
-            16 => KeyCode::Q,
-            19 => KeyCode::R,
-            31 => KeyCode::S,
-            20 => KeyCode::T,
-            22 => KeyCode::U,
-            47 => KeyCode::V,
-            17 => KeyCode::W,
-            45 => KeyCode::X,
-            21 => KeyCode::Y,
-            44 => KeyCode::Z,
-            59 => KeyCode::F1,
-            60 => KeyCode::F2,
-            61 => KeyCode::F3,
-            62 => KeyCode::F4,
-            63 => KeyCode::F5,
-            64 => KeyCode::F6,
-            65 => KeyCode::F7,
-            66 => KeyCode::F8,
-            67 => KeyCode::F9,
-            68 => KeyCode::F10,
-            87 => KeyCode::F11,
-            88 => KeyCode::F12,
-
-            39 => KeyCode::Semicolon,
-            40 => KeyCode::Quote,
-
-            51 => KeyCode::Comma,
-            52 => KeyCode::Period,
-            53 => KeyCode::Slash,
-
-            12 => KeyCode::Minus,
-            13 => KeyCode::Equal,
-
-            26 => KeyCode::LBracket,
-            27 => KeyCode::RBracket,
-            43 => KeyCode::Backslash,
-
-            41 => KeyCode::Grave,
-            58 => KeyCode::CapsLock,
-            70 => KeyCode::ScrollLock,
-            69 => KeyCode::NumLock,
-
-            100 => KeyCode::PrintScreen,
-            119 => KeyCode::Pause,
-
-            _ => return Self::Unknown(value.raw()),
-        })
+        Self::from_evdev(value.raw())
+    }
+}
+fn key_location(keysym: xkb::Keysym) -> KeyLocation {
+    match keysym {
+        xkb::Keysym::Shift_L
+        | xkb::Keysym::Control_L
+        | xkb::Keysym::Alt_L
+        | xkb::Keysym::Meta_L
+        | xkb::Keysym::Super_L
+        | xkb::Keysym::Hyper_L => KeyLocation::Left,
+        xkb::Keysym::Shift_R
+        | xkb::Keysym::Control_R
+        | xkb::Keysym::Alt_R
+        | xkb::Keysym::Meta_R
+        | xkb::Keysym::Super_R
+        | xkb::Keysym::Hyper_R => KeyLocation::Right,
+        // the keypad keysyms are all in one block, whatever num lock is set to
+        _ if (xkb::Keysym::KP_Space..=xkb::Keysym::KP_Equal).contains(&keysym) => {
+            KeyLocation::Numpad
+        }
+        _ => KeyLocation::Standard,
     }
 }
 impl From<xkb::Keysym> for LogicalKey {
@@ -565,6 +669,35 @@ impl From<xkb::Keysym> for LogicalKey {
             xkb::Keysym::F10 => Key::F10,
             xkb::Keysym::F11 => Key::F11,
             xkb::Keysym::F12 => Key::F12,
+            xkb::Keysym::F13 => Key::F13,
+            xkb::Keysym::F14 => Key::F14,
+            xkb::Keysym::F15 => Key::F15,
+            xkb::Keysym::F16 => Key::F16,
+            xkb::Keysym::F17 => Key::F17,
+            xkb::Keysym::F18 => Key::F18,
+            xkb::Keysym::F19 => Key::F19,
+            xkb::Keysym::F20 => Key::F20,
+            xkb::Keysym::F21 => Key::F21,
+            xkb::Keysym::F22 => Key::F22,
+            xkb::Keysym::F23 => Key::F23,
+            xkb::Keysym::F24 => Key::F24,
+
+            xkb::Keysym::XF86_AudioPlay => Key::MediaPlayPause,
+            xkb::Keysym::XF86_AudioPause => Key::MediaPause,
+            xkb::Keysym::XF86_AudioStop => Key::MediaStop,
+            xkb::Keysym::XF86_AudioNext => Key::MediaNextTrack,
+            xkb::Keysym::XF86_AudioPrev => Key::MediaPrevTrack,
+            xkb::Keysym::XF86_AudioRaiseVolume => Key::VolumeUp,
+            xkb::Keysym::XF86_AudioLowerVolume => Key::VolumeDown,
+            xkb::Keysym::XF86_AudioMute => Key::VolumeMute,
+
+            xkb::Keysym::XF86_Back => Key::BrowserBack,
+            xkb::Keysym::XF86_Forward => Key::BrowserForward,
+            xkb::Keysym::XF86_Reload => Key::BrowserRefresh,
+            xkb::Keysym::XF86_Stop => Key::BrowserStop,
+            xkb::Keysym::XF86_Search => Key::BrowserSearch,
+            xkb::Keysym::XF86_Favorites => Key::BrowserFavorites,
+            xkb::Keysym::XF86_HomePage => Key::BrowserHome,
 
             xkb::Keysym::KP_Enter => Key::NumpadEnter,
 
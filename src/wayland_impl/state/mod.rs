@@ -1,46 +1,107 @@
-use crate::event::WaywinEvent;
-use keyboard::KeyboardState;
+use crate::{
+    event::{WaywinEvent, WindowEvent},
+    Error, RepeatInfo, WindowId,
+};
+use data_device::DataDeviceState;
+use output::OutputState;
 use pointer::PointerState;
+use primary_selection::PrimarySelectionState;
+use seat::{InputSerials, Seat};
 use std::{
-    ops::Deref,
+    collections::HashMap,
+    ops::{Deref, RangeInclusive},
     sync::{Arc, Mutex, Weak},
+    time::Instant,
 };
+use text_input::TextInputState;
 use wayland_client::{
     globals::registry_queue_init,
-    protocol::{wl_compositor::WlCompositor, wl_seat::WlSeat},
-    Connection, EventQueue, QueueHandle,
+    protocol::{
+        wl_compositor::WlCompositor, wl_data_device_manager::WlDataDeviceManager,
+        wl_output::WlOutput, wl_seat::WlSeat, wl_shm::WlShm,
+    },
+    Connection, EventQueue, Proxy, QueueHandle,
 };
 use wayland_protocols::{
     wp::{
         fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+        idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+        keyboard_shortcuts_inhibit::zv1::client::zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1,
+        pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        presentation_time::client::wp_presentation::WpPresentation,
+        relative_pointer::zv1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
         viewporter::client::wp_viewporter::WpViewporter,
     },
     xdg::{
+        activation::v1::client::xdg_activation_v1::XdgActivationV1,
         decoration::zv1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1,
-        shell::client::xdg_wm_base::XdgWmBase,
+        dialog::v1::client::xdg_wm_dialog_v1::XdgWmDialogV1, shell::client::xdg_wm_base::XdgWmBase,
     },
 };
 
+pub mod data_device;
+mod gestures;
 mod keyboard;
+pub mod output;
 pub mod pointer;
+mod presentation;
+pub mod primary_selection;
 mod proxy;
+pub mod seat;
+pub mod text_input;
+mod touch;
+
+/// The versions of the required globals that are bound, also reported if binding fails.
+const COMPOSITOR_VERSIONS: RangeInclusive<u32> = 1..=6;
+const XDG_WM_BASE_VERSIONS: RangeInclusive<u32> = 1..=7;
+
+/// Enough for a busy frame of input, the event buffers only grow past it under heavier load.
+pub const EVENTS_CAPACITY: usize = 256;
+
+/// Set with `Window::set_event_handler`, they get their window's events instead of the event hook.
+pub type EventHandlers = Arc<Mutex<HashMap<WindowId, Box<dyn FnMut(WindowEvent) + Send>>>>;
 
 pub struct WaywinState {
     pub compositor: WlCompositor,
     pub xdg_wm_base: Arc<OwnedXdgWmBase>,
-    pub seat: WlSeat,
     pub decoration: Option<ZxdgDecorationManagerV1>,
     pub viewporter: Option<WpViewporter>,
     pub scaling: Option<WpFractionalScaleManagerV1>,
+    pub shm: Option<WlShm>,
+    pub presentation: Option<WpPresentation>,
+    pub idle_inhibit_manager: Option<ZwpIdleInhibitManagerV1>,
+    pub shortcuts_inhibit_manager: Option<ZwpKeyboardShortcutsInhibitManagerV1>,
+    pub dialog_manager: Option<XdgWmDialogV1>,
+    pub activation: Option<XdgActivationV1>,
+
+    pub relative_pointer_manager: Option<ZwpRelativePointerManagerV1>,
+    pub pointer_gestures: Option<ZwpPointerGesturesV1>,
 
-    pub keyboard_state: KeyboardState,
+    /// Come and go like outputs, the first one is the one windows use.
+    pub seats: Vec<Seat>,
+    /// Of the first seat, cursors and pointer grabs apply to it.
     pub pointer_state: Arc<Mutex<PointerState>>,
+    /// Set with `Waywin::set_key_repeat`, takes precedence over what each keyboard was sent.
+    pub repeat_override: Option<Option<RepeatInfo>>,
+    pub text_input_state: Arc<Mutex<TextInputState>>,
+    pub data_device_state: DataDeviceState,
+    pub output_state: Arc<Mutex<OutputState>>,
+    pub primary_selection_state: PrimarySelectionState,
+
+    pub input_serials: Arc<Mutex<InputSerials>>,
+    /// The clipboard's devices belong to it.
+    pub clipboard_seat: u32,
 
     pub qhandle: QueueHandle<Self>,
     pub connection: Connection,
     pub app_id: String,
 
-    pub windows: Vec<Weak<Mutex<super::window::WindowState>>>,
+    /// The id is kept around to send `Destroyed` once the window is gone.
+    pub windows: Vec<(WindowId, Weak<Mutex<super::window::WindowState>>)>,
+    /// Wake up timers for the windows' redraw deadlines and the deadline each is for.
+    /// Kept here rather than by the windows, which can't hold on to the loop handle.
+    pub redraw_timers: HashMap<WindowId, (Instant, calloop::RegistrationToken)>,
+    pub event_handlers: EventHandlers,
     pub handle: calloop::LoopHandle<'static, Self>,
 
     pub events: Vec<WaywinEvent>,
@@ -48,59 +109,130 @@ pub struct WaywinState {
 impl WaywinState {
     pub fn new(
         instance: &str,
+        connection: Connection,
         handle: calloop::LoopHandle<'static, Self>,
-    ) -> Result<(Self, EventQueue<Self>), String> {
-        let connection = Connection::connect_to_env()
-            .map_err(|err| format!("failed to connect to wayland: {err}"))?;
-
-        let (globals, event_queue) = registry_queue_init(&connection).unwrap();
+    ) -> Result<(Self, EventQueue<Self>), Error> {
+        let (globals, event_queue) =
+            registry_queue_init(&connection).map_err(|err| Error::Connection(err.to_string()))?;
 
         let qhandle = event_queue.handle();
 
         let compositor = globals
-            .bind(&qhandle, 1..=6, ())
-            .map_err(|err| format!("failed to bind WlCompositor: {err}"))?;
+            .bind(&qhandle, COMPOSITOR_VERSIONS, ())
+            .map_err(|_| Error::GlobalBind {
+                interface: "wl_compositor",
+                versions: COMPOSITOR_VERSIONS,
+            })?;
         let xdg_wm_base = globals
-            .bind(&qhandle, 1..=7, ())
-            .map_err(|err| format!("failed to bind XdgWmBase: {err}"))?;
-        let seat = globals
-            .bind(&qhandle, 1..=9, ())
-            .map_err(|err| format!("failed to bind WlSeat: {err}"))?;
+            .bind(&qhandle, XDG_WM_BASE_VERSIONS, ())
+            .map_err(|_| Error::GlobalBind {
+                interface: "xdg_wm_base",
+                versions: XDG_WM_BASE_VERSIONS,
+            })?;
         let decoration = globals.bind(&qhandle, 1..=1, ()).ok();
         let viewporter = globals.bind(&qhandle, 1..=1, ()).ok();
         let scaling = globals.bind(&qhandle, 1..=1, ()).ok();
+        let shm = globals.bind(&qhandle, 1..=1, ()).ok();
+        let presentation = globals.bind(&qhandle, 1..=1, ()).ok();
+        let idle_inhibit_manager = globals.bind(&qhandle, 1..=1, ()).ok();
+        let shortcuts_inhibit_manager = globals.bind(&qhandle, 1..=1, ()).ok();
+        let dialog_manager = globals.bind(&qhandle, 1..=1, ()).ok();
+        let activation = globals.bind(&qhandle, 1..=1, ()).ok();
 
         let relative_pointer_manager = globals.bind(&qhandle, 1..=1, ()).ok();
-        let pointer_constraints = globals.bind(&qhandle, 1..=1, ()).ok();
+        let pointer_gestures = globals.bind(&qhandle, 1..=2, ()).ok();
+        let text_input_manager = globals.bind(&qhandle, 1..=1, ()).ok();
+
+        // outputs and seats come and go, the rest are bound by `Dispatch<WlRegistry>`
+        let mut output_state = OutputState::default();
+        let mut seats = vec![];
+        let input_serials = Arc::new(Mutex::new(InputSerials::default()));
+        globals.contents().with_list(|list| {
+            for global in list {
+                if global.interface == WlOutput::interface().name {
+                    output_state.add(globals.registry(), global.name, global.version, &qhandle);
+                } else if global.interface == WlSeat::interface().name {
+                    seats.push(Seat::new(
+                        globals.registry(),
+                        global.name,
+                        global.version,
+                        &qhandle,
+                        &input_serials,
+                    ));
+                }
+            }
+        });
+        let Some(seat) = seats.first() else {
+            return Err(Error::NoSeat);
+        };
+        let clipboard_seat = seat.global_name;
+        let pointer_state = seat.pointer_state.clone();
+        pointer_state.lock().unwrap().pointer_constraints = globals.bind(&qhandle, 1..=1, ()).ok();
+        let seat = seat.seat.clone();
+
+        let mut primary_selection_state =
+            PrimarySelectionState::new(globals.bind(&qhandle, 1..=1, ()).ok());
+        primary_selection_state.device = primary_selection_state
+            .manager
+            .as_ref()
+            .map(|manager| manager.get_device(&seat, &qhandle, ()));
+
+        let mut data_device_state = DataDeviceState::new(globals.bind(&qhandle, 1..=3, ()).ok());
+        data_device_state.data_device = data_device_state
+            .data_device_manager
+            .as_ref()
+            .map(|manager: &WlDataDeviceManager| manager.get_data_device(&seat, &qhandle, ()));
 
         Ok((
             Self {
                 compositor,
                 xdg_wm_base: Arc::new(OwnedXdgWmBase(xdg_wm_base)),
-                seat,
                 decoration,
                 viewporter,
                 scaling,
+                shm,
+                presentation,
+                idle_inhibit_manager,
+                shortcuts_inhibit_manager,
+                dialog_manager,
+                activation,
 
-                pointer_state: Arc::new(Mutex::new(PointerState {
-                    pointer: None,
-                    relative_pointer: None,
-                    focused_window: None,
-                    relative_pointer_manager,
-                    pointer_constraints,
-                })),
-                keyboard_state: KeyboardState::default(),
+                relative_pointer_manager,
+                pointer_gestures,
+                seats,
+                pointer_state,
+                repeat_override: None,
+                data_device_state,
+                output_state: Arc::new(Mutex::new(output_state)),
+                primary_selection_state,
+                text_input_state: Arc::new(Mutex::new(TextInputState::new(text_input_manager))),
 
+                input_serials,
+                clipboard_seat,
                 connection,
                 qhandle,
                 app_id: instance.to_owned(),
                 windows: vec![],
+                redraw_timers: HashMap::new(),
+                event_handlers: EventHandlers::default(),
                 handle,
-                events: vec![],
+                events: Vec::with_capacity(EVENTS_CAPACITY),
             },
             event_queue,
         ))
     }
+    pub fn seat_pointer_state(&self, global_name: u32) -> Option<Arc<Mutex<PointerState>>> {
+        self.seats
+            .iter()
+            .find(|seat| seat.global_name == global_name)
+            .map(|seat| seat.pointer_state.clone())
+    }
+    pub fn window(&self, id: WindowId) -> Option<Arc<Mutex<super::window::WindowState>>> {
+        self.windows
+            .iter()
+            .find(|(window_id, _)| *window_id == id)
+            .and_then(|(_, window)| window.upgrade())
+    }
 }
 impl Drop for WaywinState {
     fn drop(&mut self) {
@@ -113,7 +245,29 @@ impl Drop for WaywinState {
         if let Some(s) = self.decoration.take() {
             s.destroy();
         }
-        self.seat.release();
+        if let Some(s) = self.presentation.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.idle_inhibit_manager.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.shortcuts_inhibit_manager.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.dialog_manager.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.activation.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.relative_pointer_manager.take() {
+            s.destroy();
+        }
+        if let Some(s) = self.pointer_gestures.take() {
+            if s.version() >= 2 {
+                s.release();
+            }
+        }
 
         // should be destroyed automatically when it can
         // self.xdg_wm_base.destroy();
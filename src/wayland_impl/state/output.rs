@@ -0,0 +1,165 @@
+use super::WaywinState;
+use crate::{wayland_impl::Monitor, MonitorId, VideoMode};
+use wayland_client::{
+    protocol::{
+        wl_output::{self, Mode, WlOutput},
+        wl_registry::WlRegistry,
+    },
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+
+pub fn monitor_id(output: &WlOutput) -> MonitorId {
+    MonitorId(output.id().as_ptr() as usize)
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputInfo {
+    pub name: Option<String>,
+    pub position: (i32, i32),
+    /// Of the current mode.
+    pub size: (u32, u32),
+    pub scale: i32,
+    pub modes: Vec<VideoMode>,
+}
+impl Default for OutputInfo {
+    fn default() -> Self {
+        Self {
+            name: None,
+            position: (0, 0),
+            size: (0, 0),
+            scale: 1,
+            modes: vec![],
+        }
+    }
+}
+
+pub struct Output {
+    /// The name of the global in the registry.
+    pub global_name: u32,
+    pub output: WlOutput,
+    pub info: OutputInfo,
+    /// Output events are applied together on `Done`.
+    pending: OutputInfo,
+}
+
+#[derive(Default)]
+pub struct OutputState {
+    pub outputs: Vec<Output>,
+}
+impl OutputState {
+    pub fn add(
+        &mut self,
+        registry: &WlRegistry,
+        global_name: u32,
+        version: u32,
+        qhandle: &QueueHandle<WaywinState>,
+    ) {
+        let output = registry.bind(global_name, version.min(4), qhandle, ());
+        self.outputs.push(Output {
+            global_name,
+            output,
+            info: OutputInfo::default(),
+            pending: OutputInfo::default(),
+        });
+    }
+    /// Returns the output that was removed.
+    pub fn remove(&mut self, global_name: u32) -> Option<WlOutput> {
+        let index = self
+            .outputs
+            .iter()
+            .position(|output| output.global_name == global_name)?;
+        let output = self.outputs.remove(index).output;
+        if output.version() >= 3 {
+            output.release();
+        }
+        Some(output)
+    }
+    pub fn monitor(&self, output: &WlOutput) -> Option<Monitor> {
+        self.outputs
+            .iter()
+            .find(|o| o.output == *output)
+            .map(Monitor::new)
+    }
+    pub fn output(&self, id: MonitorId) -> Option<WlOutput> {
+        self.outputs
+            .iter()
+            .find(|o| monitor_id(&o.output) == id)
+            .map(|o| o.output.clone())
+    }
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.outputs.iter().map(Monitor::new).collect()
+    }
+}
+
+impl Dispatch<WlOutput, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlOutput,
+        event: <WlOutput as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let mut output_state = state.output_state.lock().unwrap();
+        let Some(output) = output_state
+            .outputs
+            .iter_mut()
+            .find(|output| output.output == *proxy)
+        else {
+            return;
+        };
+
+        match event {
+            wl_output::Event::Geometry { x, y, .. } => {
+                output.pending.position = (x, y);
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                refresh,
+            } => {
+                let size = (width as u32, height as u32);
+                let current = matches!(flags, WEnum::Value(flags) if flags.contains(Mode::Current));
+                if current {
+                    output.pending.size = size;
+                    for mode in &mut output.pending.modes {
+                        mode.current = false;
+                    }
+                }
+
+                // a mode is sent again when it becomes the current one
+                let refresh_rate_millihertz = refresh.max(0) as u32;
+                let pending = &mut output.pending.modes;
+                match pending.iter_mut().find(|mode| {
+                    mode.size == size && mode.refresh_rate_millihertz == refresh_rate_millihertz
+                }) {
+                    Some(mode) => mode.current = current,
+                    None => pending.push(VideoMode {
+                        size,
+                        bit_depth: None,
+                        refresh_rate_millihertz,
+                        current,
+                        monitor: monitor_id(proxy),
+                    }),
+                }
+            }
+            wl_output::Event::Scale { factor } => {
+                output.pending.scale = factor;
+            }
+            wl_output::Event::Name { name } => {
+                output.pending.name = Some(name);
+            }
+            wl_output::Event::Description { description: _ } => {}
+            wl_output::Event::Done => {
+                output.info = output.pending.clone();
+            }
+            _ => unimplemented!(),
+        }
+
+        // version 1 has no `Done`
+        if proxy.version() < 2 {
+            output.info = output.pending.clone();
+        }
+    }
+}
@@ -1,55 +1,181 @@
-use super::WaywinState;
-use crate::event::{DeviceEvent, PointerButton, ScrollDirection, WaywinEvent, WindowEvent};
+use super::{gestures::Gesture, WaywinState};
+use crate::{
+    event::{
+        ClickCounter, DeviceEvent, KeyModifiers, PointerButton, ScrollDelta, ScrollSource,
+        WaywinEvent, WindowEvent,
+    },
+    SeatId, WindowId,
+};
+use std::time::Duration;
 use wayland_client::{
-    protocol::wl_pointer::{Axis, ButtonState, WlPointer},
+    protocol::wl_pointer::{Axis, AxisSource, ButtonState, WlPointer},
     Connection, Dispatch, Proxy, QueueHandle, WEnum,
 };
 use wayland_protocols::wp::{
     pointer_constraints::zv1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1,
-    relative_pointer::zv1::client::{
-        zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
-        zwp_relative_pointer_v1::{self, ZwpRelativePointerV1},
+    pointer_gestures::zv1::client::{
+        zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+        zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
     },
+    relative_pointer::zv1::client::zwp_relative_pointer_v1::{self, ZwpRelativePointerV1},
 };
 
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+/// In logical pixels on either axis.
+const DOUBLE_CLICK_DISTANCE: f64 = 4.0;
+
 #[derive(Default)]
 pub struct PointerState {
     pub pointer: Option<WlPointer>,
     pub relative_pointer: Option<ZwpRelativePointerV1>,
-    pub relative_pointer_manager: Option<ZwpRelativePointerManagerV1>,
+    /// Only set for the first seat.
     pub pointer_constraints: Option<ZwpPointerConstraintsV1>,
-    pub focused_window: Option<usize>,
+    pub swipe_gesture: Option<ZwpPointerGestureSwipeV1>,
+    pub pinch_gesture: Option<ZwpPointerGesturePinchV1>,
+    pub gesture: Option<Gesture>,
+    pub focused_window: Option<WindowId>,
+    /// Needed to set the cursor.
+    pub enter_serial: u32,
+    /// Last position inside `focused_window`.
+    pub position: (f64, f64),
+    /// Scroll collected since the last `Frame`.
+    pub scroll: Option<PendingScroll>,
+    /// Only the latest motion of a frame is sent.
+    pub motion: bool,
+    /// Of the latest event that had one, `Enter` doesn't.
+    pub time: u32,
+    /// Reset when entering a window.
+    pub clicks: ClickCounter,
 }
-impl Drop for PointerState {
-    fn drop(&mut self) {
+impl PointerState {
+    pub fn release_devices(&mut self) {
         if let Some(s) = self.pointer.take() {
             s.release()
         }
         if let Some(s) = self.relative_pointer.take() {
             s.destroy()
         }
-        if let Some(s) = self.relative_pointer_manager.take() {
+        if let Some(s) = self.swipe_gesture.take() {
             s.destroy()
         }
+        if let Some(s) = self.pinch_gesture.take() {
+            s.destroy()
+        }
+        self.gesture = None;
+    }
+    /// Sends the motion held back so far, so it stays in order with other pointer events.
+    fn flush_motion(&mut self, events: &mut Vec<WaywinEvent>, seat: SeatId) {
+        if let Some(id) = self.focused_window.filter(|_| self.motion) {
+            events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::PointerMoved {
+                    x: self.position.0,
+                    y: self.position.1,
+                    timestamp: Duration::from_millis(self.time as u64),
+                    seat,
+                },
+                window_id: id,
+            });
+        }
+        self.motion = false;
+    }
+}
+
+#[derive(Default)]
+pub struct PendingScroll {
+    /// In logical pixels.
+    delta: (f64, f64),
+    /// In notches, only sent for wheels.
+    discrete: Option<(f64, f64)>,
+    source: Option<ScrollSource>,
+    stop: bool,
+    time: u32,
+}
+impl PendingScroll {
+    fn axis(&mut self, axis: Axis) -> &mut f64 {
+        match axis {
+            Axis::HorizontalScroll => &mut self.delta.0,
+            _ => &mut self.delta.1,
+        }
+    }
+    fn discrete_axis(&mut self, axis: Axis) -> &mut f64 {
+        let discrete = self.discrete.get_or_insert((0.0, 0.0));
+        match axis {
+            Axis::HorizontalScroll => &mut discrete.0,
+            _ => &mut discrete.1,
+        }
+    }
+    /// A frame can stop one axis while the other keeps moving,
+    /// the stop is split off then so it's sent after the motion without any of its own.
+    fn split_stop(&mut self) -> Option<Self> {
+        let motion = self.delta != (0.0, 0.0) || self.discrete.is_some();
+        if !self.stop || !motion {
+            return None;
+        }
+        self.stop = false;
+        Some(Self {
+            source: self.source,
+            stop: true,
+            time: self.time,
+            ..Self::default()
+        })
+    }
+    /// With `shift` held a vertical wheel scrolls horizontally, like on windows.
+    fn into_event(mut self, shift: bool, seat: SeatId) -> WindowEvent {
+        let wheel = self.source == Some(ScrollSource::Wheel) || self.discrete.is_some();
+        if shift && wheel && self.delta.0 == 0.0 {
+            self.delta = (self.delta.1, 0.0);
+            self.discrete = self.discrete.map(|(_, y)| (y, 0.0));
+        }
+        let delta = match self.discrete {
+            Some((x, y)) => ScrollDelta::Lines(x, y),
+            None => ScrollDelta::Pixels(self.delta.0, self.delta.1),
+        };
+        WindowEvent::Scroll {
+            delta,
+            source: self.source,
+            stop: self.stop,
+            timestamp: Duration::from_millis(self.time as u64),
+            seat,
+        }
+    }
+}
+/// On the keyboard of the same seat.
+fn shift_held(state: &WaywinState, global_name: u32) -> bool {
+    state
+        .seats
+        .iter()
+        .find(|seat| seat.global_name == global_name)
+        .is_some_and(|seat| {
+            seat.keyboard_state
+                .modifiers()
+                .contains(KeyModifiers::SHIFT)
+        })
+}
+impl Drop for PointerState {
+    fn drop(&mut self) {
+        self.release_devices();
         if let Some(s) = self.pointer_constraints.take() {
             s.destroy()
         }
     }
 }
 
-impl Dispatch<WlPointer, ()> for WaywinState {
+impl Dispatch<WlPointer, u32> for WaywinState {
     fn event(
         state: &mut Self,
-        _proxy: &WlPointer,
+        proxy: &WlPointer,
         event: <WlPointer as wayland_client::Proxy>::Event,
-        _data: &(),
+        data: &u32,
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
-        let mut pointer_state = state.pointer_state.lock().unwrap();
+        let Some(pointer_state) = state.seat_pointer_state(*data) else {
+            return;
+        };
+        let mut pointer_state = pointer_state.lock().unwrap();
         match event {
             wayland_client::protocol::wl_pointer::Event::Enter {
-                serial: _,
+                serial,
                 surface,
                 surface_x,
                 surface_y,
@@ -61,22 +187,33 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                         window_id: id,
                     });
                 }
-                let id = surface.id().as_ptr() as usize;
+                let id = WindowId(surface.id().as_ptr() as usize);
                 pointer_state.focused_window = Some(id);
+                pointer_state.enter_serial = serial;
+                pointer_state.clicks.reset();
+                pointer_state.position = (surface_x, surface_y);
+                if let Some((pointer, window)) =
+                    pointer_state.pointer.as_ref().zip(state.window(id))
+                {
+                    if let Some(cursor) = &window.lock().unwrap().cursor {
+                        cursor.apply(pointer, serial);
+                    }
+                }
                 state.events.push(WaywinEvent::WindowEvent {
                     event: WindowEvent::PointerEntered,
                     window_id: id,
                 });
-                state.events.push(WaywinEvent::WindowEvent {
-                    event: WindowEvent::PointerMoved(surface_x, surface_y),
-                    window_id: id,
-                });
+                pointer_state.motion = true;
+                if proxy.version() < 5 {
+                    pointer_state.flush_motion(&mut state.events, SeatId(*data));
+                }
             }
             wayland_client::protocol::wl_pointer::Event::Leave { serial: _, surface } => {
-                let id = surface.id().as_ptr() as usize;
+                let id = WindowId(surface.id().as_ptr() as usize);
                 if Some(id) != pointer_state.focused_window {
-                    log::warn!("pointer leaving unfocused window: {id}");
+                    log::warn!("pointer leaving unfocused window: {id:?}");
                 } else {
+                    pointer_state.flush_motion(&mut state.events, SeatId(*data));
                     pointer_state.focused_window = None;
                     state.events.push(WaywinEvent::WindowEvent {
                         event: WindowEvent::PointerLeft,
@@ -85,40 +222,75 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                 }
             }
             wayland_client::protocol::wl_pointer::Event::Motion {
-                time: _,
+                time,
                 surface_x,
                 surface_y,
             } => {
-                let Some(id) = pointer_state.focused_window else {
+                if pointer_state.focused_window.is_none() {
                     log::warn!("recieved a pointer motion event while no window is focused");
                     return;
-                };
-                state.events.push(WaywinEvent::WindowEvent {
-                    event: WindowEvent::PointerMoved(surface_x, surface_y),
-                    window_id: id,
-                });
+                }
+                pointer_state.position = (surface_x, surface_y);
+                pointer_state.time = time;
+                pointer_state.motion = true;
+                // frames were only added in version 5
+                if proxy.version() < 5 {
+                    pointer_state.flush_motion(&mut state.events, SeatId(*data));
+                }
             }
             wayland_client::protocol::wl_pointer::Event::Button {
-                serial: _,
-                time: _,
+                serial,
+                time,
                 button,
                 state: WEnum::Value(ButtonState::Pressed),
             } => {
+                state.input_serials.lock().unwrap().set(*data, serial);
                 let Some(id) = pointer_state.focused_window else {
                     log::warn!("recieved a pointer button down event while no window is focused");
                     return;
                 };
+                pointer_state.flush_motion(&mut state.events, SeatId(*data));
+                pointer_state.time = time;
+                let seat = state
+                    .seats
+                    .iter()
+                    .find(|seat| seat.global_name == *data)
+                    .map(|seat| seat.seat.clone());
+                if let Some((seat, window)) = seat.zip(state.window(id)) {
+                    let position = pointer_state.position;
+                    if window
+                        .lock()
+                        .unwrap()
+                        .hittest_press(&seat, serial, button, position)
+                    {
+                        return;
+                    }
+                }
+                let button = PointerButton::from(button);
+                let timestamp = Duration::from_millis(time as u64);
+                let position = pointer_state.position;
+                // there's no system setting, these are common defaults
+                let click_count = pointer_state.clicks.press(
+                    button,
+                    timestamp,
+                    position,
+                    DOUBLE_CLICK_TIME,
+                    (DOUBLE_CLICK_DISTANCE, DOUBLE_CLICK_DISTANCE),
+                );
                 state.events.push(WaywinEvent::WindowEvent {
                     event: WindowEvent::PointerButton {
                         down: true,
-                        button: PointerButton::from(button),
+                        button,
+                        click_count,
+                        timestamp,
+                        seat: SeatId(*data),
                     },
                     window_id: id,
                 });
             }
             wayland_client::protocol::wl_pointer::Event::Button {
                 serial: _,
-                time: _,
+                time,
                 button,
                 state: WEnum::Value(ButtonState::Released),
             } => {
@@ -126,10 +298,15 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                     log::warn!("recieved a pointer button up event while no window is focused");
                     return;
                 };
+                pointer_state.flush_motion(&mut state.events, SeatId(*data));
+                pointer_state.time = time;
                 state.events.push(WaywinEvent::WindowEvent {
                     event: WindowEvent::PointerButton {
                         down: false,
                         button: PointerButton::from(button),
+                        click_count: pointer_state.clicks.release(PointerButton::from(button)),
+                        timestamp: Duration::from_millis(time as u64),
+                        seat: SeatId(*data),
                     },
                     window_id: id,
                 });
@@ -143,7 +320,7 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                 log::error!("unknown pointer button state sent by OS")
             }
             wayland_client::protocol::wl_pointer::Event::Axis {
-                time: _,
+                time,
                 axis: WEnum::Value(axis),
                 value,
             } => {
@@ -151,13 +328,17 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                     log::warn!("recieved a pointer scroll event while no window is focused");
                     return;
                 };
-                state.events.push(WaywinEvent::WindowEvent {
-                    event: WindowEvent::Scroll {
-                        direction: ScrollDirection::from(axis),
-                        value: -value,
-                    },
-                    window_id: id,
-                });
+                let scroll = pointer_state.scroll.get_or_insert_default();
+                *scroll.axis(axis) -= value;
+                scroll.time = time;
+                // frames were only added in version 5
+                if proxy.version() < 5 {
+                    let scroll = pointer_state.scroll.take().unwrap();
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: scroll.into_event(shift_held(state, *data), SeatId(*data)),
+                        window_id: id,
+                    });
+                }
             }
             wayland_client::protocol::wl_pointer::Event::Axis {
                 time: _,
@@ -167,12 +348,60 @@ impl Dispatch<WlPointer, ()> for WaywinState {
                 log::error!("unknown pointer scroll axis sent by OS")
             }
             wayland_client::protocol::wl_pointer::Event::Frame => {
-                // TODO: maybe collect pointer events into a frame
+                pointer_state.flush_motion(&mut state.events, SeatId(*data));
+                if let Some((mut scroll, id)) = pointer_state
+                    .scroll
+                    .take()
+                    .zip(pointer_state.focused_window)
+                {
+                    let shift = shift_held(state, *data);
+                    let stop = scroll.split_stop();
+                    for scroll in std::iter::once(scroll).chain(stop) {
+                        state.events.push(WaywinEvent::WindowEvent {
+                            event: scroll.into_event(shift, SeatId(*data)),
+                            window_id: id,
+                        });
+                    }
+                }
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisSource {
+                axis_source: WEnum::Value(source),
+            } => {
+                pointer_state.scroll.get_or_insert_default().source = match source {
+                    AxisSource::Wheel | AxisSource::WheelTilt => Some(ScrollSource::Wheel),
+                    AxisSource::Finger => Some(ScrollSource::Finger),
+                    AxisSource::Continuous => Some(ScrollSource::Continuous),
+                    _ => None,
+                };
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisSource {
+                axis_source: WEnum::Unknown(_),
+            } => {
+                log::error!("unknown pointer scroll source sent by OS")
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisStop { time, axis: _ } => {
+                let scroll = pointer_state.scroll.get_or_insert_default();
+                scroll.stop = true;
+                scroll.time = time;
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisDiscrete {
+                axis: WEnum::Value(axis),
+                discrete,
+            } => {
+                let scroll = pointer_state.scroll.get_or_insert_default();
+                *scroll.discrete_axis(axis) -= discrete as f64;
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisValue120 {
+                axis: WEnum::Value(axis),
+                value120,
+            } => {
+                let scroll = pointer_state.scroll.get_or_insert_default();
+                *scroll.discrete_axis(axis) -= value120 as f64 / 120.0;
+            }
+            wayland_client::protocol::wl_pointer::Event::AxisDiscrete { .. }
+            | wayland_client::protocol::wl_pointer::Event::AxisValue120 { .. } => {
+                log::error!("unknown pointer scroll axis sent by OS")
             }
-            wayland_client::protocol::wl_pointer::Event::AxisSource { .. } => {}
-            wayland_client::protocol::wl_pointer::Event::AxisStop { .. } => {}
-            wayland_client::protocol::wl_pointer::Event::AxisDiscrete { .. } => {}
-            wayland_client::protocol::wl_pointer::Event::AxisValue120 { .. } => {}
             wayland_client::protocol::wl_pointer::Event::AxisRelativeDirection { .. } => {}
             _ => {
                 unimplemented!()
@@ -213,23 +442,50 @@ impl Dispatch<ZwpRelativePointerV1, ()> for WaywinState {
 
 impl From<u32> for PointerButton {
     fn from(value: u32) -> Self {
-        match value {
-            0x110 => Self::Left,
-            0x111 => Self::Right,
-            0x112 => Self::Middle,
-            0x114 => Self::Forward,
-            0x113 => Self::Back,
-            _ => Self::Unknown(value),
-        }
+        Self::from_evdev(value)
     }
 }
 
-impl From<Axis> for ScrollDirection {
-    fn from(value: Axis) -> Self {
-        match value {
-            Axis::VerticalScroll => Self::Vertical,
-            Axis::HorizontalScroll => Self::Horizontal,
-            _ => unimplemented!(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_is_sent_without_motion() {
+        // the vertical axis stops while the horizontal one keeps moving
+        let mut scroll = PendingScroll {
+            delta: (3.0, 0.0),
+            source: Some(ScrollSource::Finger),
+            stop: true,
+            ..PendingScroll::default()
+        };
+        let stop_scroll = scroll.split_stop().unwrap();
+        let WindowEvent::Scroll { delta, stop, .. } = scroll.into_event(false, SeatId(1)) else {
+            unreachable!()
+        };
+        assert_eq!(delta, ScrollDelta::Pixels(3.0, 0.0));
+        assert!(!stop);
+        let WindowEvent::Scroll {
+            delta,
+            source,
+            stop,
+            ..
+        } = stop_scroll.into_event(false, SeatId(1))
+        else {
+            unreachable!()
+        };
+        assert_eq!(delta, ScrollDelta::Pixels(0.0, 0.0));
+        assert_eq!(source, Some(ScrollSource::Finger));
+        assert!(stop);
+    }
+
+    #[test]
+    fn lone_stop_stays_whole() {
+        let mut scroll = PendingScroll {
+            stop: true,
+            ..PendingScroll::default()
+        };
+        assert!(scroll.split_stop().is_none());
+        assert!(scroll.stop);
     }
 }
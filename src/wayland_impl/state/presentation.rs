@@ -0,0 +1,51 @@
+use super::WaywinState;
+use crate::{
+    event::{WaywinEvent, WindowEvent},
+    WindowId,
+};
+use std::time::Duration;
+use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::presentation_time::client::{
+    wp_presentation::WpPresentation,
+    wp_presentation_feedback::{self, WpPresentationFeedback},
+};
+
+impl Dispatch<WpPresentationFeedback, WindowId> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &WpPresentationFeedback,
+        event: <WpPresentationFeedback as wayland_client::Proxy>::Event,
+        data: &WindowId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_presentation_feedback::Event::Presented {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+                refresh,
+                seq_hi,
+                seq_lo,
+                flags: _,
+            } => {
+                let secs = (tv_sec_hi as u64) << 32 | tv_sec_lo as u64;
+                state.events.push(WaywinEvent::WindowEvent {
+                    event: WindowEvent::PresentationFeedback {
+                        presented_at: Duration::new(secs, tv_nsec),
+                        refresh: Duration::from_nanos(refresh as u64),
+                        seq: (seq_hi as u64) << 32 | seq_lo as u64,
+                    },
+                    window_id: *data,
+                });
+            }
+            // the frame was replaced before it was shown
+            wp_presentation_feedback::Event::Discarded => {}
+            wp_presentation_feedback::Event::SyncOutput { output: _ } => {}
+            _ => unimplemented!(),
+        }
+    }
+}
+
+// only sends the clock id, the timestamps are only compared with each other
+delegate_noop!(WaywinState: ignore WpPresentation);
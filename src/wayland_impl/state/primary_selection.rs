@@ -0,0 +1,131 @@
+use super::{data_device::DataOfferData, WaywinState};
+use std::{
+    fs::File,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+    zwp_primary_selection_device_v1::{self, ZwpPrimarySelectionDeviceV1},
+    zwp_primary_selection_offer_v1::{self, ZwpPrimarySelectionOfferV1},
+    zwp_primary_selection_source_v1::{self, ZwpPrimarySelectionSourceV1},
+};
+
+/// The selection pasted with a middle click.
+pub struct PrimarySelectionState {
+    pub manager: Option<ZwpPrimarySelectionDeviceManagerV1>,
+    pub device: Option<ZwpPrimarySelectionDeviceV1>,
+    /// Shared with the clipboard handles.
+    pub selection: Arc<Mutex<PrimarySelection>>,
+}
+#[derive(Default)]
+pub struct PrimarySelection {
+    /// What another client selected.
+    pub offer: Option<ZwpPrimarySelectionOfferV1>,
+    /// What this client selected, read back without going through the compositor.
+    pub source: Option<(ZwpPrimarySelectionSourceV1, String)>,
+}
+impl PrimarySelectionState {
+    pub fn new(manager: Option<ZwpPrimarySelectionDeviceManagerV1>) -> Self {
+        Self {
+            manager,
+            device: None,
+            selection: Arc::default(),
+        }
+    }
+}
+impl Drop for PrimarySelectionState {
+    fn drop(&mut self) {
+        let mut selection = self.selection.lock().unwrap();
+        if let Some(offer) = selection.offer.take() {
+            offer.destroy()
+        }
+        if let Some((source, _)) = selection.source.take() {
+            source.destroy()
+        }
+        if let Some(s) = self.device.take() {
+            s.destroy()
+        }
+        if let Some(s) = self.manager.take() {
+            s.destroy()
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionDeviceV1, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPrimarySelectionDeviceV1,
+        event: <ZwpPrimarySelectionDeviceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_primary_selection_device_v1::Event::DataOffer { offer: _ } => {
+                // mime types arrive on the offer itself
+            }
+            zwp_primary_selection_device_v1::Event::Selection { id } => {
+                let mut selection = state.primary_selection_state.selection.lock().unwrap();
+                if let Some(offer) = std::mem::replace(&mut selection.offer, id) {
+                    offer.destroy();
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+
+    wayland_client::event_created_child!(WaywinState, ZwpPrimarySelectionDeviceV1, [
+        zwp_primary_selection_device_v1::EVT_DATA_OFFER_OPCODE => (ZwpPrimarySelectionOfferV1, DataOfferData::default()),
+    ]);
+}
+
+impl Dispatch<ZwpPrimarySelectionOfferV1, DataOfferData> for WaywinState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpPrimarySelectionOfferV1,
+        event: <ZwpPrimarySelectionOfferV1 as wayland_client::Proxy>::Event,
+        data: &DataOfferData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_primary_selection_offer_v1::Event::Offer { mime_type } => {
+                data.push_mime_type(mime_type);
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl Dispatch<ZwpPrimarySelectionSourceV1, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpPrimarySelectionSourceV1,
+        event: <ZwpPrimarySelectionSourceV1 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let mut selection = state.primary_selection_state.selection.lock().unwrap();
+        match event {
+            zwp_primary_selection_source_v1::Event::Send { mime_type: _, fd } => {
+                let Some((_, text)) = selection.source.as_ref().filter(|(s, _)| s == proxy) else {
+                    return;
+                };
+                if let Err(err) = File::from(fd).write_all(text.as_bytes()) {
+                    log::error!("failed to send primary selection text: {err}");
+                }
+            }
+            zwp_primary_selection_source_v1::Event::Cancelled => {
+                // replaced by another selection
+                if selection.source.as_ref().is_some_and(|(s, _)| s == proxy) {
+                    selection.source = None;
+                }
+                proxy.destroy();
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
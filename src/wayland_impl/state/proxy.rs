@@ -1,37 +1,89 @@
-use super::WaywinState;
+use super::{seat::Seat, WaywinState};
 use wayland_client::{
     delegate_noop,
     globals::GlobalListContents,
     protocol::{
+        wl_buffer::WlBuffer,
         wl_compositor::WlCompositor,
+        wl_data_device_manager::WlDataDeviceManager,
+        wl_output::WlOutput,
         wl_registry::{self, WlRegistry},
-        wl_seat::{self, Capability, WlSeat},
+        wl_seat::WlSeat,
+        wl_shm::WlShm,
+        wl_shm_pool::WlShmPool,
+        wl_surface::WlSurface,
     },
-    Connection, Dispatch, QueueHandle, WEnum,
+    Connection, Dispatch, Proxy, QueueHandle,
 };
 use wayland_protocols::{
     wp::{
         fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+        idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+        keyboard_shortcuts_inhibit::zv1::client::zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1,
         pointer_constraints::zv1::client::zwp_pointer_constraints_v1::ZwpPointerConstraintsV1,
+        pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        primary_selection::zv1::client::zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
         relative_pointer::zv1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+        text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3,
         viewporter::client::wp_viewporter::WpViewporter,
     },
     xdg::{
+        activation::v1::client::xdg_activation_v1::XdgActivationV1,
         decoration::zv1::client::zxdg_decoration_manager_v1::ZxdgDecorationManagerV1,
+        dialog::v1::client::xdg_wm_dialog_v1::XdgWmDialogV1,
         shell::client::xdg_wm_base::{self, XdgWmBase},
     },
 };
 
 impl Dispatch<WlRegistry, GlobalListContents> for WaywinState {
     fn event(
-        _state: &mut Self,
-        _proxy: &WlRegistry,
-        _event: wl_registry::Event,
+        state: &mut Self,
+        proxy: &WlRegistry,
+        event: wl_registry::Event,
         _data: &GlobalListContents,
         _conn: &Connection,
-        _qhandle: &wayland_client::QueueHandle<Self>,
+        qhandle: &wayland_client::QueueHandle<Self>,
     ) {
-        // TODO: react to dynamic global
+        // TODO: react to other dynamic globals
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => {
+                if interface == WlOutput::interface().name {
+                    state
+                        .output_state
+                        .lock()
+                        .unwrap()
+                        .add(proxy, name, version, qhandle);
+                } else if interface == WlSeat::interface().name {
+                    let seat = Seat::new(proxy, name, version, qhandle, &state.input_serials);
+                    state.seats.push(seat);
+                }
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if let Some(index) = state.seats.iter().position(|seat| seat.global_name == name) {
+                    state
+                        .seats
+                        .remove(index)
+                        .remove(&mut state.events, &state.input_serials);
+                    return;
+                }
+                let Some(output) = state.output_state.lock().unwrap().remove(name) else {
+                    return;
+                };
+                // surfaces aren't guaranteed a `Leave` for a removed output
+                for window in state
+                    .windows
+                    .iter()
+                    .filter_map(|(_, window)| window.upgrade())
+                {
+                    window.lock().unwrap().leave_output(&output);
+                }
+            }
+            _ => unimplemented!(),
+        }
     }
 }
 impl Dispatch<XdgWmBase, ()> for WaywinState {
@@ -51,55 +103,22 @@ impl Dispatch<XdgWmBase, ()> for WaywinState {
         }
     }
 }
-impl Dispatch<WlSeat, ()> for WaywinState {
-    fn event(
-        state: &mut Self,
-        proxy: &WlSeat,
-        event: <WlSeat as wayland_client::Proxy>::Event,
-        _data: &(),
-        _conn: &Connection,
-        qhandle: &QueueHandle<Self>,
-    ) {
-        let mut pointer_state = state.pointer_state.lock().unwrap();
-
-        match event {
-            wl_seat::Event::Capabilities { capabilities } => {
-                if let Some(s) = pointer_state.pointer.take() {
-                    s.release();
-                }
-                if let Some(s) = pointer_state.relative_pointer.take() {
-                    s.destroy();
-                }
-                if let Some(s) = state.keyboard_state.keyboard.take() {
-                    s.release();
-                }
-                if let WEnum::Value(cap) = capabilities {
-                    if cap.intersects(Capability::Pointer) {
-                        pointer_state.pointer = Some(proxy.get_pointer(qhandle, ()));
-                        pointer_state.relative_pointer = pointer_state
-                            .pointer
-                            .as_ref()
-                            .zip(pointer_state.relative_pointer_manager.as_ref())
-                            .map(|(pointer, manager)| {
-                                manager.get_relative_pointer(pointer, qhandle, ())
-                            });
-                    }
-                    if cap.intersects(Capability::Keyboard) {
-                        state.keyboard_state.keyboard = Some(proxy.get_keyboard(qhandle, ()));
-                    }
-                }
-            }
-            wl_seat::Event::Name { name: _ } => {
-                // TODO
-            }
-            _ => unimplemented!(),
-        }
-    }
-}
-
 delegate_noop!(WaywinState: WlCompositor);
+delegate_noop!(WaywinState: ignore WlShm);
+delegate_noop!(WaywinState: WlShmPool);
+delegate_noop!(WaywinState: ignore WlBuffer);
+// cursor surfaces
+delegate_noop!(WaywinState: ignore WlSurface);
 delegate_noop!(WaywinState: ZxdgDecorationManagerV1);
 delegate_noop!(WaywinState: WpViewporter);
+delegate_noop!(WaywinState: ZwpIdleInhibitManagerV1);
+delegate_noop!(WaywinState: ZwpKeyboardShortcutsInhibitManagerV1);
+delegate_noop!(WaywinState: XdgWmDialogV1);
+delegate_noop!(WaywinState: XdgActivationV1);
 delegate_noop!(WaywinState: WpFractionalScaleManagerV1);
 delegate_noop!(WaywinState: ZwpRelativePointerManagerV1);
 delegate_noop!(WaywinState: ZwpPointerConstraintsV1);
+delegate_noop!(WaywinState: ZwpPointerGesturesV1);
+delegate_noop!(WaywinState: ZwpTextInputManagerV3);
+delegate_noop!(WaywinState: WlDataDeviceManager);
+delegate_noop!(WaywinState: ZwpPrimarySelectionDeviceManagerV1);
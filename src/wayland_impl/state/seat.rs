@@ -0,0 +1,190 @@
+use super::{keyboard::KeyboardState, pointer::PointerState, touch::TouchState, WaywinState};
+use crate::event::{WaywinEvent, WindowEvent};
+use std::sync::{Arc, Mutex};
+use wayland_client::{
+    protocol::{
+        wl_registry::WlRegistry,
+        wl_seat::{self, Capability, WlSeat},
+    },
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+};
+
+/// Multi-seat setups have several, each with its own input devices.
+///
+/// Cursors, pointer grabs, text input and the clipboard only use the first one.
+/// Popups grab and windows are focused for the one that got input last.
+pub struct Seat {
+    /// The name of the global in the registry, also the user data of the seat's devices.
+    pub global_name: u32,
+    pub seat: WlSeat,
+    pub keyboard_state: KeyboardState,
+    pub pointer_state: Arc<Mutex<PointerState>>,
+    pub touch_state: TouchState,
+}
+impl Seat {
+    pub fn new(
+        registry: &WlRegistry,
+        global_name: u32,
+        version: u32,
+        qhandle: &QueueHandle<WaywinState>,
+        input_serials: &Mutex<InputSerials>,
+    ) -> Self {
+        let seat: WlSeat = registry.bind(global_name, version.min(9), qhandle, global_name);
+        input_serials
+            .lock()
+            .unwrap()
+            .seats
+            .push((global_name, seat.clone(), 0));
+        Self {
+            global_name,
+            seat,
+            keyboard_state: KeyboardState::default(),
+            pointer_state: Arc::default(),
+            touch_state: TouchState::default(),
+        }
+    }
+    /// The seat's devices are gone without any `Leave`, so the windows are told here.
+    pub fn remove(self, events: &mut Vec<WaywinEvent>, input_serials: &Mutex<InputSerials>) {
+        let mut input_serials = input_serials.lock().unwrap();
+        input_serials
+            .seats
+            .retain(|(global_name, ..)| *global_name != self.global_name);
+        input_serials
+            .latest
+            .take_if(|global_name| *global_name == self.global_name);
+        drop(input_serials);
+
+        if let Some(id) = self.keyboard_state.focused_window {
+            events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::Focus(false),
+                window_id: id,
+            });
+        }
+        if let Some(id) = self.pointer_state.lock().unwrap().focused_window.take() {
+            events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::PointerLeft,
+                window_id: id,
+            });
+        }
+    }
+}
+
+/// Requests tied to input, like grabbing a popup or setting the clipboard,
+/// need the serial of a recent input event on the seat they're for.
+#[derive(Default)]
+pub struct InputSerials {
+    /// Each seat's global name, the seat and the serial of its latest input event.
+    seats: Vec<(u32, WlSeat, u32)>,
+    /// The global name of the seat that got input last.
+    latest: Option<u32>,
+}
+impl InputSerials {
+    pub fn set(&mut self, global_name: u32, serial: u32) {
+        if let Some((.., latest_serial)) = self
+            .seats
+            .iter_mut()
+            .find(|(name, ..)| *name == global_name)
+        {
+            *latest_serial = serial;
+            self.latest = Some(global_name);
+        }
+    }
+    pub fn get(&self, global_name: u32) -> Option<u32> {
+        self.seats
+            .iter()
+            .find(|(name, ..)| *name == global_name)
+            .map(|(.., serial)| *serial)
+    }
+    /// The seat that got input last and the serial of that input.
+    pub fn latest(&self) -> Option<(&WlSeat, u32)> {
+        let latest = self.latest?;
+        self.seats
+            .iter()
+            .find(|(name, ..)| *name == latest)
+            .map(|(_, seat, serial)| (seat, *serial))
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        // windows can keep the pointer state around
+        self.pointer_state.lock().unwrap().release_devices();
+        if self.seat.version() >= 5 {
+            self.seat.release();
+        }
+    }
+}
+
+impl Dispatch<WlSeat, u32> for WaywinState {
+    fn event(
+        state: &mut Self,
+        proxy: &WlSeat,
+        event: <WlSeat as wayland_client::Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        qhandle: &QueueHandle<Self>,
+    ) {
+        let first = state
+            .seats
+            .first()
+            .is_some_and(|seat| seat.global_name == *data);
+        let Some(seat) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.global_name == *data)
+        else {
+            return;
+        };
+        let mut pointer_state = seat.pointer_state.lock().unwrap();
+
+        match event {
+            wl_seat::Event::Capabilities { capabilities } => {
+                pointer_state.release_devices();
+                if let Some(s) = seat.keyboard_state.keyboard.take() {
+                    s.release();
+                }
+                if let Some(s) = seat.touch_state.touch.take() {
+                    s.release();
+                }
+                let mut text_input_state = state.text_input_state.lock().unwrap();
+                if first {
+                    if let Some(s) = text_input_state.text_input.take() {
+                        s.destroy();
+                    }
+                }
+                if let WEnum::Value(cap) = capabilities {
+                    if cap.intersects(Capability::Pointer) {
+                        let pointer = proxy.get_pointer(qhandle, *data);
+                        pointer_state.relative_pointer = state
+                            .relative_pointer_manager
+                            .as_ref()
+                            .map(|manager| manager.get_relative_pointer(&pointer, qhandle, ()));
+                        if let Some(manager) = &state.pointer_gestures {
+                            pointer_state.swipe_gesture =
+                                Some(manager.get_swipe_gesture(&pointer, qhandle, *data));
+                            pointer_state.pinch_gesture =
+                                Some(manager.get_pinch_gesture(&pointer, qhandle, *data));
+                        }
+                        pointer_state.pointer = Some(pointer);
+                    }
+                    if cap.intersects(Capability::Touch) {
+                        seat.touch_state.touch = Some(proxy.get_touch(qhandle, *data));
+                    }
+                    if cap.intersects(Capability::Keyboard) {
+                        seat.keyboard_state.keyboard = Some(proxy.get_keyboard(qhandle, *data));
+                        if first {
+                            text_input_state.text_input = text_input_state
+                                .text_input_manager
+                                .as_ref()
+                                .map(|manager| manager.get_text_input(proxy, qhandle, ()));
+                        }
+                    }
+                }
+            }
+            wl_seat::Event::Name { name: _ } => {
+                // TODO
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use super::WaywinState;
+use crate::{
+    event::{Ime, WaywinEvent, WindowEvent},
+    Rect, WindowId,
+};
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::wp::text_input::zv3::client::{
+    zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+    zwp_text_input_v3::{self, ContentHint, ContentPurpose, ZwpTextInputV3},
+};
+
+pub struct TextInputState {
+    pub text_input: Option<ZwpTextInputV3>,
+    pub text_input_manager: Option<ZwpTextInputManagerV3>,
+    pub focused_window: Option<WindowId>,
+    /// Applied on the next `Done`.
+    preedit: Option<(String, i32, i32)>,
+    commit: Option<String>,
+    /// A non empty preedit has been sent.
+    preedit_sent: bool,
+}
+impl TextInputState {
+    pub fn new(text_input_manager: Option<ZwpTextInputManagerV3>) -> Self {
+        Self {
+            text_input: None,
+            text_input_manager,
+            focused_window: None,
+            preedit: None,
+            commit: None,
+            preedit_sent: false,
+        }
+    }
+    /// Enables or disables the input method for the focused window.
+    pub fn set_enabled(&self, enabled: bool, cursor_area: Option<Rect>) {
+        let Some(text_input) = &self.text_input else {
+            return;
+        };
+        if enabled {
+            text_input.enable();
+            text_input.set_content_type(ContentHint::None, ContentPurpose::Normal);
+            if let Some(area) = cursor_area {
+                text_input.set_cursor_rectangle(area.x, area.y, area.width, area.height);
+            }
+        } else {
+            text_input.disable();
+        }
+        text_input.commit();
+    }
+    pub fn set_cursor_area(&self, area: Rect) {
+        if let Some(text_input) = &self.text_input {
+            text_input.set_cursor_rectangle(area.x, area.y, area.width, area.height);
+            text_input.commit();
+        }
+    }
+}
+impl Drop for TextInputState {
+    fn drop(&mut self) {
+        if let Some(s) = self.text_input.take() {
+            s.destroy()
+        }
+        if let Some(s) = self.text_input_manager.take() {
+            s.destroy()
+        }
+    }
+}
+
+impl Dispatch<ZwpTextInputV3, ()> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTextInputV3,
+        event: <ZwpTextInputV3 as wayland_client::Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let mut text_input_state = state.text_input_state.lock().unwrap();
+        match event {
+            zwp_text_input_v3::Event::Enter { surface } => {
+                let id = WindowId(surface.id().as_ptr() as usize);
+                text_input_state.focused_window = Some(id);
+                if let Some(window) = state.window(id) {
+                    let window = window.lock().unwrap();
+                    if window.ime_allowed {
+                        text_input_state.set_enabled(true, window.ime_cursor_area);
+                    }
+                }
+            }
+            zwp_text_input_v3::Event::Leave { surface } => {
+                let id = WindowId(surface.id().as_ptr() as usize);
+                if Some(id) != text_input_state.focused_window {
+                    log::warn!("text input leaving unfocused window: {id:?}");
+                    return;
+                }
+                text_input_state.set_enabled(false, None);
+                text_input_state.focused_window = None;
+                text_input_state.preedit = None;
+                text_input_state.commit = None;
+                text_input_state.preedit_sent = false;
+            }
+            zwp_text_input_v3::Event::PreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => {
+                text_input_state.preedit = text.map(|text| (text, cursor_begin, cursor_end));
+            }
+            zwp_text_input_v3::Event::CommitString { text } => {
+                text_input_state.commit = text;
+            }
+            zwp_text_input_v3::Event::DeleteSurroundingText { .. } => {
+                // surrounding text is never sent so there is nothing to delete
+            }
+            zwp_text_input_v3::Event::Done { serial: _ } => {
+                let Some(id) = text_input_state.focused_window else {
+                    log::warn!("recieved a text input done event while no window is focused");
+                    return;
+                };
+                let commit = text_input_state.commit.take();
+                let preedit = text_input_state.preedit.take();
+
+                if let Some(text) = commit {
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: WindowEvent::Ime(Ime::Commit(text)),
+                        window_id: id,
+                    });
+                }
+                if preedit.is_some() || text_input_state.preedit_sent {
+                    let (text, begin, end) = preedit.unwrap_or_default();
+                    // negative values hide the cursor
+                    let cursor = (begin >= 0 && end >= 0).then_some((begin as usize, end as usize));
+                    text_input_state.preedit_sent = !text.is_empty();
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: WindowEvent::Ime(Ime::Preedit { text, cursor }),
+                        window_id: id,
+                    });
+                }
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
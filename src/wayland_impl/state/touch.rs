@@ -0,0 +1,99 @@
+use super::WaywinState;
+use crate::{
+    event::{TouchPhase, WaywinEvent, WindowEvent},
+    SeatId, WindowId,
+};
+use std::collections::HashMap;
+use wayland_client::{
+    protocol::wl_touch::{self, WlTouch},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+
+#[derive(Default)]
+pub struct TouchState {
+    pub touch: Option<WlTouch>,
+    /// The window each active touch point started on and its last position.
+    points: HashMap<i32, (WindowId, (f64, f64))>,
+}
+impl Drop for TouchState {
+    fn drop(&mut self) {
+        if let Some(s) = self.touch.take() {
+            s.release()
+        }
+    }
+}
+
+impl Dispatch<WlTouch, u32> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &WlTouch,
+        event: <WlTouch as wayland_client::Proxy>::Event,
+        data: &u32,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(touch_state) = state
+            .seats
+            .iter_mut()
+            .find(|seat| seat.global_name == *data)
+            .map(|seat| &mut seat.touch_state)
+        else {
+            return;
+        };
+        let mut push = |id: i32, phase: TouchPhase, window_id: WindowId, (x, y): (f64, f64)| {
+            state.events.push(WaywinEvent::WindowEvent {
+                event: WindowEvent::Touch {
+                    id: id as u64,
+                    phase,
+                    x,
+                    y,
+                    seat: SeatId(*data),
+                },
+                window_id,
+            });
+        };
+        match event {
+            wl_touch::Event::Down {
+                serial,
+                time: _,
+                surface,
+                id,
+                x,
+                y,
+            } => {
+                state.input_serials.lock().unwrap().set(*data, serial);
+                let window_id = WindowId(surface.id().as_ptr() as usize);
+                touch_state.points.insert(id, (window_id, (x, y)));
+                push(id, TouchPhase::Started, window_id, (x, y));
+            }
+            wl_touch::Event::Up {
+                serial: _,
+                time: _,
+                id,
+            } => {
+                let Some((window_id, position)) = touch_state.points.remove(&id) else {
+                    log::warn!("recieved a touch up event for an unknown touch point: {id}");
+                    return;
+                };
+                push(id, TouchPhase::Ended, window_id, position);
+            }
+            wl_touch::Event::Motion { time: _, id, x, y } => {
+                let Some((window_id, position)) = touch_state.points.get_mut(&id) else {
+                    log::warn!("recieved a touch motion event for an unknown touch point: {id}");
+                    return;
+                };
+                *position = (x, y);
+                push(id, TouchPhase::Moved, *window_id, (x, y));
+            }
+            wl_touch::Event::Cancel => {
+                for (id, (window_id, position)) in touch_state.points.drain() {
+                    push(id, TouchPhase::Cancelled, window_id, position);
+                }
+            }
+            wl_touch::Event::Frame => {}
+            wl_touch::Event::Shape { .. } => {}
+            wl_touch::Event::Orientation { .. } => {}
+            _ => unimplemented!(),
+        }
+    }
+}
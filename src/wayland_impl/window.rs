@@ -1,36 +1,79 @@
-use super::{state::pointer::PointerState, Waywin, WaywinState};
-use crate::event::{WaywinEvent, WindowEvent};
+use super::{
+    cursor::CustomCursor,
+    state::{
+        output::OutputState, pointer::PointerState, seat::InputSerials, text_input::TextInputState,
+        EventHandlers, OwnedXdgWmBase,
+    },
+    Monitor, Waywin, WaywinState,
+};
+use crate::{
+    event::{KeyModifiers, LockState, PhysicalKey, WaywinEvent, WindowEvent},
+    Backdrop, CornerPreference, CursorGrabMode, CursorImage, Error, Fullscreen, HitTestResult,
+    Progress, Rect, Theme, WindowBuilder, WindowId, WmCapabilities,
+};
 use raw_window_handle as rwh;
 use std::{
     ptr::NonNull,
     sync::{Arc, Mutex, Weak},
+    time::Instant,
 };
 use wayland_client::{
     delegate_noop,
     protocol::{
         wl_callback::{self, WlCallback},
+        wl_compositor::WlCompositor,
+        wl_output::WlOutput,
+        wl_region::WlRegion,
+        wl_seat::WlSeat,
+        wl_shm::WlShm,
         wl_surface::{self, WlSurface},
     },
     Connection, Dispatch, Proxy, QueueHandle,
 };
 use wayland_protocols::{
     wp::{
-        fractional_scale::v1::client::wp_fractional_scale_v1::{self, WpFractionalScaleV1},
+        fractional_scale::v1::client::{
+            wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1,
+            wp_fractional_scale_v1::{self, WpFractionalScaleV1},
+        },
+        idle_inhibit::zv1::client::{
+            zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+            zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+        },
+        keyboard_shortcuts_inhibit::zv1::client::{
+            zwp_keyboard_shortcuts_inhibit_manager_v1::ZwpKeyboardShortcutsInhibitManagerV1,
+            zwp_keyboard_shortcuts_inhibitor_v1::ZwpKeyboardShortcutsInhibitorV1,
+        },
         pointer_constraints::zv1::client::{
             zwp_confined_pointer_v1::ZwpConfinedPointerV1,
             zwp_locked_pointer_v1::ZwpLockedPointerV1, zwp_pointer_constraints_v1::Lifetime,
         },
-        viewporter::client::wp_viewport::WpViewport,
+        presentation_time::client::wp_presentation::WpPresentation,
+        viewporter::client::{wp_viewport::WpViewport, wp_viewporter::WpViewporter},
     },
     xdg::{
-        decoration::zv1::client::zxdg_toplevel_decoration_v1::{Mode, ZxdgToplevelDecorationV1},
+        activation::v1::client::{
+            xdg_activation_token_v1::{self, XdgActivationTokenV1},
+            xdg_activation_v1::XdgActivationV1,
+        },
+        decoration::zv1::client::{
+            zxdg_decoration_manager_v1::ZxdgDecorationManagerV1,
+            zxdg_toplevel_decoration_v1::{Mode, ZxdgToplevelDecorationV1},
+        },
+        dialog::v1::client::{xdg_dialog_v1::XdgDialogV1, xdg_wm_dialog_v1::XdgWmDialogV1},
         shell::client::{
+            xdg_popup::{self, XdgPopup},
+            xdg_positioner::{Anchor, ConstraintAdjustment, Gravity, XdgPositioner},
             xdg_surface::{self, XdgSurface},
-            xdg_toplevel::{self, XdgToplevel},
+            xdg_toplevel::{self, ResizeEdge, XdgToplevel},
         },
     },
 };
 
+/// Linux input event codes of the buttons the hit test handler acts on.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+
 #[derive(Clone, Copy)]
 pub(crate) struct State {
     pub size: (i32, i32),
@@ -49,54 +92,305 @@ impl State {
             (self.size.1 as f64 * self.scale).round() as u32,
         )
     }
+    /// `ScaleFactorChanged` and `Resized` for what changed since `prev`,
+    /// however many configures it took to get there. Compared in physical pixels
+    /// so a scale change that keeps the buffer size, like a logical size halved
+    /// with a doubled scale, doesn't resize swapchains.
+    pub fn changes_since(&self, prev: &State) -> [Option<WindowEvent>; 2] {
+        let scaled = (prev.scale != self.scale).then(|| WindowEvent::ScaleFactorChanged {
+            scale: self.scale,
+            suggested_size: self.physical_size(),
+        });
+        let (width, height) = self.physical_size();
+        let resized = (prev.physical_size() != (width, height))
+            .then_some(WindowEvent::Resized { width, height });
+        [scaled, resized]
+    }
+    /// Converts a logical size to surface coordinates, going through physical pixels
+    /// so the constraint lands on a whole pixel at the current scale.
+    pub fn surface_size(&self, size: (f64, f64)) -> (i32, i32) {
+        (
+            ((size.0 * self.scale).round() / self.scale).ceil() as i32,
+            ((size.1 * self.scale).round() / self.scale).ceil() as i32,
+        )
+    }
+}
+/// The min and max size to send for logical ones, `(0, 0)` meaning none.
+fn size_constraints(
+    state: State,
+    min_size: Option<(f64, f64)>,
+    max_size: Option<(f64, f64)>,
+) -> ((i32, i32), (i32, i32)) {
+    let convert =
+        |size: Option<(f64, f64)>| size.map(|size| state.surface_size(size)).unwrap_or((0, 0));
+    (convert(min_size), convert(max_size))
 }
 #[derive(Clone, Copy, Default)]
 struct PendingConfigure {
     pub size: Option<(i32, i32)>,
 }
+impl PendingConfigure {
+    /// Applies the size once the configure is acknowledged.
+    /// Without one the window keeps its current size.
+    fn apply(&mut self, state: &mut State) {
+        match self.size {
+            Some(size) => state.size = size,
+            None => self.size = Some(state.size),
+        }
+    }
+}
 
 pub struct WindowState {
     surface: WlSurface,
     xdg_surface: XdgSurface,
-    toplevel: XdgToplevel,
+    /// One of these, depending on the kind of window.
+    toplevel: Option<XdgToplevel>,
+    popup: Option<XdgPopup>,
 
     // make sure `XdgWmBase` isn't destroyed while this window is alive
     _xdg_base: Arc<super::state::OwnedXdgWmBase>,
 
     pub state: State,
+    /// The state as of the last emitted `Resized`/`ScaleFactorChanged`.
     pub prev_state: State,
     configure: PendingConfigure,
 
-    // title: String,
+    title: String,
     fullscreen: bool,
+    /// From `ConfigureBounds`, `None` if unknown.
+    suggested_max_size: Option<(i32, i32)>,
+    wm_capabilities: WmCapabilities,
+    /// Last sent with `Occluded`.
+    occluded: bool,
+
+    min_size: Option<(f64, f64)>,
+    max_size: Option<(f64, f64)>,
+    /// In logical pixels, applied to the sizes the compositor asks for.
+    resize_increments: Option<(u32, u32)>,
+    /// Width to height, applied to the sizes the compositor asks for.
+    aspect_ratio: Option<(u32, u32)>,
+    /// Set with `Window::set_theme`, the system's isn't known.
+    theme: Option<Theme>,
+    /// Set with `Window::set_hittest_handler`, asked on button presses.
+    hittest: Option<Box<dyn Fn(f64, f64) -> HitTestResult + Send>>,
+
+    /// Keep the opaque region covering the whole surface.
+    /// Cleared once the app sets its own region.
+    auto_opaque: bool,
 
     redraw: bool,
+    /// What to redraw, `None` for everything.
+    damage: Option<Rect>,
+    /// The damage of the last paint, sent with the commit of what was drawn for it.
+    pending_damage: Option<Rect>,
+    /// Paint is held back until the compositor is done with the last frame.
+    frame_callback: Option<WlCallback>,
+    pub redraw_deadline: Option<Instant>,
+    /// The initial commit is delayed until the window is shown.
+    visible: bool,
+    /// Set after the first configure since the window was last shown.
+    configured: bool,
 
     locked_pointer: Option<ZwpLockedPointerV1>,
     confined_pointer: Option<ZwpConfinedPointerV1>,
+    grab_escape_key: Option<PhysicalKey>,
+    /// Updated as `ModifiersChanged` is delivered.
+    pub modifiers: KeyModifiers,
+    /// Updated as `LockStateChanged` is delivered.
+    pub lock_state: LockState,
+
+    pub ime_allowed: bool,
+    pub ime_cursor_area: Option<Rect>,
+    /// Whether `Ime::Enabled` was the last sent of `Enabled`/`Disabled`.
+    pub ime_enabled: bool,
+
+    /// The outputs the surface is on, in the order they were entered.
+    outputs: Vec<WlOutput>,
+    /// The output as of the last emitted `MonitorChanged`.
+    pub prev_output: Option<WlOutput>,
+
+    /// Shown while the pointer is over the window.
+    pub cursor: Option<CustomCursor>,
 
     viewport_scaling: Option<(WpViewport, WpFractionalScaleV1)>,
     decoration: Option<ZxdgToplevelDecorationV1>,
+    /// Only for modal windows.
+    dialog: Option<XdgDialogV1>,
+    idle_inhibitor: Option<ZwpIdleInhibitorV1>,
+    /// Only in effect while the window has keyboard focus.
+    shortcuts_inhibitor: Option<ZwpKeyboardShortcutsInhibitorV1>,
 }
 impl WindowState {
+    pub fn request_redraw(&mut self) {
+        self.redraw = true;
+        self.damage = None;
+    }
+    pub fn request_redraw_region(&mut self, rect: Rect) {
+        // a pending full redraw already covers it
+        if !self.redraw {
+            self.damage = Some(rect);
+        } else if let Some(damage) = self.damage {
+            self.damage = Some(damage.union(rect));
+        }
+        self.redraw = true;
+    }
+    /// Asks for `PresentationFeedback` about the next commit.
+    pub fn request_presentation_feedback(
+        &self,
+        presentation: &WpPresentation,
+        qhandle: &QueueHandle<WaywinState>,
+    ) {
+        presentation.feedback(&self.surface, qhandle, self.id());
+    }
+    /// What's about to be redrawn, kept until the app commits what it drew for it.
+    pub fn take_damage(&mut self) -> Option<Rect> {
+        self.pending_damage = self.damage.take();
+        self.pending_damage
+    }
+    /// Damages what the last paint redrew, in buffer pixels rounded outwards.
+    /// Returns `false` if that's everything, drawing the whole buffer takes care of it then.
+    pub fn send_pending_damage(&mut self) -> bool {
+        let Some(damage) = self.pending_damage.take() else {
+            return false;
+        };
+        // surface coordinates are logical ones, with a viewport as well as a buffer scale
+        if self.surface.version() < 4 {
+            self.surface
+                .damage(damage.x, damage.y, damage.width, damage.height);
+            return true;
+        }
+        let scale = self.state.scale;
+        let left = (damage.x as f64 * scale).floor() as i32;
+        let top = (damage.y as f64 * scale).floor() as i32;
+        let right = ((damage.x + damage.width) as f64 * scale).ceil() as i32;
+        let bottom = ((damage.y + damage.height) as f64 * scale).ceil() as i32;
+        self.surface
+            .damage_buffer(left, top, right - left, bottom - top);
+        true
+    }
+    pub fn awaiting_frame(&self) -> bool {
+        self.frame_callback.is_some()
+    }
+    /// Not committed here, it goes along with the app's commit of the frame
+    /// so a buffer attached by another thread is never committed halfway.
+    pub fn request_frame(
+        &mut self,
+        qhandle: &QueueHandle<WaywinState>,
+        weak: Weak<Mutex<WindowState>>,
+    ) {
+        if self.is_mapped() {
+            self.frame_callback = Some(self.surface.frame(qhandle, weak));
+        }
+    }
     pub fn reset_redraw(&mut self) -> bool {
         let redraw = self.redraw;
         self.redraw = false;
         redraw
     }
-    pub fn id(&self) -> usize {
-        self.surface.id().as_ptr() as usize
+    /// Turns a passed redraw deadline into a redraw request.
+    pub fn check_redraw_deadline(&mut self, now: Instant) {
+        if self.redraw_deadline.is_some_and(|deadline| deadline <= now) {
+            self.redraw_deadline = None;
+            self.redraw = true;
+        }
+    }
+    pub fn id(&self) -> WindowId {
+        WindowId(self.surface.id().as_ptr() as usize)
+    }
+    /// Starts a move, resize or window menu if the hit test handler says the press is for one.
+    /// Returns whether the press was used up by it.
+    pub fn hittest_press(
+        &self,
+        seat: &WlSeat,
+        serial: u32,
+        button: u32,
+        position: (f64, f64),
+    ) -> bool {
+        let (Some(toplevel), Some(hittest)) = (&self.toplevel, &self.hittest) else {
+            return false;
+        };
+        let edge = match (button, hittest(position.0, position.1)) {
+            (BTN_RIGHT, HitTestResult::Caption) => {
+                toplevel.show_window_menu(seat, serial, position.0 as i32, position.1 as i32);
+                return true;
+            }
+            (BTN_LEFT, HitTestResult::Caption) => {
+                toplevel._move(seat, serial);
+                return true;
+            }
+            (BTN_LEFT, HitTestResult::Top) => ResizeEdge::Top,
+            (BTN_LEFT, HitTestResult::Bottom) => ResizeEdge::Bottom,
+            (BTN_LEFT, HitTestResult::Left) => ResizeEdge::Left,
+            (BTN_LEFT, HitTestResult::Right) => ResizeEdge::Right,
+            (BTN_LEFT, HitTestResult::TopLeft) => ResizeEdge::TopLeft,
+            (BTN_LEFT, HitTestResult::TopRight) => ResizeEdge::TopRight,
+            (BTN_LEFT, HitTestResult::BottomLeft) => ResizeEdge::BottomLeft,
+            (BTN_LEFT, HitTestResult::BottomRight) => ResizeEdge::BottomRight,
+            _ => return false,
+        };
+        toplevel.resize(seat, serial, edge);
+        true
+    }
+    /// (Re)sends the size constraints for the current scale.
+    /// They're double buffered, the app's next present commits them.
+    pub fn apply_size_constraints(&self) {
+        // popups get their size from whoever creates them
+        let Some(toplevel) = &self.toplevel else {
+            return;
+        };
+        let ((min_w, min_h), (max_w, max_h)) =
+            size_constraints(self.state, self.min_size, self.max_size);
+        toplevel.set_min_size(min_w, min_h);
+        toplevel.set_max_size(max_w, max_h);
     }
-    pub fn unlock_pointer(&mut self) {
+    pub fn set_opaque_region(
+        &self,
+        compositor: &WlCompositor,
+        qhandle: &QueueHandle<WaywinState>,
+        region: Option<&[Rect]>,
+    ) {
+        let region = region.map(|rects| {
+            let region = compositor.create_region(qhandle, ());
+            for rect in rects {
+                region.add(rect.x, rect.y, rect.width, rect.height);
+            }
+            region
+        });
+        self.surface.set_opaque_region(region.as_ref());
+        if let Some(region) = region {
+            region.destroy();
+        }
+    }
+    /// Whether the window can be drawn to.
+    pub fn is_mapped(&self) -> bool {
+        self.visible && self.configured
+    }
+    pub fn release_grab(&mut self) {
         if let Some(locked_pointer) = self.locked_pointer.take() {
             locked_pointer.destroy();
         }
-    }
-    pub fn unconfine_pointer(&mut self) {
         if let Some(confined_pointer) = self.confined_pointer.take() {
             confined_pointer.destroy();
         }
     }
+    /// The output the window is considered to be on.
+    pub fn current_output(&self) -> Option<&WlOutput> {
+        self.outputs.first()
+    }
+    pub fn leave_output(&mut self, output: &WlOutput) {
+        self.outputs.retain(|o| o != output);
+    }
+    /// Releases the pointer grab if `key` is the grab escape key.
+    /// Returns whether a grab was released.
+    pub fn release_grab_on(&mut self, key: PhysicalKey) -> bool {
+        if self.grab_escape_key != Some(key)
+            || (self.locked_pointer.is_none() && self.confined_pointer.is_none())
+        {
+            return false;
+        }
+        self.release_grab();
+        true
+    }
 }
 impl Drop for WindowState {
     fn drop(&mut self) {
@@ -107,219 +401,736 @@ impl Drop for WindowState {
         if let Some(decoration) = &self.decoration {
             decoration.destroy();
         }
-        self.toplevel.destroy();
+        if let Some(idle_inhibitor) = &self.idle_inhibitor {
+            idle_inhibitor.destroy();
+        }
+        if let Some(shortcuts_inhibitor) = &self.shortcuts_inhibitor {
+            shortcuts_inhibitor.destroy();
+        }
+        if let Some(dialog) = &self.dialog {
+            dialog.destroy();
+        }
+        if let Some(popup) = &self.popup {
+            popup.destroy();
+        }
+        if let Some(toplevel) = &self.toplevel {
+            toplevel.destroy();
+        }
         self.xdg_surface.destroy();
         self.surface.destroy();
     }
 }
 
-pub struct Window {
-    state: Arc<Mutex<WindowState>>,
-
-    signal: calloop::LoopSignal,
+/// What windows need from `Waywin`, every window keeps a copy so popups can be created from it.
+#[derive(Clone)]
+pub struct WindowContext {
+    qhandle: QueueHandle<WaywinState>,
+    compositor: WlCompositor,
+    xdg_wm_base: Arc<OwnedXdgWmBase>,
+    decoration: Option<ZxdgDecorationManagerV1>,
+    viewporter: Option<WpViewporter>,
+    scaling: Option<WpFractionalScaleManagerV1>,
+    shm: Option<WlShm>,
+    idle_inhibit_manager: Option<ZwpIdleInhibitManagerV1>,
+    shortcuts_inhibit_manager: Option<ZwpKeyboardShortcutsInhibitManagerV1>,
+    dialog_manager: Option<XdgWmDialogV1>,
+    activation: Option<XdgActivationV1>,
+    app_id: String,
+    /// Of the first seat, keyboard grabs apply to it.
+    seat: Option<WlSeat>,
+    input_serials: Arc<Mutex<InputSerials>>,
 
     pointer_state: Arc<Mutex<PointerState>>,
+    text_input_state: Arc<Mutex<TextInputState>>,
+    output_state: Arc<Mutex<OutputState>>,
+    event_handlers: EventHandlers,
 
-    qhandle: QueueHandle<WaywinState>,
-
+    signal: calloop::LoopSignal,
+    /// Windows created without access to `Waywin` are added to its list through this.
+    new_windows: calloop::channel::Sender<(WindowId, Weak<Mutex<WindowState>>)>,
     // for HasDisplayHandle
     connection: Connection,
+}
+impl WindowContext {
+    pub fn new(
+        state: &WaywinState,
+        signal: calloop::LoopSignal,
+        new_windows: calloop::channel::Sender<(WindowId, Weak<Mutex<WindowState>>)>,
+    ) -> Self {
+        Self {
+            qhandle: state.qhandle.clone(),
+            compositor: state.compositor.clone(),
+            xdg_wm_base: state.xdg_wm_base.clone(),
+            decoration: state.decoration.clone(),
+            viewporter: state.viewporter.clone(),
+            scaling: state.scaling.clone(),
+            shm: state.shm.clone(),
+            idle_inhibit_manager: state.idle_inhibit_manager.clone(),
+            shortcuts_inhibit_manager: state.shortcuts_inhibit_manager.clone(),
+            dialog_manager: state.dialog_manager.clone(),
+            activation: state.activation.clone(),
+            app_id: state.app_id.clone(),
+            seat: state.seats.first().map(|seat| seat.seat.clone()),
+            input_serials: state.input_serials.clone(),
+            pointer_state: state.pointer_state.clone(),
+            text_input_state: state.text_input_state.clone(),
+            output_state: state.output_state.clone(),
+            event_handlers: state.event_handlers.clone(),
+            signal,
+            new_windows,
+            connection: state.connection.clone(),
+        }
+    }
+    /// Adds a window to the event loop's list, which is busy dispatching right now.
+    fn register(&self, id: WindowId, weak: Weak<Mutex<WindowState>>) -> Result<(), Error> {
+        self.new_windows
+            .send((id, weak))
+            .map_err(|_| Error::WindowCreation("the event loop is gone".to_owned()))
+    }
+}
+
+/// The toplevel of a window, for others to be dialogs of.
+#[derive(Debug, Clone)]
+pub struct WindowParent(XdgToplevel);
+
+pub struct Window {
+    state: Arc<Mutex<WindowState>>,
+    context: WindowContext,
+
     // for id and HasWindowHandle
     surface: WlSurface,
 }
 impl Window {
-    pub fn new(waywin: &mut Waywin, title: &str) -> Result<Self, String> {
-        let freeze = waywin.state.qhandle.freeze();
+    pub fn new(waywin: &mut Waywin, builder: &WindowBuilder) -> Result<Self, Error> {
+        let (window, weak) = Self::create(&waywin.window_context, builder, None);
+        waywin.state.windows.push((window.id(), weak));
+        Ok(window)
+    }
+    pub fn from_context(context: &WindowContext, builder: &WindowBuilder) -> Result<Self, Error> {
+        let (window, weak) = Self::create(context, builder, None);
+        context.register(window.id(), weak)?;
+        Ok(window)
+    }
+    pub fn create_popup(
+        &self,
+        anchor: Rect,
+        offset: (i32, i32),
+        size: (f64, f64),
+    ) -> Result<Self, Error> {
+        let (parent, (width, height)) = {
+            let state = self.state.lock().unwrap();
+            // the compositor would dismiss it right away
+            if !state.is_mapped() {
+                return Err(Error::WindowCreation(
+                    "the parent of a popup has to be shown".to_owned(),
+                ));
+            }
+            (state.xdg_surface.clone(), state.state.surface_size(size))
+        };
+        let positioner = self
+            .context
+            .xdg_wm_base
+            .create_positioner(&self.context.qhandle, ());
+        positioner.set_size(width.max(1), height.max(1));
+        positioner.set_anchor_rect(
+            anchor.x,
+            anchor.y,
+            anchor.width.max(1),
+            anchor.height.max(1),
+        );
+        // below the anchor, like a dropdown
+        positioner.set_anchor(Anchor::BottomLeft);
+        positioner.set_gravity(Gravity::BottomRight);
+        positioner.set_constraint_adjustment(
+            ConstraintAdjustment::FlipY
+                | ConstraintAdjustment::SlideX
+                | ConstraintAdjustment::SlideY,
+        );
+        positioner.set_offset(offset.0, offset.1);
 
-        let state = Arc::new_cyclic(|weak| {
-            let surface = {
-                waywin
-                    .state
+        let (popup, weak) = Self::create(
+            &self.context,
+            &WindowBuilder::new(""),
+            Some((&parent, &positioner)),
+        );
+        positioner.destroy();
+        popup.state.lock().unwrap().state.size = (width, height);
+        self.context.register(popup.id(), weak)?;
+        Ok(popup)
+    }
+    /// A toplevel, or a popup of `parent` placed by `positioner`.
+    fn create(
+        context: &WindowContext,
+        builder: &WindowBuilder,
+        parent: Option<(&XdgSurface, &XdgPositioner)>,
+    ) -> (Self, Weak<Mutex<WindowState>>) {
+        let freeze = context.qhandle.freeze();
+
+        let state =
+            Arc::new_cyclic(|weak| {
+                let surface = context
                     .compositor
-                    .create_surface(&waywin.state.qhandle, weak.clone())
-            };
-            let xdg_surface = waywin.state.xdg_wm_base.get_xdg_surface(
-                &surface,
-                &waywin.state.qhandle,
-                weak.clone(),
-            );
-            let toplevel = xdg_surface.get_toplevel(&waywin.state.qhandle, weak.clone());
-            toplevel.set_title(title.to_owned());
-            toplevel.set_app_id(waywin.state.app_id.clone());
+                    .create_surface(&context.qhandle, weak.clone());
+                let xdg_surface =
+                    context
+                        .xdg_wm_base
+                        .get_xdg_surface(&surface, &context.qhandle, weak.clone());
+                let (toplevel, popup) = match parent {
+                    Some((parent, positioner)) => {
+                        let popup = xdg_surface.get_popup(
+                            Some(parent),
+                            positioner,
+                            &context.qhandle,
+                            weak.clone(),
+                        );
+                        // grabbing makes the compositor dismiss it on clicks elsewhere,
+                        // the seat that opened it is the one that got input last
+                        let input_serials = context.input_serials.lock().unwrap();
+                        if let Some((seat, serial)) = input_serials.latest() {
+                            popup.grab(seat, serial);
+                        }
+                        (None, Some(popup))
+                    }
+                    None => {
+                        let toplevel = xdg_surface.get_toplevel(&context.qhandle, weak.clone());
+                        toplevel.set_title(builder.title.clone());
+                        toplevel.set_app_id(context.app_id.clone());
+                        if let Some(WindowParent(parent)) = &builder.parent {
+                            toplevel.set_parent(Some(parent));
+                        }
+                        (Some(toplevel), None)
+                    }
+                };
 
-            let decoration = waywin.state.decoration.as_ref().map(|decoration| {
-                let decor =
-                    decoration.get_toplevel_decoration(&toplevel, &waywin.state.qhandle, ());
-                decor.set_mode(Mode::ServerSide);
-                decor
-            });
+                let decoration = toplevel.as_ref().zip(context.decoration.as_ref()).map(
+                    |(toplevel, decoration)| {
+                        let decor =
+                            decoration.get_toplevel_decoration(toplevel, &context.qhandle, ());
+                        decor.set_mode(Mode::ServerSide);
+                        decor
+                    },
+                );
 
-            let viewport_scaling = waywin
-                .state
-                .viewporter
-                .as_ref()
-                .zip(waywin.state.scaling.as_ref())
-                .map(|(viewporter, scaling)| {
-                    (
-                        viewporter.get_viewport(&surface, &waywin.state.qhandle, ()),
-                        scaling.get_fractional_scale(&surface, &waywin.state.qhandle, weak.clone()),
-                    )
-                });
+                // without the protocol it's only a dialog that doesn't block its parent
+                let dialog = toplevel
+                    .as_ref()
+                    .zip(context.dialog_manager.as_ref())
+                    .filter(|_| builder.modal && builder.parent.is_some())
+                    .map(|(toplevel, manager)| {
+                        let dialog = manager.get_xdg_dialog(toplevel, &context.qhandle, ());
+                        dialog.set_modal();
+                        dialog
+                    });
 
-            let state = State {
-                size: (800, 600),
-                scale: 1.0,
-            };
+                // without it the integer `PreferredBufferScale` is used
+                let viewport_scaling = context
+                    .viewporter
+                    .as_ref()
+                    .zip(context.scaling.as_ref())
+                    .filter(|_| builder.fractional_scaling)
+                    .map(|(viewporter, scaling)| {
+                        (
+                            viewporter.get_viewport(&surface, &context.qhandle, ()),
+                            scaling.get_fractional_scale(&surface, &context.qhandle, weak.clone()),
+                        )
+                    });
 
-            Mutex::new(WindowState {
-                surface,
-                xdg_surface,
-                toplevel,
-                _xdg_base: waywin.state.xdg_wm_base.clone(),
-                state,
-                prev_state: state,
-                configure: PendingConfigure { size: None },
-                redraw: true,
-                fullscreen: false,
-                locked_pointer: None,
-                confined_pointer: None,
-                viewport_scaling,
-                decoration,
-            })
-        });
+                let state = State {
+                    size: (800, 600),
+                    scale: 1.0,
+                };
+
+                Mutex::new(WindowState {
+                    surface,
+                    xdg_surface,
+                    toplevel,
+                    popup,
+                    _xdg_base: context.xdg_wm_base.clone(),
+                    state,
+                    prev_state: state,
+                    configure: PendingConfigure { size: None },
+                    redraw: true,
+                    damage: None,
+                    pending_damage: None,
+                    frame_callback: None,
+                    redraw_deadline: None,
+                    visible: builder.visible,
+                    configured: false,
+                    title: builder.title.clone(),
+                    fullscreen: false,
+                    suggested_max_size: None,
+                    wm_capabilities: WmCapabilities::ALL,
+                    occluded: false,
+                    min_size: None,
+                    max_size: None,
+                    resize_increments: None,
+                    aspect_ratio: None,
+                    theme: None,
+                    hittest: None,
+                    auto_opaque: !builder.transparent,
+                    locked_pointer: None,
+                    confined_pointer: None,
+                    grab_escape_key: None,
+                    modifiers: KeyModifiers::empty(),
+                    lock_state: LockState::default(),
+                    ime_allowed: false,
+                    ime_cursor_area: None,
+                    ime_enabled: false,
+                    outputs: vec![],
+                    prev_output: None,
+                    cursor: None,
+                    viewport_scaling,
+                    decoration,
+                    dialog,
+                    idle_inhibitor: None,
+                    shortcuts_inhibitor: None,
+                })
+            });
         let surface = state.lock().unwrap().surface.clone();
         let weak = Arc::downgrade(&state);
 
-        surface.commit();
-
-        waywin.state.windows.push(weak.clone());
+        // an uncommitted surface is never mapped
+        if builder.visible {
+            surface.commit();
+        }
 
         drop(freeze);
 
-        Ok(Self {
-            surface,
-            state,
-            qhandle: waywin.state.qhandle.clone(),
-            pointer_state: waywin.state.pointer_state.clone(),
-            connection: waywin.state.connection.clone(),
-            signal: waywin.event_loop.get_signal(),
-        })
+        (
+            Self {
+                surface,
+                state,
+                context: context.clone(),
+            },
+            weak,
+        )
     }
 }
 impl Window {
+    /// `None` for popups, they can't have dialogs.
+    pub fn as_parent(&self) -> Option<WindowParent> {
+        self.state
+            .lock()
+            .unwrap()
+            .toplevel
+            .clone()
+            .map(WindowParent)
+    }
     pub fn get_physical_size(&self) -> (u32, u32) {
         self.state.lock().unwrap().state.physical_size()
     }
     pub fn get_logical_size(&self) -> (f64, f64) {
         self.state.lock().unwrap().state.logical_size()
     }
+    pub fn size_at_last_event(&self) -> (f64, f64) {
+        self.state.lock().unwrap().prev_state.logical_size()
+    }
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.state.lock().unwrap().modifiers
+    }
+    pub fn lock_state(&self) -> LockState {
+        self.state.lock().unwrap().lock_state
+    }
     pub fn get_scale(&self) -> f64 {
         self.state.lock().unwrap().state.scale
     }
     pub fn set_title(&self, title: &str) {
-        self.state
-            .lock()
-            .unwrap()
-            .toplevel
-            .set_title(title.to_owned());
+        let mut state = self.state.lock().unwrap();
+        if let Some(toplevel) = &state.toplevel {
+            toplevel.set_title(title.to_owned());
+        }
+        state.title = title.to_owned();
+    }
+    pub fn get_title(&self) -> String {
+        self.state.lock().unwrap().title.clone()
+    }
+    pub fn set_app_id(&self, app_id: &str) {
+        if let Some(toplevel) = &self.state.lock().unwrap().toplevel {
+            toplevel.set_app_id(app_id.to_owned());
+        }
     }
     pub fn request_redraw(&self) {
-        self.state.lock().unwrap().redraw = true;
-        self.signal.wakeup();
+        self.state.lock().unwrap().request_redraw();
+        self.context.signal.wakeup();
     }
-    pub fn set_fullscreen(&self, fullscreen: bool) {
+    pub fn request_redraw_region(&self, rect: Rect) {
+        self.state.lock().unwrap().request_redraw_region(rect);
+        self.context.signal.wakeup();
+    }
+    pub fn request_redraw_at(&self, deadline: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .redraw_deadline
+            .is_none_or(|current| deadline < current)
+        {
+            state.redraw_deadline = Some(deadline);
+            self.context.signal.wakeup();
+        }
+    }
+    pub fn set_min_size(&self, size: Option<(f64, f64)>) {
+        let mut state = self.state.lock().unwrap();
+        state.min_size = size;
+        state.apply_size_constraints();
+        // for a present to commit them with
+        state.request_redraw();
+        self.context.signal.wakeup();
+    }
+    pub fn set_max_size(&self, size: Option<(f64, f64)>) {
+        let mut state = self.state.lock().unwrap();
+        state.max_size = size;
+        state.apply_size_constraints();
+        state.request_redraw();
+        self.context.signal.wakeup();
+    }
+    pub fn set_resize_increments(&self, increments: Option<(u32, u32)>) {
+        self.state.lock().unwrap().resize_increments = increments;
+    }
+    pub fn set_aspect_ratio(&self, ratio: Option<(u32, u32)>) {
+        self.state.lock().unwrap().aspect_ratio = ratio;
+    }
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        self.state.lock().unwrap().theme = theme;
+    }
+    pub fn theme(&self) -> Option<Theme> {
+        self.state.lock().unwrap().theme
+    }
+    pub fn set_corner_preference(&self, _preference: CornerPreference) {}
+    pub fn set_backdrop(&self, _backdrop: Backdrop) {}
+    pub fn set_taskbar_progress(&self, _progress: Option<Progress>) {}
+    pub fn set_hittest_handler(
+        &self,
+        handler: impl Fn(f64, f64) -> HitTestResult + Send + 'static,
+    ) {
+        self.state.lock().unwrap().hittest = Some(Box::new(handler));
+    }
+    pub fn set_content_protected(&self, protected: bool) -> bool {
+        if protected {
+            log::warn!("content protection isn't supported on wayland");
+        }
+        !protected
+    }
+    pub fn set_opaque_region(&self, region: Option<&[Rect]>) {
         let mut state = self.state.lock().unwrap();
-        if fullscreen {
-            state.toplevel.set_fullscreen(None);
+        state.auto_opaque = false;
+        state.set_opaque_region(&self.context.compositor, &self.context.qhandle, region);
+    }
+    pub fn set_content_geometry(&self, geometry: Rect) {
+        // an empty geometry is a protocol error
+        if geometry.width <= 0 || geometry.height <= 0 {
+            log::warn!("content geometry has to have a size, got {geometry:?}");
+            return;
+        }
+        self.state.lock().unwrap().xdg_surface.set_window_geometry(
+            geometry.x,
+            geometry.y,
+            geometry.width,
+            geometry.height,
+        );
+    }
+    pub fn set_visible(&self, visible: bool) {
+        let mut state = self.state.lock().unwrap();
+        if state.visible == visible {
+            return;
+        }
+        if visible && state.popup.is_some() {
+            log::warn!("a hidden popup can't be shown again");
+            return;
+        }
+        state.visible = visible;
+        state.configured = false;
+        // won't fire while unmapped
+        state.frame_callback = None;
+        if visible {
+            // the compositor replies with a configure like it would for a new window
+            state.redraw = true;
+        } else {
+            // a null buffer unmaps the window
+            state.surface.attach(None, 0, 0);
+        }
+        state.surface.commit();
+        self.context.signal.wakeup();
+    }
+    pub fn is_visible(&self) -> bool {
+        self.state.lock().unwrap().visible
+    }
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        // the compositor can't be asked for a mode, only for the output
+        let output = match &fullscreen {
+            Some(Fullscreen::Borderless(monitor)) => monitor
+                .as_ref()
+                .map(|monitor| monitor.backend_impl.output().clone()),
+            Some(Fullscreen::Exclusive(mode)) => self
+                .context
+                .output_state
+                .lock()
+                .unwrap()
+                .output(mode.monitor),
+            None => None,
+        };
+        let mut state = self.state.lock().unwrap();
+        let Some(toplevel) = &state.toplevel else {
+            return;
+        };
+        if fullscreen.is_some() {
+            toplevel.set_fullscreen(output.as_ref());
         } else {
-            state.toplevel.unset_fullscreen();
+            toplevel.unset_fullscreen();
         }
-        state.fullscreen = fullscreen;
+        state.fullscreen = fullscreen.is_some();
     }
     pub fn get_fullscreen(&self) -> bool {
         self.state.lock().unwrap().fullscreen
     }
+    pub fn suggested_max_size(&self) -> Option<(i32, i32)> {
+        self.state.lock().unwrap().suggested_max_size
+    }
+    pub fn wm_capabilities(&self) -> WmCapabilities {
+        self.state.lock().unwrap().wm_capabilities
+    }
+    pub fn set_idle_inhibited(&self, inhibited: bool) {
+        let Some(manager) = &self.context.idle_inhibit_manager else {
+            log::warn!("the compositor doesn't support idle inhibition");
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        match (inhibited, state.idle_inhibitor.take()) {
+            (true, None) => {
+                state.idle_inhibitor =
+                    Some(manager.create_inhibitor(&state.surface, &self.context.qhandle, ()));
+            }
+            (false, Some(inhibitor)) => inhibitor.destroy(),
+            (_, inhibitor) => state.idle_inhibitor = inhibitor,
+        }
+    }
+    pub fn set_keyboard_grab(&self, grab: bool) {
+        let Some((manager, seat)) = self
+            .context
+            .shortcuts_inhibit_manager
+            .as_ref()
+            .zip(self.context.seat.as_ref())
+        else {
+            log::warn!("the compositor doesn't support inhibiting keyboard shortcuts");
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        match (grab, state.shortcuts_inhibitor.take()) {
+            (true, None) => {
+                state.shortcuts_inhibitor = Some(manager.inhibit_shortcuts(
+                    &state.surface,
+                    seat,
+                    &self.context.qhandle,
+                    (),
+                ));
+            }
+            (false, Some(inhibitor)) => inhibitor.destroy(),
+            (_, inhibitor) => state.shortcuts_inhibitor = inhibitor,
+        }
+    }
 
-    pub fn lock_pointer(&self) {
-        let pointer_state = self.pointer_state.lock().unwrap();
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        let pointer_state = self.context.pointer_state.lock().unwrap();
         let mut state = self.state.lock().unwrap();
-        if let Some((pointer_constraints, pointer)) = pointer_state
+        state.release_grab();
+        let Some((pointer_constraints, pointer)) = pointer_state
             .pointer_constraints
             .as_ref()
             .zip(pointer_state.pointer.as_ref())
-        {
-            state.unlock_pointer();
-            state.unconfine_pointer();
-            let locked_pointer = pointer_constraints.lock_pointer(
-                &self.surface,
-                pointer,
-                None,
-                Lifetime::Persistent,
-                &self.qhandle,
-                (),
-            );
-            state.locked_pointer = Some(locked_pointer);
+        else {
+            return;
+        };
+        match mode {
+            CursorGrabMode::None => {}
+            CursorGrabMode::Confined => {
+                state.confined_pointer = Some(pointer_constraints.confine_pointer(
+                    &self.surface,
+                    pointer,
+                    None,
+                    Lifetime::Persistent,
+                    &self.context.qhandle,
+                    (),
+                ));
+            }
+            CursorGrabMode::Locked => {
+                state.locked_pointer = Some(pointer_constraints.lock_pointer(
+                    &self.surface,
+                    pointer,
+                    None,
+                    Lifetime::Persistent,
+                    &self.context.qhandle,
+                    (),
+                ));
+            }
         }
     }
-    pub fn unlock_pointer(&self) {
-        self.state.lock().unwrap().unlock_pointer();
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        let state = self.state.lock().unwrap();
+        if state.locked_pointer.is_some() {
+            CursorGrabMode::Locked
+        } else if state.confined_pointer.is_some() {
+            CursorGrabMode::Confined
+        } else {
+            CursorGrabMode::None
+        }
     }
-    pub fn is_pointer_locked(&self) -> bool {
-        self.state.lock().unwrap().locked_pointer.is_some()
+    pub fn set_grab_escape_key(&self, key: Option<PhysicalKey>) {
+        self.state.lock().unwrap().grab_escape_key = key;
     }
 
-    pub fn confine_pointer(&self) {
-        let pointer_state = self.pointer_state.lock().unwrap();
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        let text_input_state = self.context.text_input_state.lock().unwrap();
         let mut state = self.state.lock().unwrap();
-        if let Some((pointer_constraints, pointer)) = pointer_state
-            .pointer_constraints
+        if state.ime_allowed == allowed {
+            return;
+        }
+        state.ime_allowed = allowed;
+        if text_input_state.focused_window == Some(self.id()) {
+            text_input_state.set_enabled(allowed, state.ime_cursor_area);
+            // `Enabled`/`Disabled` are sent by the event loop
+            self.context.signal.wakeup();
+        }
+    }
+    pub fn set_ime_cursor_area(&self, area: Rect) {
+        let text_input_state = self.context.text_input_state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+        state.ime_cursor_area = Some(area);
+        if state.ime_allowed && text_input_state.focused_window == Some(self.id()) {
+            text_input_state.set_cursor_area(area);
+        }
+    }
+
+    #[cfg(feature = "present")]
+    pub fn present_buffer(&self, size: (u32, u32), pixels: &[u32]) -> Result<(), Error> {
+        let shm =
+            self.context.shm.as_ref().ok_or_else(|| {
+                Error::Present("the compositor doesn't support wl_shm".to_owned())
+            })?;
+        // attaching a buffer before the first configure is a protocol error
+        if !self.state.lock().unwrap().is_mapped() {
+            return Err(Error::Present("the window isn't shown yet".to_owned()));
+        }
+        // the whole buffer is new unless the last paint said otherwise
+        let damaged = self.pre_present_state();
+        super::present::present_buffer(
+            &self.surface,
+            shm,
+            &self.context.qhandle,
+            size,
+            pixels,
+            !damaged,
+        )
+    }
+    pub fn pre_present(&self) {
+        self.pre_present_state();
+    }
+    /// Damage and frame callback for the commit of a frame.
+    /// Returns whether only part of the buffer was damaged.
+    fn pre_present_state(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.request_frame(&self.context.qhandle, Arc::downgrade(&self.state));
+        state.send_pending_damage()
+    }
+    pub fn set_custom_cursor(&self, image: &CursorImage) -> Result<(), Error> {
+        let shm = self
+            .context
+            .shm
             .as_ref()
-            .zip(pointer_state.pointer.as_ref())
+            .ok_or_else(|| Error::Cursor("the compositor doesn't support wl_shm".to_owned()))?;
+        let cursor =
+            CustomCursor::new(image, &self.context.compositor, shm, &self.context.qhandle)?;
+
+        let pointer_state = self.context.pointer_state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
+        if let Some(pointer) = pointer_state
+            .pointer
+            .as_ref()
+            .filter(|_| pointer_state.focused_window == Some(self.id()))
         {
-            state.unconfine_pointer();
-            state.unlock_pointer();
-            let confined_pointer = pointer_constraints.confine_pointer(
-                &self.surface,
-                pointer,
-                None,
-                Lifetime::Persistent,
-                &self.qhandle,
-                (),
-            );
-            state.confined_pointer = Some(confined_pointer);
+            cursor.apply(pointer, pointer_state.enter_serial);
         }
+        state.cursor = Some(cursor);
+        Ok(())
     }
-    pub fn unconfine_pointer(&self) {
-        self.state.lock().unwrap().unconfine_pointer();
+
+    pub fn pointer_position(&self) -> Option<(f64, f64)> {
+        let pointer_state = self.context.pointer_state.lock().unwrap();
+        (pointer_state.focused_window == Some(self.id())).then_some(pointer_state.position)
     }
-    pub fn is_pointer_confined(&self) -> bool {
-        self.state.lock().unwrap().confined_pointer.is_some()
+
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        let output = self.state.lock().unwrap().current_output()?.clone();
+        self.context.output_state.lock().unwrap().monitor(&output)
     }
 
-    pub fn id(&self) -> usize {
-        self.surface.id().as_ptr() as usize
+    pub fn id(&self) -> WindowId {
+        WindowId(self.surface.id().as_ptr() as usize)
+    }
+    pub fn raise(&self) -> bool {
+        false
+    }
+    pub fn lower(&self) -> bool {
+        false
+    }
+    pub fn focus(&self) -> bool {
+        let Some(activation) = &self.context.activation else {
+            log::warn!("xdg_activation_v1 isn't supported, the window can't be focused");
+            return false;
+        };
+        // activated once the token is done
+        let token = activation.get_activation_token(&self.context.qhandle, self.surface.clone());
+        // compositors prefer tokens tied to recent input
+        if let Some((seat, serial)) = self.context.input_serials.lock().unwrap().latest() {
+            token.set_serial(serial, seat);
+        }
+        token.set_surface(&self.surface);
+        token.commit();
+        true
+    }
+    pub fn set_event_handler(&self, handler: impl FnMut(WindowEvent) + Send + 'static) {
+        self.context
+            .event_handlers
+            .lock()
+            .unwrap()
+            .insert(self.id(), Box::new(handler));
+    }
+    pub fn surface_ptr(&self) -> *mut std::ffi::c_void {
+        self.surface.id().as_ptr() as *mut _
     }
 }
 
 impl rwh::HasWindowHandle for Window {
     fn window_handle(&self) -> std::result::Result<rwh::WindowHandle<'_>, rwh::HandleError> {
-        let ptr = self.surface.id().as_ptr();
-        let handle = rwh::WaylandWindowHandle::new(NonNull::new(ptr as *mut _).unwrap());
+        let handle = rwh::WaylandWindowHandle::new(NonNull::new(self.surface_ptr()).unwrap());
         unsafe { Ok(rwh::WindowHandle::borrow_raw(handle.into())) }
     }
 }
+impl std::fmt::Debug for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Window")
+            .field("id", &state.id())
+            .field("size", &state.state.logical_size())
+            .field("scale", &state.state.scale)
+            .field("fullscreen", &state.fullscreen)
+            .finish_non_exhaustive()
+    }
+}
 impl rwh::HasDisplayHandle for Window {
     fn display_handle(&self) -> std::result::Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
-        let ptr = self.connection.display().id().as_ptr();
+        let ptr = self.context.connection.display().id().as_ptr();
         let handle = rwh::WaylandDisplayHandle::new(NonNull::new(ptr as *mut _).unwrap());
         unsafe { Ok(rwh::DisplayHandle::borrow_raw(handle.into())) }
     }
 }
 
 delegate_noop!(WaywinState: WpViewport);
+delegate_noop!(WaywinState: WlRegion);
 delegate_noop!(WaywinState: ignore ZxdgToplevelDecorationV1);
 delegate_noop!(WaywinState: ignore ZwpLockedPointerV1);
 delegate_noop!(WaywinState: ignore ZwpConfinedPointerV1);
+delegate_noop!(WaywinState: ZwpIdleInhibitorV1);
+delegate_noop!(WaywinState: ignore ZwpKeyboardShortcutsInhibitorV1);
+delegate_noop!(WaywinState: XdgDialogV1);
+delegate_noop!(WaywinState: XdgPositioner);
 
 impl Dispatch<WlSurface, Weak<Mutex<WindowState>>> for WaywinState {
     fn event(
@@ -336,8 +1147,12 @@ impl Dispatch<WlSurface, Weak<Mutex<WindowState>>> for WaywinState {
         let mut data = data.lock().unwrap();
 
         match event {
-            wl_surface::Event::Enter { output: _ } => {}
-            wl_surface::Event::Leave { output: _ } => {}
+            wl_surface::Event::Enter { output } => {
+                data.outputs.push(output);
+            }
+            wl_surface::Event::Leave { output } => {
+                data.leave_output(&output);
+            }
             wl_surface::Event::PreferredBufferScale { factor } => {
                 // if fractional scaling is supported
                 // ignore this surface event
@@ -358,18 +1173,22 @@ impl Dispatch<WlSurface, Weak<Mutex<WindowState>>> for WaywinState {
 impl Dispatch<WlCallback, Weak<Mutex<WindowState>>> for WaywinState {
     fn event(
         _state: &mut Self,
-        _proxy: &WlCallback,
+        proxy: &WlCallback,
         event: <WlCallback as wayland_client::Proxy>::Event,
         data: &Weak<Mutex<WindowState>>,
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
-        let Some(_data) = data.upgrade() else {
+        let Some(data) = data.upgrade() else {
             return;
         };
+        let mut data = data.lock().unwrap();
         match event {
             wl_callback::Event::Done { callback_data: _ } => {
-                todo!()
+                // an outdated callback can still fire after the window was hidden
+                if data.frame_callback.as_ref() == Some(proxy) {
+                    data.frame_callback = None;
+                }
             }
             _ => unimplemented!(),
         }
@@ -377,7 +1196,7 @@ impl Dispatch<WlCallback, Weak<Mutex<WindowState>>> for WaywinState {
 }
 impl Dispatch<XdgSurface, Weak<Mutex<WindowState>>> for WaywinState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         proxy: &XdgSurface,
         event: <XdgSurface as wayland_client::Proxy>::Event,
         data: &Weak<Mutex<WindowState>>,
@@ -392,16 +1211,26 @@ impl Dispatch<XdgSurface, Weak<Mutex<WindowState>>> for WaywinState {
                     return;
                 };
                 let mut data = data.lock().unwrap();
+                let data = &mut *data;
+                data.configured = true;
 
-                match data.configure.size {
-                    Some(configure_size) => {
-                        data.state.size = configure_size;
-                    }
-                    None => data.configure.size = Some(data.state.size),
-                }
+                data.configure.apply(&mut data.state);
                 if let Some((viewport, _)) = &data.viewport_scaling {
                     viewport.set_destination(data.state.size.0, data.state.size.1);
                 }
+                if data.auto_opaque {
+                    let (width, height) = data.state.size;
+                    data.set_opaque_region(
+                        &state.compositor,
+                        &state.qhandle,
+                        Some(&[Rect {
+                            x: 0,
+                            y: 0,
+                            width,
+                            height,
+                        }]),
+                    );
+                }
             }
             _ => unimplemented!(),
         }
@@ -425,28 +1254,152 @@ impl Dispatch<XdgToplevel, Weak<Mutex<WindowState>>> for WaywinState {
             xdg_toplevel::Event::Configure {
                 width,
                 height,
-                states: _,
+                states,
             } => {
+                let states: Vec<_> = u32_array(&states)
+                    .filter_map(|s| xdg_toplevel::State::try_from(s).ok())
+                    .collect();
+                // these sizes have to be used as they are
+                let constrained = states.iter().any(|s| {
+                    matches!(
+                        s,
+                        xdg_toplevel::State::Maximized
+                            | xdg_toplevel::State::Fullscreen
+                            | xdg_toplevel::State::TiledLeft
+                            | xdg_toplevel::State::TiledRight
+                            | xdg_toplevel::State::TiledTop
+                            | xdg_toplevel::State::TiledBottom
+                    )
+                });
                 if !(width == 0 || height == 0) {
-                    data.configure.size = Some((width, height))
+                    let mut size = (width, height);
+                    if !constrained {
+                        if let Some((inc_w, inc_h)) = data.resize_increments {
+                            size = (snap_down(size.0, inc_w), snap_down(size.1, inc_h));
+                        }
+                        if let Some(ratio) = data.aspect_ratio {
+                            size = keep_aspect_ratio(size, data.state.size, ratio);
+                        }
+                    }
+                    data.configure.size = Some(size);
                 } else {
                     data.configure.size = None;
                 }
+                let suspended = states.contains(&xdg_toplevel::State::Suspended);
+                if data.occluded != suspended {
+                    data.occluded = suspended;
+                    state.events.push(WaywinEvent::WindowEvent {
+                        event: WindowEvent::Occluded(suspended),
+                        window_id: data.id(),
+                    });
+                }
             }
             xdg_toplevel::Event::Close => {
                 state.events.push(WaywinEvent::WindowEvent {
-                    event: WindowEvent::Close,
+                    event: WindowEvent::CloseRequested,
                     window_id: data.id(),
                 });
             }
-            xdg_toplevel::Event::ConfigureBounds {
-                width: _,
-                height: _,
+            xdg_toplevel::Event::ConfigureBounds { width, height } => {
+                data.suggested_max_size = (width > 0 && height > 0).then_some((width, height));
+            }
+            xdg_toplevel::Event::WmCapabilities { capabilities } => {
+                let capabilities: Vec<_> = u32_array(&capabilities)
+                    .filter_map(|c| xdg_toplevel::WmCapabilities::try_from(c).ok())
+                    .collect();
+                let has = |capability| capabilities.contains(&capability);
+                data.wm_capabilities = WmCapabilities {
+                    maximize: has(xdg_toplevel::WmCapabilities::Maximize),
+                    minimize: has(xdg_toplevel::WmCapabilities::Minimize),
+                    fullscreen: has(xdg_toplevel::WmCapabilities::Fullscreen),
+                    window_menu: has(xdg_toplevel::WmCapabilities::WindowMenu),
+                };
+            }
+            _ => unimplemented!(),
+        }
+    }
+}
+/// Rounds `size` down to a multiple of `increment`, keeping at least one.
+fn snap_down(size: i32, increment: u32) -> i32 {
+    let increment = (increment as i32).max(1);
+    (size - size % increment).max(increment)
+}
+/// Changes `size` to `ratio` following the dimension that changed from `current`,
+/// or the larger one if both did so the other one doesn't jitter.
+fn keep_aspect_ratio(size: (i32, i32), current: (i32, i32), ratio: (u32, u32)) -> (i32, i32) {
+    let (ratio_w, ratio_h) = (ratio.0.max(1) as f64, ratio.1.max(1) as f64);
+    let follow_width = match (size.0 != current.0, size.1 != current.1) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => size.0 as f64 / ratio_w >= size.1 as f64 / ratio_h,
+    };
+    if follow_width {
+        let height = (size.0 as f64 * ratio_h / ratio_w).round() as i32;
+        (size.0, height.max(1))
+    } else {
+        let width = (size.1 as f64 * ratio_w / ratio_h).round() as i32;
+        (width.max(1), size.1)
+    }
+}
+/// Wayland arrays of enums are native endian u32s.
+fn u32_array(array: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    array
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+}
+impl Dispatch<XdgPopup, Weak<Mutex<WindowState>>> for WaywinState {
+    fn event(
+        state: &mut Self,
+        _proxy: &XdgPopup,
+        event: <XdgPopup as wayland_client::Proxy>::Event,
+        data: &Weak<Mutex<WindowState>>,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(data) = data.upgrade() else {
+            return;
+        };
+        let mut data = data.lock().unwrap();
+
+        match event {
+            xdg_popup::Event::Configure {
+                x: _,
+                y: _,
+                width,
+                height,
             } => {
-                // TODO
+                data.configure.size = Some((width, height));
             }
-            xdg_toplevel::Event::WmCapabilities { capabilities: _ } => {
-                // TODO
+            xdg_popup::Event::PopupDone => {
+                // already unmapped by the compositor and can't come back
+                data.visible = false;
+                data.configured = false;
+                data.frame_callback = None;
+                state.events.push(WaywinEvent::WindowEvent {
+                    event: WindowEvent::CloseRequested,
+                    window_id: data.id(),
+                });
+            }
+            xdg_popup::Event::Repositioned { token: _ } => {}
+            _ => unimplemented!(),
+        }
+    }
+}
+impl Dispatch<XdgActivationTokenV1, WlSurface> for WaywinState {
+    fn event(
+        state: &mut Self,
+        proxy: &XdgActivationTokenV1,
+        event: <XdgActivationTokenV1 as Proxy>::Event,
+        data: &WlSurface,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_activation_token_v1::Event::Done { token } => {
+                if let Some(activation) = &state.activation {
+                    activation.activate(token, data);
+                }
+                proxy.destroy();
             }
             _ => unimplemented!(),
         }
@@ -476,3 +1429,88 @@ impl Dispatch<WpFractionalScaleV1, Weak<Mutex<WindowState>>> for WaywinState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_constraints_follow_scale() {
+        let min_size = Some((100.3, 50.0));
+        let max_size = Some((333.3, 200.0));
+        let mut state = State {
+            size: (400, 300),
+            scale: 1.0,
+        };
+        assert_eq!(
+            size_constraints(state, min_size, max_size),
+            ((100, 50), (333, 200))
+        );
+
+        // 200.6 physical pixels round to 201, half of which is 100.5
+        state.scale = 2.0;
+        assert_eq!(
+            size_constraints(state, min_size, max_size),
+            ((101, 50), (334, 200))
+        );
+
+        // back down to 150 physical pixels, while 499.95 rounds up to 500
+        state.scale = 1.5;
+        assert_eq!(
+            size_constraints(state, min_size, max_size),
+            ((100, 50), (334, 200))
+        );
+        assert_eq!(size_constraints(state, None, None), ((0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn rapid_resizes_send_the_final_size() {
+        let prev = State {
+            size: (800, 600),
+            scale: 1.0,
+        };
+        let mut state = prev;
+        let mut configure = PendingConfigure::default();
+        // both come in before the event loop gets to look at the window
+        for size in [(1000, 700), (1200, 900)] {
+            configure.size = Some(size);
+            configure.apply(&mut state);
+        }
+        let [scaled, resized] = state.changes_since(&prev);
+        assert!(scaled.is_none());
+        assert!(matches!(
+            resized,
+            Some(WindowEvent::Resized {
+                width: 1200,
+                height: 900
+            })
+        ));
+        assert_eq!(state.logical_size(), (1200.0, 900.0));
+
+        // resized and back again, there's nothing to tell
+        configure.size = Some(prev.size);
+        configure.apply(&mut state);
+        assert!(matches!(state.changes_since(&prev), [None, None]));
+    }
+
+    #[test]
+    fn scale_change_keeping_the_buffer_size_isnt_a_resize() {
+        let prev = State {
+            size: (800, 600),
+            scale: 1.0,
+        };
+        let state = State {
+            size: (400, 300),
+            scale: 2.0,
+        };
+        let [scaled, resized] = state.changes_since(&prev);
+        assert!(matches!(
+            scaled,
+            Some(WindowEvent::ScaleFactorChanged {
+                suggested_size: (800, 600),
+                ..
+            })
+        ));
+        assert!(resized.is_none());
+    }
+}
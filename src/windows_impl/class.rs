@@ -1,33 +1,55 @@
 use super::utils::{instance, PWSTRING};
-use std::num::NonZero;
+use crate::Error;
+use std::{
+    num::NonZero,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use windows::{
     core::PCWSTR,
-    Win32::UI::WindowsAndMessaging::{
-        RegisterClassExW, UnregisterClassW, CS_HREDRAW, CS_VREDRAW, WNDCLASSEXW,
+    Win32::{
+        Foundation::ERROR_CLASS_ALREADY_EXISTS,
+        UI::WindowsAndMessaging::{
+            RegisterClassExW, UnregisterClassW, CS_HREDRAW, CS_VREDRAW, WNDCLASSEXW, WNDPROC,
+        },
     },
 };
 
+/// Only ever a class registered by itself, shared by the windows using it
+/// so it's unregistered after the last one is gone.
 pub struct WindowClass {
     name: PWSTRING,
     // _atom: NonZero<u16>,
 }
 impl WindowClass {
-    pub fn new(name: &str) -> Result<Self, String> {
-        let name = PWSTRING::from(name);
-
-        let win_class = WNDCLASSEXW {
-            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
-            style: CS_HREDRAW | CS_VREDRAW | windows::Win32::UI::WindowsAndMessaging::CS_OWNDC,
-            lpfnWndProc: Some(super::window::wndproc),
-            hInstance: instance(),
-            lpszClassName: name.as_pcwstr(),
-            ..Default::default()
-        };
-        let _atom = NonZero::new(unsafe { RegisterClassExW(&win_class) })
-            .ok_or_else(windows::core::Error::from_win32)
-            .map_err(|err| format!("failed to register class: {err}"))?;
+    /// A counter is appended to `name` so that several `Waywin`s,
+    /// on the same thread or not, can be initialized with the same one.
+    /// Names already taken, e.g. by a host application, are skipped.
+    pub fn new(name: &str, wndproc: WNDPROC) -> Result<Self, Error> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-        Ok(Self { name })
+        loop {
+            let unique_name = PWSTRING::from(
+                format!("{name}-{}", COUNTER.fetch_add(1, Ordering::Relaxed)).as_str(),
+            );
+            let win_class = WNDCLASSEXW {
+                cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+                style: CS_HREDRAW | CS_VREDRAW | windows::Win32::UI::WindowsAndMessaging::CS_OWNDC,
+                lpfnWndProc: wndproc,
+                hInstance: instance(),
+                lpszClassName: unique_name.as_pcwstr(),
+                ..Default::default()
+            };
+            if NonZero::new(unsafe { RegisterClassExW(&win_class) }).is_some() {
+                return Ok(Self { name: unique_name });
+            }
+            let err = windows::core::Error::from_win32();
+            // someone else's class has a different window procedure, so it can't be reused
+            if err.code() != ERROR_CLASS_ALREADY_EXISTS.to_hresult() {
+                return Err(Error::WindowCreation(format!(
+                    "failed to register class: {err}"
+                )));
+            }
+        }
     }
 
     pub fn name(&self) -> PCWSTR {
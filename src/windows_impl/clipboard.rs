@@ -0,0 +1,89 @@
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HGLOBAL},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+pub struct Clipboard;
+impl Clipboard {
+    pub fn get_text(&self) -> Option<String> {
+        let _guard = OpenGuard::open()?;
+
+        let handle = unsafe { GetClipboardData(CF_UNICODETEXT.0 as u32) }.ok()?;
+        let global = HGLOBAL(handle.0);
+        let ptr = unsafe { GlobalLock(global) } as *const u16;
+        if ptr.is_null() {
+            log::error!("failed to lock clipboard data");
+            return None;
+        }
+        let len = (0..).take_while(|&i| unsafe { *ptr.add(i) } != 0).count();
+        let text = String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(ptr, len) });
+        let _ = unsafe { GlobalUnlock(global) };
+        Some(text)
+    }
+    pub fn set_text(&self, text: &str) {
+        let Some(_guard) = OpenGuard::open() else {
+            return;
+        };
+        if let Err(err) = unsafe { EmptyClipboard() } {
+            log::error!("failed to empty clipboard: {err}");
+            return;
+        }
+
+        let text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        let size = std::mem::size_of_val(text.as_slice());
+        let global = match unsafe { GlobalAlloc(GMEM_MOVEABLE, size) } {
+            Ok(global) => global,
+            Err(err) => {
+                log::error!("failed to allocate clipboard data: {err}");
+                return;
+            }
+        };
+        let ptr = unsafe { GlobalLock(global) } as *mut u16;
+        if ptr.is_null() {
+            log::error!("failed to lock clipboard data");
+            let _ = unsafe { GlobalFree(Some(global)) };
+            return;
+        }
+        unsafe { std::ptr::copy_nonoverlapping(text.as_ptr(), ptr, text.len()) };
+        let _ = unsafe { GlobalUnlock(global) };
+
+        // the clipboard owns the memory once it's set
+        if let Err(err) =
+            unsafe { SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(global.0))) }
+        {
+            log::error!("failed to set clipboard data: {err}");
+            let _ = unsafe { GlobalFree(Some(global)) };
+        }
+    }
+    pub fn get_primary_text(&self) -> Option<String> {
+        None
+    }
+    pub fn set_primary_text(&self, _text: &str) {}
+}
+
+/// Only one window can have the clipboard open at a time, so it's closed as soon as possible.
+struct OpenGuard;
+impl OpenGuard {
+    fn open() -> Option<Self> {
+        match unsafe { OpenClipboard(None) } {
+            Ok(()) => Some(Self),
+            Err(err) => {
+                log::error!("failed to open clipboard: {err}");
+                None
+            }
+        }
+    }
+}
+impl Drop for OpenGuard {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { CloseClipboard() } {
+            log::error!("failed to close clipboard: {err}");
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{CursorImage, Error};
+use windows::Win32::{
+    Graphics::Gdi::{
+        CreateBitmap, CreateDIBSection, DeleteObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        DIB_RGB_COLORS,
+    },
+    UI::WindowsAndMessaging::{CreateIconIndirect, HCURSOR, ICONINFO},
+};
+
+pub fn create_cursor(image: &CursorImage) -> Result<HCURSOR, Error> {
+    let (width, height) = (image.width as i32, image.height as i32);
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // top-down
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    let color = unsafe { CreateDIBSection(None, &info, DIB_RGB_COLORS, &mut bits, None, 0) }
+        .map_err(|err| Error::Cursor(format!("failed to create bitmap: {err}")))?;
+    // bgra
+    let pixels = unsafe { std::slice::from_raw_parts_mut(bits as *mut u8, image.rgba.len()) };
+    for (bgra, rgba) in pixels.chunks_exact_mut(4).zip(image.rgba.chunks_exact(4)) {
+        bgra.copy_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+    }
+    // unused with an alpha channel but still required
+    let mask = unsafe { CreateBitmap(width, height, 1, 1, None) };
+
+    let icon_info = ICONINFO {
+        fIcon: false.into(),
+        xHotspot: image.hotspot_x,
+        yHotspot: image.hotspot_y,
+        hbmMask: mask,
+        hbmColor: color,
+    };
+    let icon = unsafe { CreateIconIndirect(&icon_info) };
+    // the icon has its own copies
+    unsafe {
+        let _ = DeleteObject(color.into());
+        let _ = DeleteObject(mask.into());
+    }
+    icon.map(|icon| HCURSOR(icon.0))
+        .map_err(|err| Error::Cursor(format!("failed to create cursor: {err}")))
+}
@@ -0,0 +1,141 @@
+use crate::event::WindowEvent;
+use std::{cell::RefCell, ffi::OsString, os::windows::ffi::OsStringExt, path::PathBuf};
+use windows::{
+    core::{implement, Ref, Result},
+    Win32::{
+        Foundation::{HWND, POINTL},
+        System::{
+            Com::{IDataObject, DVASPECT_CONTENT, FORMATETC, TYMED_HGLOBAL},
+            Ole::{
+                IDropTarget, IDropTarget_Impl, OleInitialize, OleUninitialize, RegisterDragDrop,
+                ReleaseStgMedium, RevokeDragDrop, CF_HDROP, DROPEFFECT, DROPEFFECT_COPY,
+                DROPEFFECT_NONE,
+            },
+            SystemServices::MODIFIERKEYS_FLAGS,
+        },
+        UI::Shell::{DragQueryFileW, HDROP},
+    },
+};
+
+/// Receives the files dragged over a window while `WM_DROPFILES` only reports the drop.
+#[implement(IDropTarget)]
+struct DropTarget {
+    hook: Box<dyn Fn(WindowEvent)>,
+    /// The files of the drag currently over the window.
+    hovered: RefCell<Vec<PathBuf>>,
+}
+impl DropTarget {
+    fn effect(&self) -> DROPEFFECT {
+        if self.hovered.borrow().is_empty() {
+            DROPEFFECT_NONE
+        } else {
+            DROPEFFECT_COPY
+        }
+    }
+}
+impl IDropTarget_Impl for DropTarget_Impl {
+    fn DragEnter(
+        &self,
+        data: Ref<'_, IDataObject>,
+        _keys: MODIFIERKEYS_FLAGS,
+        _point: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        let paths = data.as_ref().map(data_paths).unwrap_or_default();
+        for path in &paths {
+            (self.hook)(WindowEvent::HoveredFile(path.clone()));
+        }
+        *self.hovered.borrow_mut() = paths;
+        unsafe { *effect = self.effect() };
+        Ok(())
+    }
+    fn DragOver(
+        &self,
+        _keys: MODIFIERKEYS_FLAGS,
+        _point: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        unsafe { *effect = self.effect() };
+        Ok(())
+    }
+    fn DragLeave(&self) -> Result<()> {
+        if !self.hovered.take().is_empty() {
+            (self.hook)(WindowEvent::HoveredFileCancelled);
+        }
+        Ok(())
+    }
+    fn Drop(
+        &self,
+        _data: Ref<'_, IDataObject>,
+        _keys: MODIFIERKEYS_FLAGS,
+        _point: &POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        unsafe { *effect = self.effect() };
+        for path in self.hovered.take() {
+            (self.hook)(WindowEvent::DroppedFile(path));
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a window registered as a drop target, revoked before the window is destroyed.
+pub struct DropRegistration {
+    hwnd: HWND,
+}
+impl DropRegistration {
+    /// `hook` gets the window's `HoveredFile`, `HoveredFileCancelled` and `DroppedFile` events.
+    pub fn new(hwnd: HWND, hook: impl Fn(WindowEvent) + 'static) -> Result<Self> {
+        // balanced in drop, also when ole was already initialized
+        unsafe { OleInitialize(None) }?;
+        let target: IDropTarget = DropTarget {
+            hook: Box::new(hook),
+            hovered: RefCell::default(),
+        }
+        .into();
+        if let Err(err) = unsafe { RegisterDragDrop(hwnd, &target) } {
+            unsafe { OleUninitialize() };
+            return Err(err);
+        }
+        Ok(Self { hwnd })
+    }
+}
+impl Drop for DropRegistration {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { RevokeDragDrop(self.hwnd) } {
+            log::error!("failed to revoke drop target: {err}");
+        }
+        unsafe { OleUninitialize() };
+    }
+}
+
+/// The files of an `HDROP`, e.g. from `WM_DROPFILES`.
+pub fn query_paths(hdrop: HDROP) -> Vec<PathBuf> {
+    let count = unsafe { DragQueryFileW(hdrop, u32::MAX, None) };
+    (0..count)
+        .map(|i| {
+            // without the nul terminator
+            let len = unsafe { DragQueryFileW(hdrop, i, None) } as usize;
+            let mut buf = vec![0u16; len + 1];
+            let len = unsafe { DragQueryFileW(hdrop, i, Some(&mut buf)) } as usize;
+            PathBuf::from(OsString::from_wide(&buf[..len]))
+        })
+        .collect()
+}
+
+/// Empty if the dragged data isn't files.
+fn data_paths(data: &IDataObject) -> Vec<PathBuf> {
+    let format = FORMATETC {
+        cfFormat: CF_HDROP.0,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    };
+    let Ok(mut medium) = (unsafe { data.GetData(&format) }) else {
+        return Vec::new();
+    };
+    let paths = query_paths(HDROP(unsafe { medium.u.hGlobal.0 }));
+    unsafe { ReleaseStgMedium(&mut medium) };
+    paths
+}
@@ -0,0 +1,343 @@
+use crate::event::{Key, KeyCode, KeyLocation, LogicalKey, PhysicalKey};
+use smol_str::SmolStr;
+use windows::Win32::{
+    Foundation::HWND,
+    UI::{
+        Input::KeyboardAndMouse::{
+            GetKeyboardState, MapVirtualKeyW, ToUnicode, MAPVK_VK_TO_CHAR, VIRTUAL_KEY, VK_0, VK_9,
+            VK_ADD, VK_APPS, VK_BACK, VK_BROWSER_BACK, VK_BROWSER_FAVORITES, VK_BROWSER_FORWARD,
+            VK_BROWSER_HOME, VK_BROWSER_REFRESH, VK_BROWSER_SEARCH, VK_BROWSER_STOP, VK_CAPITAL,
+            VK_CLEAR, VK_CONTROL, VK_DECIMAL, VK_DELETE, VK_DIVIDE, VK_DOWN, VK_END, VK_ESCAPE,
+            VK_F1, VK_F24, VK_HOME, VK_INSERT, VK_LCONTROL, VK_LEFT, VK_LMENU, VK_LSHIFT, VK_LWIN,
+            VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_MEDIA_STOP, VK_MENU,
+            VK_MULTIPLY, VK_NEXT, VK_NUMLOCK, VK_NUMPAD0, VK_NUMPAD9, VK_PAUSE, VK_PRIOR,
+            VK_RCONTROL, VK_RETURN, VK_RIGHT, VK_RMENU, VK_RSHIFT, VK_RWIN, VK_SCROLL, VK_SHIFT,
+            VK_SNAPSHOT, VK_SPACE, VK_SUBTRACT, VK_TAB, VK_UP, VK_VOLUME_DOWN, VK_VOLUME_MUTE,
+            VK_VOLUME_UP,
+        },
+        WindowsAndMessaging::{PeekMessageW, MSG, PM_NOREMOVE, PM_REMOVE, WM_CHAR, WM_DEADCHAR},
+    },
+};
+
+/// The virtual keys of both sides are usually the same, the scancode tells them apart.
+pub fn key_location(physical_key: PhysicalKey) -> KeyLocation {
+    match physical_key {
+        PhysicalKey::KeyCode(
+            KeyCode::LShift | KeyCode::LCtrl | KeyCode::LAlt | KeyCode::LSuper,
+        ) => KeyLocation::Left,
+        PhysicalKey::KeyCode(
+            KeyCode::RShift | KeyCode::RCtrl | KeyCode::RAlt | KeyCode::RSuper,
+        ) => KeyLocation::Right,
+        PhysicalKey::KeyCode(
+            KeyCode::Numpad0
+            | KeyCode::Numpad1
+            | KeyCode::Numpad2
+            | KeyCode::Numpad3
+            | KeyCode::Numpad4
+            | KeyCode::Numpad5
+            | KeyCode::Numpad6
+            | KeyCode::Numpad7
+            | KeyCode::Numpad8
+            | KeyCode::Numpad9
+            | KeyCode::NumpadDecimal
+            | KeyCode::NumpadDivide
+            | KeyCode::NumpadMultiply
+            | KeyCode::NumpadSubtract
+            | KeyCode::NumpadAdd
+            | KeyCode::NumpadEnter,
+        ) => KeyLocation::Numpad,
+        _ => KeyLocation::Standard,
+    }
+}
+
+/// Converts a set 1 scancode, as found in the `lParam` of key messages,
+/// to the evdev numbering the wayland backend uses.
+pub fn scancode_to_physical(scancode: u32, extended: bool) -> PhysicalKey {
+    let code = match (extended, scancode) {
+        // windows has these two the other way around
+        (false, 0x45) => 119, // pause
+        (true, 0x45) => 69,   // num lock
+
+        (false, 0x64..=0x6e) => 183 + scancode - 0x64, // F13-F23
+        (false, 0x76) => 194,                          // F24
+        (false, 0x73) => 89,                           // ro
+        (false, 0x7d) => 124,                          // yen
+        // the lower half is numbered the same
+        (false, 0x01..=0x53 | 0x56..=0x58) => scancode,
+
+        (true, 0x1c) => 96,  // numpad enter
+        (true, 0x1d) => 97,  // right ctrl
+        (true, 0x35) => 98,  // numpad divide
+        (true, 0x37) => 99,  // print screen
+        (true, 0x38) => 100, // right alt
+        (true, 0x47) => 102, // home
+        (true, 0x48) => 103, // up
+        (true, 0x49) => 104, // page up
+        (true, 0x4b) => 105, // left
+        (true, 0x4d) => 106, // right
+        (true, 0x4f) => 107, // end
+        (true, 0x50) => 108, // down
+        (true, 0x51) => 109, // page down
+        (true, 0x52) => 110, // insert
+        (true, 0x53) => 111, // delete
+        (true, 0x5b) => 125, // left super
+        (true, 0x5c) => 126, // right super
+        (true, 0x5d) => 127, // menu
+
+        (true, 0x10) => 165, // previous track
+        (true, 0x19) => 163, // next track
+        (true, 0x20) => 113, // mute
+        (true, 0x22) => 164, // play/pause
+        (true, 0x24) => 166, // stop
+        (true, 0x2e) => 114, // volume down
+        (true, 0x30) => 115, // volume up
+
+        (true, 0x32) => 172, // browser home
+        (true, 0x65) => 217, // browser search
+        (true, 0x66) => 156, // browser favorites
+        (true, 0x67) => 173, // browser refresh
+        (true, 0x68) => 128, // browser stop
+        (true, 0x69) => 159, // browser forward
+        (true, 0x6a) => 158, // browser back
+
+        // keep codes without an evdev equivalent out of its range
+        (false, _) => 0x1_0000 | scancode,
+        (true, _) => 0x1_e000 | scancode,
+    };
+    PhysicalKey::from_evdev(code)
+}
+
+/// The key without any modifiers applied.
+pub fn vk_to_logical(vk: VIRTUAL_KEY, physical_key: PhysicalKey) -> LogicalKey {
+    if let Some(key) = named_key(vk, physical_key) {
+        return LogicalKey::Key(key);
+    }
+    // the high bit marks dead keys
+    let code = unsafe { MapVirtualKeyW(vk.0 as u32, MAPVK_VK_TO_CHAR) } & 0x7fff_ffff;
+    match char::from_u32(code).filter(|&c| c != '\0') {
+        Some(c) => char_to_logical(c.to_lowercase().collect()),
+        None => LogicalKey::Unknown(vk.0 as u32),
+    }
+}
+
+/// The key with the modifiers applied, `text` is what it typed.
+pub fn text_to_logical(vk: VIRTUAL_KEY, physical_key: PhysicalKey, text: &SmolStr) -> LogicalKey {
+    match named_key(vk, physical_key) {
+        Some(key) => LogicalKey::Key(key),
+        None if !text.is_empty() && !text.chars().any(char::is_control) => {
+            char_to_logical(text.clone())
+        }
+        // e.g. dead keys
+        None => vk_to_logical(vk, physical_key),
+    }
+}
+
+/// Keys that don't type a character. `physical_key` tells apart
+/// the sides of modifiers and the numpad from the other navigation keys.
+fn named_key(vk: VIRTUAL_KEY, physical_key: PhysicalKey) -> Option<Key> {
+    let numpad = matches!(
+        physical_key,
+        PhysicalKey::KeyCode(
+            KeyCode::Numpad0
+                | KeyCode::Numpad1
+                | KeyCode::Numpad2
+                | KeyCode::Numpad3
+                | KeyCode::Numpad4
+                | KeyCode::Numpad5
+                | KeyCode::Numpad6
+                | KeyCode::Numpad7
+                | KeyCode::Numpad8
+                | KeyCode::Numpad9
+                | KeyCode::NumpadDecimal
+                | KeyCode::NumpadEnter
+        )
+    );
+    let right = matches!(
+        physical_key,
+        PhysicalKey::KeyCode(KeyCode::RShift | KeyCode::RCtrl | KeyCode::RAlt)
+    );
+
+    Some(match vk {
+        VK_TAB => Key::Tab,
+        VK_RETURN if numpad => Key::NumpadEnter,
+        VK_RETURN => Key::Enter,
+        VK_SPACE => Key::Space,
+        VK_BACK => Key::Backspace,
+        VK_ESCAPE => Key::Escape,
+
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT if right => Key::RShift,
+        VK_SHIFT | VK_LSHIFT | VK_RSHIFT => Key::LShift,
+        VK_CONTROL | VK_LCONTROL | VK_RCONTROL if right => Key::RCtrl,
+        VK_CONTROL | VK_LCONTROL | VK_RCONTROL => Key::LCtrl,
+        VK_MENU | VK_LMENU | VK_RMENU if right => Key::RAlt,
+        VK_MENU | VK_LMENU | VK_RMENU => Key::LAlt,
+        VK_LWIN => Key::LSuper,
+        VK_RWIN => Key::RSuper,
+
+        // the numpad sends these while num lock is off
+        VK_LEFT if numpad => Key::NumpadLeftArrow,
+        VK_RIGHT if numpad => Key::NumpadRightArrow,
+        VK_UP if numpad => Key::NumpadUpArrow,
+        VK_DOWN if numpad => Key::NumpadDownArrow,
+        VK_PRIOR if numpad => Key::NumpadPageUp,
+        VK_NEXT if numpad => Key::NumpadPageDown,
+        VK_HOME if numpad => Key::NumpadHome,
+        VK_END if numpad => Key::NumpadEnd,
+        VK_INSERT if numpad => Key::NumpadInsert,
+        VK_DELETE if numpad => Key::NumpadDelete,
+        VK_CLEAR => Key::NumpadBegin,
+
+        VK_LEFT => Key::LeftArrow,
+        VK_RIGHT => Key::RightArrow,
+        VK_UP => Key::UpArrow,
+        VK_DOWN => Key::DownArrow,
+        VK_PRIOR => Key::PageUp,
+        VK_NEXT => Key::PageDown,
+        VK_HOME => Key::Home,
+        VK_END => Key::End,
+        VK_INSERT => Key::Insert,
+        VK_DELETE => Key::Delete,
+
+        VK_ADD => Key::NumpadAdd,
+        VK_SUBTRACT => Key::NumpadSubtract,
+        VK_MULTIPLY => Key::NumpadMultiply,
+        VK_DIVIDE => Key::NumpadDivide,
+        VK_DECIMAL => Key::NumpadDecimal,
+
+        VK_CAPITAL => Key::CapsLock,
+        VK_NUMLOCK => Key::NumLock,
+        VK_SCROLL => Key::ScrollLock,
+        VK_SNAPSHOT => Key::PrintScreen,
+        VK_PAUSE => Key::Pause,
+        VK_APPS => Key::Menu,
+
+        VK_MEDIA_PLAY_PAUSE => Key::MediaPlayPause,
+        VK_MEDIA_STOP => Key::MediaStop,
+        VK_MEDIA_NEXT_TRACK => Key::MediaNextTrack,
+        VK_MEDIA_PREV_TRACK => Key::MediaPrevTrack,
+        VK_VOLUME_UP => Key::VolumeUp,
+        VK_VOLUME_DOWN => Key::VolumeDown,
+        VK_VOLUME_MUTE => Key::VolumeMute,
+
+        VK_BROWSER_BACK => Key::BrowserBack,
+        VK_BROWSER_FORWARD => Key::BrowserForward,
+        VK_BROWSER_REFRESH => Key::BrowserRefresh,
+        VK_BROWSER_STOP => Key::BrowserStop,
+        VK_BROWSER_SEARCH => Key::BrowserSearch,
+        VK_BROWSER_FAVORITES => Key::BrowserFavorites,
+        VK_BROWSER_HOME => Key::BrowserHome,
+
+        vk if (VK_0.0..=VK_9.0).contains(&vk.0) => [
+            Key::Key0,
+            Key::Key1,
+            Key::Key2,
+            Key::Key3,
+            Key::Key4,
+            Key::Key5,
+            Key::Key6,
+            Key::Key7,
+            Key::Key8,
+            Key::Key9,
+        ][(vk.0 - VK_0.0) as usize],
+        vk if (VK_NUMPAD0.0..=VK_NUMPAD9.0).contains(&vk.0) => [
+            Key::Numpad0,
+            Key::Numpad1,
+            Key::Numpad2,
+            Key::Numpad3,
+            Key::Numpad4,
+            Key::Numpad5,
+            Key::Numpad6,
+            Key::Numpad7,
+            Key::Numpad8,
+            Key::Numpad9,
+        ][(vk.0 - VK_NUMPAD0.0) as usize],
+        vk if (VK_F1.0..=VK_F24.0).contains(&vk.0) => [
+            Key::F1,
+            Key::F2,
+            Key::F3,
+            Key::F4,
+            Key::F5,
+            Key::F6,
+            Key::F7,
+            Key::F8,
+            Key::F9,
+            Key::F10,
+            Key::F11,
+            Key::F12,
+            Key::F13,
+            Key::F14,
+            Key::F15,
+            Key::F16,
+            Key::F17,
+            Key::F18,
+            Key::F19,
+            Key::F20,
+            Key::F21,
+            Key::F22,
+            Key::F23,
+            Key::F24,
+        ][(vk.0 - VK_F1.0) as usize],
+
+        _ => return None,
+    })
+}
+
+/// Characters that also have a [`Key`] get that, like on wayland.
+fn char_to_logical(text: SmolStr) -> LogicalKey {
+    LogicalKey::Key(match text.as_str() {
+        "." => Key::Period,
+        "+" => Key::Plus,
+        "-" => Key::Minus,
+        "*" => Key::Asterisk,
+        "/" => Key::Slash,
+        _ => return LogicalKey::Character(text),
+    })
+}
+
+/// Takes the `WM_CHAR`s that `TranslateMessage` queued for the key down being handled,
+/// so the text ends up in its key event. Returns `None` for a dead key,
+/// its character only arrives combined with the next key.
+pub fn take_chars(hwnd: HWND) -> Option<SmolStr> {
+    let mut text = Vec::new();
+    let mut message = MSG::default();
+    loop {
+        let peeked = unsafe { PeekMessageW(&mut message, Some(hwnd), 0, 0, PM_NOREMOVE) };
+        if !peeked.as_bool() || !matches!(message.message, WM_CHAR | WM_DEADCHAR) {
+            break;
+        }
+        let kind = message.message;
+        let _ = unsafe { PeekMessageW(&mut message, Some(hwnd), kind, kind, PM_REMOVE) };
+        if kind == WM_DEADCHAR {
+            return None;
+        }
+        // surrogate pairs come as two messages
+        text.push(message.wParam.0 as u16);
+    }
+    Some(String::from_utf16_lossy(&text).into())
+}
+
+/// The text a key would type without ctrl, which turns letters into control characters.
+/// Used as the `text` for shortcuts, like wayland does.
+pub fn text_without_ctrl(vk: VIRTUAL_KEY, scancode: u32) -> SmolStr {
+    let mut state = [0; 256];
+    if let Err(err) = unsafe { GetKeyboardState(&mut state) } {
+        log::error!("failed to get keyboard state: {err}");
+        return SmolStr::default();
+    }
+    for key in [VK_CONTROL, VK_LCONTROL, VK_RCONTROL] {
+        state[key.0 as usize] = 0;
+    }
+    let mut text = [0; 8];
+    // don't touch the dead key state, that's still needed for the next key
+    const DONT_CHANGE_STATE: u32 = 1 << 2;
+    let len = unsafe {
+        ToUnicode(
+            vk.0 as u32,
+            scancode,
+            Some(&state),
+            &mut text,
+            DONT_CHANGE_STATE,
+        )
+    };
+    String::from_utf16_lossy(&text[..len.max(0) as usize]).into()
+}
@@ -0,0 +1,224 @@
+use super::{
+    class::WindowClass, utils::instance, EventHook, TimerCallback, TimerId, WAYWIN_USER_EVENT,
+};
+use crate::{event::WaywinEvent, Error};
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowLongPtrW, KillTimer,
+            PostMessageW, SetTimer, SetWindowLongPtrW, CREATESTRUCTW, GWLP_USERDATA, HWND_MESSAGE,
+            WINDOW_EX_STYLE, WINDOW_STYLE, WM_NCCREATE, WM_NCDESTROY, WM_TIMER,
+        },
+    },
+};
+
+/// Events sent by an `EventLoopProxy`, waiting for the loop window to deliver them.
+pub struct UserEvents {
+    events: VecDeque<Box<dyn Any + Send>>,
+    /// Cleared once the event loop stops, nothing would deliver the events anymore.
+    alive: bool,
+}
+impl Default for UserEvents {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            alive: true,
+        }
+    }
+}
+
+/// What the loop window procedure works with.
+struct LoopData {
+    event_hook: EventHook,
+    user_events: Arc<Mutex<UserEvents>>,
+    timers: RefCell<HashMap<TimerId, TimerCallback>>,
+    /// What the timers push to, kept around so firing them doesn't allocate.
+    timer_events: RefCell<Vec<WaywinEvent>>,
+    /// Timer ids are picked by us since the timers belong to a window.
+    next_timer: Cell<TimerId>,
+}
+impl LoopData {
+    fn deliver_user_events(&self) {
+        loop {
+            // don't hold the lock while the hook runs
+            let Some(event) = self.user_events.lock().unwrap().events.pop_front() else {
+                break;
+            };
+            self.event_hook.call(WaywinEvent::User(event));
+        }
+    }
+    fn fire_timer(&self, timer: TimerId) {
+        // taken out since a modal loop in the hook can fire timers again
+        let mut events = self.timer_events.take();
+        match self.timers.borrow_mut().get_mut(&timer) {
+            Some(callback) => callback(&mut events),
+            None => return,
+        }
+        for event in events.drain(..) {
+            self.event_hook.call(event);
+        }
+        *self.timer_events.borrow_mut() = events;
+    }
+}
+
+/// A hidden message-only window owned by the event loop.
+///
+/// Unlike thread messages, its messages are still dispatched
+/// while Windows runs its own loop, e.g. during a resize or a `MessageBox`.
+pub struct LoopWindow {
+    hwnd: HWND,
+    data: Rc<LoopData>,
+    // unregistered after the window is destroyed
+    _class: WindowClass,
+}
+impl LoopWindow {
+    pub fn new(class_name: &str, event_hook: EventHook) -> Result<Self, Error> {
+        let class = WindowClass::new(&format!("{class_name}-loop"), Some(wndproc))?;
+        let data = Rc::new(LoopData {
+            event_hook,
+            user_events: Arc::default(),
+            timers: RefCell::default(),
+            timer_events: RefCell::default(),
+            next_timer: Cell::new(1),
+        });
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class.name(),
+                PCWSTR::null(),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                Some(HWND_MESSAGE),
+                None,
+                Some(instance()),
+                Some(Rc::as_ptr(&data) as _),
+            )
+        }
+        .map_err(|err| Error::EventLoop(format!("failed to create loop window: {err}")))?;
+        Ok(Self {
+            hwnd,
+            data,
+            _class: class,
+        })
+    }
+    /// Unlike thread timers, these keep firing while Windows runs its own loop.
+    pub fn add_timer(&self, interval: Duration, callback: TimerCallback) -> Result<TimerId, Error> {
+        let id = self.data.next_timer.get();
+        let elapse = interval.as_millis().min(u32::MAX as u128) as u32;
+        if unsafe { SetTimer(Some(self.hwnd), id, elapse, None) } == 0 {
+            return Err(Error::EventLoop(
+                windows::core::Error::from_win32().to_string(),
+            ));
+        }
+        self.data.next_timer.set(id + 1);
+        self.data.timers.borrow_mut().insert(id, callback);
+        Ok(id)
+    }
+    pub fn remove_timer(&self, timer: TimerId) {
+        if self.data.timers.borrow_mut().remove(&timer).is_some() {
+            let _ = unsafe { KillTimer(Some(self.hwnd), timer) };
+        }
+    }
+    pub fn proxy(&self) -> LoopProxy {
+        LoopProxy {
+            hwnd: self.hwnd.0 as usize,
+            user_events: self.data.user_events.clone(),
+        }
+    }
+    /// Proxies fail from now on, events that weren't delivered yet are dropped.
+    pub fn stop(&self) {
+        let mut user_events = self.data.user_events.lock().unwrap();
+        user_events.alive = false;
+        user_events.events.clear();
+    }
+}
+impl Drop for LoopWindow {
+    fn drop(&mut self) {
+        self.stop();
+        if let Err(err) = unsafe { DestroyWindow(self.hwnd) } {
+            log::error!("failed to destroy loop window: {err}");
+        }
+    }
+}
+
+/// Sends events to a `LoopWindow` from any thread.
+#[derive(Clone)]
+pub struct LoopProxy {
+    hwnd: usize,
+    user_events: Arc<Mutex<UserEvents>>,
+}
+impl LoopProxy {
+    pub fn send_event(&self, event: Box<dyn Any + Send>) -> Result<(), Box<dyn Any + Send>> {
+        let mut user_events = self.user_events.lock().unwrap();
+        if !user_events.alive {
+            return Err(event);
+        }
+        // the loop window takes everything queued until then with a single message
+        if user_events.events.is_empty() {
+            if let Err(err) = unsafe {
+                PostMessageW(
+                    Some(HWND(self.hwnd as *mut _)),
+                    WAYWIN_USER_EVENT,
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+            } {
+                log::error!("failed to wake up event loop: {err}");
+                return Err(event);
+            }
+        }
+        user_events.events.push_back(event);
+        Ok(())
+    }
+}
+
+extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let data = unsafe { GetWindowLongPtrW(window, GWLP_USERDATA) } as *const LoopData;
+    let data = match (data.is_null(), message) {
+        // called during CreateWindowEx, the window holds on to its own reference
+        (true, WM_NCCREATE) => {
+            let create = lparam.0 as *const CREATESTRUCTW;
+            let data = unsafe { (*create).lpCreateParams as *const LoopData };
+            unsafe { Rc::increment_strong_count(data) };
+            unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, data as isize) };
+            return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+        }
+        (true, _) => return unsafe { DefWindowProcW(window, message, wparam, lparam) },
+        // kept alive until this call returns
+        (false, _) => {
+            unsafe { Rc::increment_strong_count(data) };
+            unsafe { Rc::from_raw(data) }
+        }
+    };
+
+    match message {
+        WAYWIN_USER_EVENT => {
+            data.deliver_user_events();
+            LRESULT(0)
+        }
+        WM_TIMER => {
+            data.fire_timer(wparam.0);
+            LRESULT(0)
+        }
+        WM_NCDESTROY => {
+            unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, 0) };
+            // the window's own reference
+            drop(unsafe { Rc::from_raw(Rc::as_ptr(&data)) });
+            unsafe { DefWindowProcW(window, message, wparam, lparam) }
+        }
+        _ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
+    }
+}
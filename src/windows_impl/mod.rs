@@ -1,68 +1,299 @@
-use crate::event::WindowEvent;
+use crate::{event::WaywinEvent, ControlFlow, Error, RepeatInfo};
 use class::WindowClass;
-use std::{cell::UnsafeCell, rc::Rc};
-pub use window::Window;
-use windows::Win32::UI::WindowsAndMessaging::{
-    DispatchMessageW, GetMessageW, PostQuitMessage, TranslateMessage, MSG,
+pub use clipboard::Clipboard;
+use loop_window::{LoopProxy, LoopWindow};
+pub use monitor::Monitor;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use window::CloakHook;
+pub use window::{Window, WindowParent};
+use windows::Win32::{
+    Foundation::HWND,
+    UI::Input::{RegisterRawInputDevices, RAWINPUTDEVICE, RAWINPUTDEVICE_FLAGS},
+    UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, MsgWaitForMultipleObjectsEx, PeekMessageW,
+        SystemParametersInfoW, TranslateMessage, MSG, MWMO_INPUTAVAILABLE, PM_REMOVE, QS_ALLINPUT,
+        SPI_GETWHEELSCROLLLINES, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WM_APP, WM_QUIT,
+    },
 };
 
 mod class;
+mod clipboard;
+mod cursor;
+mod drop_target;
+mod keyboard;
+mod loop_window;
+mod monitor;
+#[cfg(feature = "present")]
+mod present;
+mod taskbar;
 mod utils;
 mod window;
 
-type EventHook = Rc<UnsafeCell<Option<Box<dyn FnMut(WindowEvent)>>>>;
+/// Posted to the loop window when an `EventLoopProxy` queued new events.
+const WAYWIN_USER_EVENT: u32 = WM_APP + 1;
+
+/// `SPI_GETWHEELSCROLLLINES` gives this when a notch scrolls a whole page.
+const WHEEL_PAGESCROLL: u32 = u32::MAX;
+
+const HID_USAGE_PAGE_GENERIC: u16 = 0x01;
+const HID_USAGE_GENERIC_MOUSE: u16 = 0x02;
+
+type EventHook = Rc<HookCell<WaywinEvent>>;
+type RawHook = Rc<RefCell<Option<Box<dyn FnMut(RawMessage) -> bool>>>>;
+type TimerCallback = Box<dyn FnMut(&mut Vec<WaywinEvent>)>;
+type Hook<E> = Box<dyn FnMut(E)>;
+
+pub type TimerId = usize;
+
+/// An event hook, shared by the event loop and every window procedure,
+/// or a window's own event handler.
+///
+/// Messages keep getting dispatched while the hook runs a modal loop, like a `MessageBox`.
+/// Their events are queued and delivered after the hook returns
+/// rather than calling into the hook again while it's still running.
+struct HookCell<E> {
+    hook: RefCell<Option<Hook<E>>>,
+    pending: RefCell<VecDeque<E>>,
+}
+impl<E> Default for HookCell<E> {
+    fn default() -> Self {
+        Self {
+            hook: RefCell::default(),
+            pending: RefCell::default(),
+        }
+    }
+}
+impl<E> HookCell<E> {
+    fn new(hook: impl FnMut(E) + 'static) -> Self {
+        Self {
+            hook: RefCell::new(Some(Box::new(hook))),
+            pending: RefCell::default(),
+        }
+    }
+    fn call(&self, event: E) {
+        let Ok(mut hook) = self.hook.try_borrow_mut() else {
+            self.pending.borrow_mut().push_back(event);
+            return;
+        };
+        let Some(hook) = hook.as_mut() else {
+            return;
+        };
+        hook(event);
+        loop {
+            // the queue can't stay borrowed while the hook runs
+            let Some(event) = self.pending.borrow_mut().pop_front() else {
+                break;
+            };
+            hook(event);
+        }
+    }
+}
+
+/// A message as it was passed to the window procedure.
+#[derive(Debug, Clone, Copy)]
+pub struct RawMessage {
+    pub hwnd: isize,
+    pub message: u32,
+    pub wparam: usize,
+    pub lparam: isize,
+}
 
 pub struct Waywin {
-    /// All created windows keep a pointer to this so **do not move it**
+    /// Shared with every window procedure.
     event_hook: EventHook,
+    raw_hook: RawHook,
     window_class: Rc<WindowClass>,
+    /// Receives what `EventLoopProxy`s send, even during modal loops.
+    loop_window: LoopWindow,
+    _cloak_hook: Option<CloakHook>,
+    /// Shared with every window, `false` drops the system's repeated key presses.
+    key_repeat: Rc<Cell<bool>>,
+    /// How many windows inhibit idling, the execution state belongs to the whole thread.
+    idle_inhibitors: Rc<Cell<usize>>,
 }
 impl Waywin {
-    pub fn init(class_name: &str) -> std::result::Result<Self, String> {
-        let window_class = Rc::new(WindowClass::new(class_name)?);
+    pub fn init(class_name: &str) -> std::result::Result<Self, Error> {
+        let window_class = Rc::new(WindowClass::new(class_name, Some(window::wndproc))?);
+        let event_hook = EventHook::default();
+        let loop_window = LoopWindow::new(class_name, event_hook.clone())?;
 
-        let event_hook = Rc::new(UnsafeCell::new(None));
+        // without a target window raw input goes to whichever window has focus
+        let mouse = RAWINPUTDEVICE {
+            usUsagePage: HID_USAGE_PAGE_GENERIC,
+            usUsage: HID_USAGE_GENERIC_MOUSE,
+            dwFlags: RAWINPUTDEVICE_FLAGS(0),
+            hwndTarget: HWND::default(),
+        };
+        if let Err(err) = unsafe {
+            RegisterRawInputDevices(&[mouse], std::mem::size_of::<RAWINPUTDEVICE>() as u32)
+        } {
+            log::error!("failed to register for raw mouse input: {err}");
+        }
 
         Ok(Self {
             event_hook,
+            raw_hook: Rc::default(),
             window_class,
+            loop_window,
+            _cloak_hook: CloakHook::new(),
+            key_repeat: Rc::new(Cell::new(true)),
+            idle_inhibitors: Rc::default(),
         })
     }
-    pub fn exit(&self) {
-        unsafe { PostQuitMessage(0) }
+    pub fn clipboard(&self) -> Clipboard {
+        Clipboard
     }
-    pub fn run(&self, event_hook: impl FnMut(WindowEvent) + 'static) {
-        // TODO: this is still unsafe and a really bad way of doing things
-
-        unsafe { assert!((*self.event_hook.get()).is_none()) }
-
-        unsafe {
-            *self.event_hook.get() = Some(Box::new(event_hook));
+    pub fn monitors(&self) -> Vec<Monitor> {
+        Monitor::all()
+    }
+    pub fn wheel_scroll_lines(&self) -> Option<u32> {
+        let mut lines = 3u32;
+        if let Err(err) = unsafe {
+            SystemParametersInfoW(
+                SPI_GETWHEELSCROLLLINES,
+                0,
+                Some(&mut lines as *mut u32 as *mut _),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )
+        } {
+            log::error!("failed to get wheel scroll lines: {err}");
+        }
+        (lines != WHEEL_PAGESCROLL).then_some(lines)
+    }
+    pub fn window_creator(&self) -> WindowCreator {
+        WindowCreator {
+            event_hook: self.event_hook.clone(),
+            raw_hook: self.raw_hook.clone(),
+            key_repeat: self.key_repeat.clone(),
+            idle_inhibitors: self.idle_inhibitors.clone(),
+            window_class: self.window_class.clone(),
         }
+    }
+    pub fn create_proxy(&self) -> EventLoopProxy {
+        EventLoopProxy {
+            loop_window: self.loop_window.proxy(),
+        }
+    }
+    pub fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    pub fn roundtrip(&self) -> Result<(), Error> {
+        Ok(())
+    }
+    pub fn set_key_repeat(&mut self, repeat: Option<RepeatInfo>) {
+        // the system's timing can't be changed per application
+        self.key_repeat.set(repeat.is_some());
+    }
+    pub fn add_timer(
+        &self,
+        interval: Duration,
+        callback: impl FnMut(&mut Vec<WaywinEvent>) + 'static,
+    ) -> Result<TimerId, Error> {
+        self.loop_window.add_timer(interval, Box::new(callback))
+    }
+    pub fn remove_timer(&self, timer: TimerId) {
+        self.loop_window.remove_timer(timer);
+    }
+    fn hook(&self, event: WaywinEvent) {
+        self.event_hook.call(event);
+    }
+    pub fn set_raw_message_hook(&mut self, hook: impl FnMut(RawMessage) -> bool + 'static) {
+        *self.raw_hook.borrow_mut() = Some(Box::new(hook));
+    }
+    pub fn run(&self, mut event_hook: impl FnMut(WaywinEvent, &mut ControlFlow) + 'static) {
+        assert!(
+            self.event_hook.hook.borrow().is_none(),
+            "the event loop is already running"
+        );
+
+        // shared with the window procedure through the event hook
+        let control_flow = Rc::new(Cell::new(ControlFlow::default()));
 
-        // // erasing the the lifetime of the event hook.
-        // // Safety: i think its ok since the event hook gets unset
-        // // at the end of the function perserving liftimes...
-        // // and as long as waywin doesn't do anything else funny.
-        // // unsafe {
-        // //     *self.event_hook.get() = Some(Box::new(std::mem::transmute::<
-        // //         Box<dyn FnMut(WindowEvent)>,
-        // //         Box<dyn FnMut(WindowEvent)>,
-        // //     >(Box::new(event_hook))));
-        // // }
+        let hook_control_flow = control_flow.clone();
+        *self.event_hook.hook.borrow_mut() = Some(Box::new(move |event| {
+            let mut flow = hook_control_flow.get();
+            event_hook(event, &mut flow);
+            hook_control_flow.set(flow);
+        }));
 
         let mut message = MSG::default();
 
-        unsafe {
-            while GetMessageW(std::ptr::addr_of_mut!(message), None, 0, 0).as_bool() {
-                let _ = TranslateMessage(std::ptr::addr_of_mut!(message));
-                DispatchMessageW(std::ptr::addr_of!(message));
+        'event_loop: loop {
+            // dispatch everything that is queued
+            while unsafe { PeekMessageW(std::ptr::addr_of_mut!(message), None, 0, 0, PM_REMOVE) }
+                .as_bool()
+            {
+                if message.message == WM_QUIT {
+                    break 'event_loop;
+                }
+                unsafe {
+                    let _ = TranslateMessage(std::ptr::addr_of_mut!(message));
+                    DispatchMessageW(std::ptr::addr_of!(message));
+                }
+                if control_flow.get() == ControlFlow::Exit {
+                    break 'event_loop;
+                }
             }
-        }
 
-        // this is important to keep lifetimes
-        unsafe {
-            *self.event_hook.get() = None;
+            self.hook(WaywinEvent::AboutToWait);
+
+            match control_flow.get() {
+                ControlFlow::Poll => {}
+                ControlFlow::Wait => {
+                    if !unsafe { GetMessageW(std::ptr::addr_of_mut!(message), None, 0, 0) }
+                        .as_bool()
+                    {
+                        break 'event_loop;
+                    }
+                    unsafe {
+                        let _ = TranslateMessage(std::ptr::addr_of_mut!(message));
+                        DispatchMessageW(std::ptr::addr_of!(message));
+                    }
+                }
+                ControlFlow::WaitUntil(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    let _ = unsafe {
+                        MsgWaitForMultipleObjectsEx(
+                            None,
+                            timeout.as_millis().min(u32::MAX as u128) as u32,
+                            QS_ALLINPUT,
+                            MWMO_INPUTAVAILABLE,
+                        )
+                    };
+                }
+                ControlFlow::Exit => break 'event_loop,
+            }
         }
+
+        // windows outliving the loop don't call into it anymore
+        self.loop_window.stop();
+        *self.event_hook.hook.borrow_mut() = None;
+        self.event_hook.pending.borrow_mut().clear();
+    }
+}
+
+/// What windows share with `Waywin`, so they can be created while it's running.
+#[derive(Clone)]
+pub struct WindowCreator {
+    event_hook: EventHook,
+    raw_hook: RawHook,
+    key_repeat: Rc<Cell<bool>>,
+    idle_inhibitors: Rc<Cell<usize>>,
+    window_class: Rc<WindowClass>,
+}
+
+#[derive(Clone)]
+pub struct EventLoopProxy {
+    loop_window: LoopProxy,
+}
+impl EventLoopProxy {
+    pub fn send_event(&self, event: Box<dyn Any + Send>) -> Result<(), Box<dyn Any + Send>> {
+        self.loop_window.send_event(event)
     }
 }
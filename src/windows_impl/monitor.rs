@@ -0,0 +1,183 @@
+use super::utils::PWSTRING;
+use super::window::to_scale_factor;
+use crate::{MonitorId, VideoMode};
+use windows::{
+    core::BOOL,
+    Win32::{
+        Foundation::{LPARAM, RECT},
+        Graphics::Gdi::{
+            ChangeDisplaySettingsExW, EnumDisplayMonitors, EnumDisplaySettingsW, GetMonitorInfoW,
+            CDS_FULLSCREEN, CDS_TYPE, DEVMODEW, DISP_CHANGE_SUCCESSFUL, DM_BITSPERPEL,
+            DM_DISPLAYFREQUENCY, DM_PELSHEIGHT, DM_PELSWIDTH, ENUM_CURRENT_SETTINGS,
+            ENUM_DISPLAY_SETTINGS_MODE, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+        },
+        UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    handle: HMONITOR,
+    name: String,
+    rect: RECT,
+    scale: f64,
+}
+impl Monitor {
+    pub fn from_handle(handle: HMONITOR) -> Option<Self> {
+        let mut info = MONITORINFOEXW {
+            monitorInfo: MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFOEXW>() as u32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(handle, &mut info as *mut _ as *mut MONITORINFO) }.as_bool() {
+            log::error!("failed to get monitor info");
+            return None;
+        }
+        let len = info
+            .szDevice
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(info.szDevice.len());
+
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        let scale =
+            match unsafe { GetDpiForMonitor(handle, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+                Ok(()) => to_scale_factor(dpi_x),
+                Err(err) => {
+                    log::error!("failed to get monitor dpi: {err}");
+                    1.0
+                }
+            };
+
+        Some(Self {
+            handle,
+            name: String::from_utf16_lossy(&info.szDevice[..len]),
+            rect: info.monitorInfo.rcMonitor,
+            scale,
+        })
+    }
+    pub fn all() -> Vec<Self> {
+        unsafe extern "system" fn callback(
+            monitor: HMONITOR,
+            _hdc: HDC,
+            _rect: *mut RECT,
+            data: LPARAM,
+        ) -> BOOL {
+            let handles = unsafe { &mut *(data.0 as *mut Vec<HMONITOR>) };
+            handles.push(monitor);
+            true.into()
+        }
+
+        let mut handles: Vec<HMONITOR> = vec![];
+        if !unsafe {
+            EnumDisplayMonitors(
+                None,
+                None,
+                Some(callback),
+                LPARAM(&mut handles as *mut _ as isize),
+            )
+        }
+        .as_bool()
+        {
+            log::error!("failed to enumerate monitors");
+        }
+        handles.into_iter().filter_map(Self::from_handle).collect()
+    }
+    pub fn handle(&self) -> HMONITOR {
+        self.handle
+    }
+    pub fn rect(&self) -> RECT {
+        self.rect
+    }
+    /// Returns whether the mode was set.
+    pub fn set_video_mode(&self, mode: &VideoMode) -> bool {
+        let devmode = DEVMODEW {
+            dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+            dmFields: DM_PELSWIDTH | DM_PELSHEIGHT | DM_BITSPERPEL | DM_DISPLAYFREQUENCY,
+            dmPelsWidth: mode.size.0,
+            dmPelsHeight: mode.size.1,
+            dmBitsPerPel: mode.bit_depth.unwrap_or(32) as u32,
+            dmDisplayFrequency: mode.refresh_rate_millihertz / 1000,
+            ..Default::default()
+        };
+        let device = PWSTRING::from(self.name.as_str());
+        let result = unsafe {
+            ChangeDisplaySettingsExW(
+                device.as_pcwstr(),
+                Some(&devmode),
+                None,
+                CDS_FULLSCREEN,
+                None,
+            )
+        };
+        if result != DISP_CHANGE_SUCCESSFUL {
+            log::error!("failed to change display mode: {}", result.0);
+            return false;
+        }
+        true
+    }
+    /// Goes back to the mode from the registry.
+    pub fn reset_video_mode(device: &str) {
+        let device = PWSTRING::from(device);
+        let result =
+            unsafe { ChangeDisplaySettingsExW(device.as_pcwstr(), None, None, CDS_TYPE(0), None) };
+        if result != DISP_CHANGE_SUCCESSFUL {
+            log::error!("failed to reset display mode: {}", result.0);
+        }
+    }
+    pub fn id(&self) -> MonitorId {
+        MonitorId(self.handle.0 as usize)
+    }
+    pub fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+    pub fn position(&self) -> (i32, i32) {
+        (self.rect.left, self.rect.top)
+    }
+    pub fn physical_size(&self) -> (u32, u32) {
+        (
+            (self.rect.right - self.rect.left) as u32,
+            (self.rect.bottom - self.rect.top) as u32,
+        )
+    }
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        let device = PWSTRING::from(self.name.as_str());
+        let settings = |mode: ENUM_DISPLAY_SETTINGS_MODE| {
+            let mut devmode = DEVMODEW {
+                dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+                ..Default::default()
+            };
+            unsafe { EnumDisplaySettingsW(device.as_pcwstr(), mode, &mut devmode) }
+                .as_bool()
+                .then_some(devmode)
+        };
+        let to_mode = |devmode: DEVMODEW, current: bool| VideoMode {
+            size: (devmode.dmPelsWidth, devmode.dmPelsHeight),
+            bit_depth: Some(devmode.dmBitsPerPel as u16),
+            refresh_rate_millihertz: devmode.dmDisplayFrequency * 1000,
+            current,
+            monitor: self.id(),
+        };
+
+        let current = settings(ENUM_CURRENT_SETTINGS).map(|devmode| to_mode(devmode, true));
+        let mut modes: Vec<VideoMode> = current.into_iter().collect();
+        // the same mode is listed once per scaling and orientation setting
+        for mode in (0..).map_while(|i| settings(ENUM_DISPLAY_SETTINGS_MODE(i))) {
+            let mode = to_mode(mode, false);
+            if !modes.iter().any(|m| {
+                VideoMode {
+                    current: false,
+                    ..*m
+                } == mode
+            }) {
+                modes.push(mode);
+            }
+        }
+        modes
+    }
+}
@@ -0,0 +1,60 @@
+use crate::Error;
+use windows::Win32::{
+    Foundation::{HWND, RECT},
+    Graphics::Gdi::{
+        GetDC, ReleaseDC, StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        SRCCOPY,
+    },
+    UI::WindowsAndMessaging::GetClientRect,
+};
+
+/// Stretches the pixels over the whole client area.
+pub fn present_buffer(
+    hwnd: HWND,
+    (width, height): (u32, u32),
+    pixels: &[u32],
+) -> Result<(), Error> {
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // top-down
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut rect = RECT::default();
+    unsafe { GetClientRect(hwnd, &mut rect) }
+        .map_err(|err| Error::Present(format!("failed to get client rect: {err}")))?;
+
+    // the class has `CS_OWNDC` so this is cheap
+    let dc = unsafe { GetDC(Some(hwnd)) };
+    let lines = unsafe {
+        StretchDIBits(
+            dc,
+            0,
+            0,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            Some(pixels.as_ptr().cast()),
+            &info,
+            DIB_RGB_COLORS,
+            SRCCOPY,
+        )
+    };
+    unsafe { ReleaseDC(Some(hwnd), dc) };
+    if lines == 0 {
+        return Err(Error::Present(
+            windows::core::Error::from_win32().to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::{Progress, ProgressState};
+use std::sync::OnceLock;
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::HWND,
+        System::Com::{
+            CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+            COINIT_APARTMENTTHREADED,
+        },
+        UI::{
+            Shell::{
+                ITaskbarList3, TaskbarList, TBPFLAG, TBPF_ERROR, TBPF_INDETERMINATE,
+                TBPF_NOPROGRESS, TBPF_NORMAL, TBPF_PAUSED,
+            },
+            WindowsAndMessaging::RegisterWindowMessageW,
+        },
+    },
+};
+
+/// Steps the progress value is rounded to.
+const PROGRESS_TOTAL: u64 = 10_000;
+
+/// Sent to a window once its taskbar button exists, again if explorer restarts.
+/// Progress set before that is lost.
+pub fn button_created_message() -> u32 {
+    static MESSAGE: OnceLock<u32> = OnceLock::new();
+    *MESSAGE.get_or_init(|| unsafe { RegisterWindowMessageW(w!("TaskbarButtonCreated")) })
+}
+
+/// Created on first use by a window and dropped with it, on the window's thread.
+pub struct Taskbar {
+    list: ITaskbarList3,
+    // released after the list
+    _com: ComGuard,
+}
+impl Taskbar {
+    pub fn new() -> windows::core::Result<Self> {
+        let com = ComGuard::init();
+        let list: ITaskbarList3 =
+            unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER) }?;
+        unsafe { list.HrInit() }?;
+        Ok(Self { list, _com: com })
+    }
+    pub fn set_progress(
+        &self,
+        hwnd: HWND,
+        progress: Option<Progress>,
+    ) -> windows::core::Result<()> {
+        let Some(progress) = progress else {
+            return unsafe { self.list.SetProgressState(hwnd, TBPF_NOPROGRESS) };
+        };
+        // setting the value switches to normal, so it goes first
+        if progress.state != ProgressState::Indeterminate {
+            let completed = (progress.value.clamp(0.0, 1.0) * PROGRESS_TOTAL as f64).round();
+            unsafe {
+                self.list
+                    .SetProgressValue(hwnd, completed as u64, PROGRESS_TOTAL)
+            }?;
+        }
+        unsafe { self.list.SetProgressState(hwnd, to_flag(progress.state)) }
+    }
+}
+
+fn to_flag(state: ProgressState) -> TBPFLAG {
+    match state {
+        ProgressState::Normal => TBPF_NORMAL,
+        ProgressState::Paused => TBPF_PAUSED,
+        ProgressState::Error => TBPF_ERROR,
+        ProgressState::Indeterminate => TBPF_INDETERMINATE,
+    }
+}
+
+/// Every successful `CoInitializeEx` needs its own `CoUninitialize`,
+/// including the ones that find com already initialized.
+struct ComGuard {
+    initialized: bool,
+}
+impl ComGuard {
+    fn init() -> Self {
+        // fails if the thread already uses another apartment, which still works for this
+        let result = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+        Self {
+            initialized: result.is_ok(),
+        }
+    }
+}
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
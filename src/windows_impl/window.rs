@@ -1,54 +1,423 @@
 use super::{
     class::WindowClass,
+    cursor::create_cursor,
+    drop_target::{self, DropRegistration},
+    keyboard::{
+        key_location, scancode_to_physical, take_chars, text_to_logical, text_without_ctrl,
+        vk_to_logical,
+    },
+    taskbar::{self, Taskbar},
     utils::{hiword, instance, loword, PWSTRING},
-    EventHook, Waywin,
+    EventHook, HookCell, Monitor, RawHook, RawMessage, Waywin, WindowCreator,
 };
 use crate::{
     event::*,
     windows_impl::utils::{get_x, get_y},
+    Backdrop, CornerPreference, CursorGrabMode, CursorImage, Error, Fullscreen, HitTestResult,
+    MonitorId, Progress, Rect, SeatId, Theme, WindowBuilder, WindowId, WmCapabilities,
 };
 use raw_window_handle as rwh;
-use std::rc::Rc;
+use smol_str::SmolStr;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use windows::core::{w, BOOL, PCWSTR};
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM},
-    Graphics::Gdi::{RedrawWindow, ValidateRect, RDW_INTERNALPAINT},
+    Foundation::{ERROR_SUCCESS, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
+    Graphics::{
+        Dwm::{
+            DwmEnableBlurBehindWindow, DwmGetWindowAttribute, DwmSetWindowAttribute,
+            DWMSBT_MAINWINDOW, DWMSBT_NONE, DWMSBT_TABBEDWINDOW, DWMSBT_TRANSIENTWINDOW,
+            DWMWA_CLOAKED, DWMWA_SYSTEMBACKDROP_TYPE, DWMWA_USE_IMMERSIVE_DARK_MODE,
+            DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_DEFAULT, DWMWCP_DONOTROUND, DWMWCP_ROUND,
+            DWMWCP_ROUNDSMALL, DWMWINDOWATTRIBUTE, DWM_BB_BLURREGION, DWM_BB_ENABLE,
+            DWM_BLURBEHIND,
+        },
+        Gdi::{
+            ClientToScreen, CreateRectRgn, DeleteObject, GetMonitorInfoW, GetUpdateRect,
+            MapWindowPoints, MonitorFromWindow, RedrawWindow, ScreenToClient, ValidateRect,
+            HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST, RDW_INTERNALPAINT, RDW_INVALIDATE,
+        },
+    },
+    System::{
+        Power::{SetThreadExecutionState, ES_CONTINUOUS, ES_DISPLAY_REQUIRED},
+        Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+        SystemServices::{MK_LBUTTON, MK_MBUTTON, MK_RBUTTON, MK_SHIFT, MK_XBUTTON1, MK_XBUTTON2},
+        Threading::{GetCurrentProcessId, GetCurrentThreadId},
+    },
     UI::{
-        HiDpi::GetDpiForWindow,
+        Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
+        Controls::WM_MOUSELEAVE,
+        HiDpi::{AdjustWindowRectExForDpi, GetDpiForWindow},
+        Input::Ime::{
+            ImmAssociateContextEx, ImmGetCompositionStringW, ImmGetContext, ImmReleaseContext,
+            ImmSetCandidateWindow, ImmSetCompositionWindow, CANDIDATEFORM, CFS_EXCLUDE, CFS_POINT,
+            COMPOSITIONFORM, GCS_COMPSTR, GCS_CURSORPOS, GCS_RESULTSTR, HIMC, IACE_DEFAULT,
+            IME_COMPOSITION_STRING,
+        },
+        Input::KeyboardAndMouse::{
+            EnableWindow, GetDoubleClickTime, GetKeyState, GetKeyboardLayoutNameW, ReleaseCapture,
+            SetCapture, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT, VIRTUAL_KEY, VK_CAPITAL,
+            VK_CONTROL, VK_LWIN, VK_MENU, VK_NUMLOCK, VK_RWIN, VK_SCROLL, VK_SHIFT,
+        },
+        Input::Touch::{
+            CloseGestureInfoHandle, CloseTouchInputHandle, GetGestureInfo, GetTouchInputInfo,
+            RegisterTouchWindow, GESTURECONFIG_ID, GESTUREINFO, GID_PAN, GID_ZOOM, HGESTUREINFO,
+            HTOUCHINPUT, REGISTER_TOUCH_WINDOW_FLAGS, TOUCHEVENTF_DOWN, TOUCHEVENTF_MOVE,
+            TOUCHEVENTF_UP, TOUCHINPUT,
+        },
+        Input::{
+            GetRawInputData, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, RAWINPUT, RAWINPUTHEADER, RID_INPUT,
+            RIM_TYPEMOUSE,
+        },
+        Shell::{DragAcceptFiles, DragFinish, HDROP},
         WindowsAndMessaging::{
-            CreateWindowExW, DefWindowProcW, DestroyWindow, GetClientRect, GetWindowLongPtrW,
-            PostMessageW, SetWindowLongPtrW, SetWindowPos, CREATESTRUCTW, CW_USEDEFAULT,
-            GWLP_HINSTANCE, GWLP_USERDATA, SWP_NOACTIVATE, SWP_NOZORDER, USER_DEFAULT_SCREEN_DPI,
-            WINDOW_EX_STYLE, WM_CLOSE, WM_CREATE, WM_DPICHANGED, WM_ERASEBKGND, WM_MOUSEMOVE,
-            WM_NCCREATE, WM_PAINT, WM_SIZE, WM_USER, WS_CLIPCHILDREN, WS_CLIPSIBLINGS,
-            WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+            ClipCursor, CreateWindowExW, DefWindowProcW, DestroyCursor, DestroyWindow,
+            GetClientRect, GetMessageTime, GetSystemMetrics, GetWindowLongPtrW, GetWindowRect,
+            GetWindowThreadProcessId, IsIconic, IsWindowVisible, KillTimer, PostMessageW,
+            SetCursor, SetCursorPos, SetForegroundWindow, SetTimer, SetWindowDisplayAffinity,
+            SetWindowLongPtrW, SetWindowPos, SetWindowTextW, ShowCursor, ShowWindow, CHILDID_SELF,
+            CREATESTRUCTW, CW_USEDEFAULT, EVENT_OBJECT_CLOAKED, EVENT_OBJECT_UNCLOAKED, GF_BEGIN,
+            GF_END, GF_INERTIA, GWLP_HINSTANCE, GWLP_USERDATA, GWLP_WNDPROC, GWL_EXSTYLE,
+            GWL_STYLE, HCURSOR, HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTCLIENT, HTLEFT,
+            HTRIGHT, HTTOP, HTTOPLEFT, HTTOPRIGHT, HWND_BOTTOM, HWND_TOP, KF_EXTENDED, KF_REPEAT,
+            KL_NAMELENGTH, MINMAXINFO, OBJID_WINDOW, SM_CXDOUBLECLK, SM_CYDOUBLECLK,
+            SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, SW_HIDE,
+            SW_RESTORE, SW_SHOW, USER_DEFAULT_SCREEN_DPI, WDA_EXCLUDEFROMCAPTURE, WDA_NONE,
+            WHEEL_DELTA, WINDOW_EX_STYLE, WINDOW_STYLE, WINEVENT_OUTOFCONTEXT, WMSZ_BOTTOM,
+            WMSZ_BOTTOMLEFT, WMSZ_LEFT, WMSZ_RIGHT, WMSZ_TOP, WMSZ_TOPLEFT, WMSZ_TOPRIGHT,
+            WM_CAPTURECHANGED, WM_CLOSE, WM_CREATE, WM_DPICHANGED, WM_DROPFILES, WM_ERASEBKGND,
+            WM_GESTURE, WM_GETMINMAXINFO, WM_IME_COMPOSITION, WM_IME_ENDCOMPOSITION,
+            WM_IME_STARTCOMPOSITION, WM_INPUT, WM_INPUTLANGCHANGE, WM_KEYDOWN, WM_KEYUP,
+            WM_KILLFOCUS, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP,
+            WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_MOVE, WM_NCCREATE, WM_NCHITTEST,
+            WM_PAINT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SETCURSOR, WM_SETFOCUS, WM_SETTINGCHANGE,
+            WM_SIZE, WM_SIZING, WM_SYSKEYDOWN, WM_SYSKEYUP, WM_TIMER, WM_TOUCH, WM_USER,
+            WM_XBUTTONDOWN, WM_XBUTTONUP, WS_CLIPCHILDREN, WS_CLIPSIBLINGS, WS_EX_TOOLWINDOW,
+            WS_OVERLAPPEDWINDOW, WS_POPUP, WS_VISIBLE,
         },
     },
 };
 
 const WAYWIN_DESTROY: u32 = WM_USER + 1;
 
+/// Windows has a single seat for all input devices.
+const SEAT: SeatId = SeatId(0);
+
+/// Touchpad scrolling comes in fractions of a wheel notch,
+/// this is about what wayland compositors send for a notch.
+const PIXELS_PER_NOTCH: f64 = 15.0;
+
+/// Timer id used by `request_redraw_at`.
+const REDRAW_TIMER: usize = 1;
+/// Timer id of the stop sent once touchpad scrolling pauses,
+/// windows doesn't say when the fingers are lifted.
+const SCROLL_STOP_TIMER: usize = 2;
+const SCROLL_STOP_DELAY: Duration = Duration::from_millis(100);
+
+type HitTestHandler = Rc<dyn Fn(f64, f64) -> HitTestResult>;
+
+/// A `WM_GESTURE` zoom or pan in progress.
+#[derive(Clone, Copy)]
+struct Gesture {
+    zoom: bool,
+    /// Between the fingers at the start, the scale of a zoom is relative to it.
+    distance: f64,
+    /// Center of the fingers at the last update, in logical pixels.
+    position: (f64, f64),
+    scale: f64,
+}
+
 pub struct CreateInfo {
     event_hook: EventHook,
+    raw_hook: RawHook,
+    key_repeat: Rc<Cell<bool>>,
+    idle_inhibitors: Rc<Cell<usize>>,
+    title: String,
     class: Rc<WindowClass>,
+    popup: bool,
+    modal_parent: Option<HWND>,
 }
+/// Only ever borrowed shared: handlers called from the window procedure can call
+/// `Window` methods, and the messages those send reenter the window procedure.
+/// The cells are never borrowed across a call that can do either.
 pub struct WindowData {
     event_hook: EventHook,
-    window_id: usize,
+    raw_hook: RawHook,
+    key_repeat: Rc<Cell<bool>>,
+    idle_inhibitors: Rc<Cell<usize>>,
+    idle_inhibited: Cell<bool>,
+    window_id: WindowId,
+    /// Cached so reading it back doesn't need `GetWindowTextW`.
+    title: RefCell<String>,
+    /// In logical pixels, converted on every `WM_GETMINMAXINFO` so they follow dpi changes.
+    min_size: Cell<Option<(f64, f64)>>,
+    max_size: Cell<Option<(f64, f64)>>,
+    /// In logical pixels, the client area snaps to these while resizing.
+    resize_increments: Cell<Option<(u32, u32)>>,
+    /// Width to height, kept by the client area while resizing.
+    aspect_ratio: Cell<Option<(u32, u32)>>,
+    redraw_deadline: Cell<Option<Instant>>,
+    /// Logical size captured when the last `Resized` was emitted.
+    last_event_size: Cell<(f64, f64)>,
+    /// In logical pixels. `None` while the pointer is outside of the window.
+    pointer_position: Cell<Option<(f64, f64)>>,
+    grab_escape_key: Cell<Option<PhysicalKey>>,
+    /// Last sent with `ModifiersChanged`.
+    modifiers: Cell<KeyModifiers>,
+    /// Last sent with `LockStateChanged`.
+    lock_state: Cell<Option<LockState>>,
+    /// Last sent with `KeyboardLayoutChanged`.
+    keyboard_layout: Cell<Option<String>>,
+    /// Has keyboard focus, so `Focus(false)` can be sent when destroyed while focused.
+    focused: Cell<bool>,
+    /// Set with `Window::set_ime_allowed`.
+    ime_allowed: Cell<bool>,
+    /// Whether `Ime::Enabled` was the last sent of `Enabled`/`Disabled`.
+    ime_enabled: Cell<bool>,
+    grab: Cell<CursorGrabMode>,
+    /// `ShowCursor` is a counter so only undo what was done.
+    cursor_hidden: Cell<bool>,
+    /// Style and window rect to go back to when leaving fullscreen.
+    /// `Some` while fullscreen.
+    fullscreen_restore: Cell<Option<(isize, RECT)>>,
+    /// Device name of the monitor whose mode was changed for exclusive fullscreen.
+    changed_display_mode: Cell<Option<String>>,
+    /// Last sent with `MonitorChanged`.
+    monitor: Cell<HMONITOR>,
+    /// Set on `WM_SETCURSOR`, the class cursor is used otherwise.
+    cursor: Cell<Option<HCURSOR>>,
+    /// Set with `Window::set_event_handler`, gets the events instead of the event hook.
+    /// Replaced without waiting for it to return, the old one finishes what it queued.
+    event_handler: RefCell<Option<Rc<HookCell<WindowEvent>>>>,
+    /// Set with `Window::set_theme`, the system's is used otherwise.
+    theme_override: Cell<Option<Theme>>,
+    /// Last applied to the title bar.
+    theme: Cell<Theme>,
+    /// Set with `Window::set_taskbar_progress`, applied again whenever the taskbar button is created.
+    taskbar_progress: Cell<Option<Progress>>,
+    /// Created on first use, the com object stays alive as long as the window.
+    taskbar: RefCell<Option<Rc<Taskbar>>>,
+    clicks: RefCell<ClickCounter>,
+    /// Of the touchpad scrolling `SCROLL_STOP_TIMER` is running for, the stop has the same.
+    scroll_burst: Cell<Option<ScrollSource>>,
+    gesture: Cell<Option<Gesture>>,
+    /// Keeps system keys like alt+f4 and alt+space from the default window procedure.
+    keyboard_grab: Cell<bool>,
+    /// Set with `Window::set_hittest_handler`, asked about points of the client area.
+    hittest: RefCell<Option<HitTestHandler>>,
+    /// Last sent with `Occluded`, minimizing and cloaking are known to hide the window.
+    occluded: Cell<bool>,
+    /// Revoked when the window is destroyed, `None` if files only arrive with `WM_DROPFILES`.
+    drop_target: RefCell<Option<DropRegistration>>,
+    /// Dismissed by clicks outside of it or by losing the mouse capture.
+    popup: bool,
+    /// Disabled while this window is open, enabled again when it's destroyed.
+    modal_parent: Option<HWND>,
     // make sure that the window class doesn't get
     // unregistered before this window is destroyed
-    _class: Rc<WindowClass>,
+    class: Rc<WindowClass>,
 }
 impl WindowData {
-    fn hook(&mut self, event: Event) {
-        if let Some(hook) = unsafe { &mut *self.event_hook.get() } {
-            hook(WindowEvent {
-                kind: event,
+    /// Returns `true` if the message was consumed.
+    fn raw_hook(&self, message: RawMessage) -> bool {
+        // a message caused by the hook itself, like one sent by a dialog it opened,
+        // can't be asked about so waywin handles it
+        match self.raw_hook.try_borrow_mut() {
+            Ok(mut hook) => hook.as_mut().is_some_and(|hook| hook(message)),
+            Err(_) => false,
+        }
+    }
+    /// (Re)applies the pointer grab. Clipping affects the whole desktop
+    /// so it's only done while focused.
+    fn apply_grab(&self, hwnd: HWND) {
+        self.release_grab();
+        if !self.focused.get() || self.grab.get() == CursorGrabMode::None {
+            return;
+        }
+        let rect = client_screen_rect(hwnd);
+        if let Err(err) = unsafe { ClipCursor(Some(&rect as *const _)) } {
+            log::error!("failed to clip cursor: {err}");
+        }
+        if self.grab.get() == CursorGrabMode::Locked {
+            unsafe { ShowCursor(false) };
+            self.cursor_hidden.set(true);
+            center_cursor(hwnd);
+        }
+    }
+    fn release_grab(&self) {
+        if let Err(err) = unsafe { ClipCursor(None) } {
+            log::error!("failed to unclip cursor: {err}");
+        }
+        if self.cursor_hidden.replace(false) {
+            unsafe { ShowCursor(true) };
+        }
+    }
+    /// Sends `KeyboardLayoutChanged` if the thread's layout isn't the one last sent.
+    fn check_keyboard_layout(&self) {
+        let mut name = [0; KL_NAMELENGTH as usize];
+        if let Err(err) = unsafe { GetKeyboardLayoutNameW(&mut name) } {
+            log::error!("failed to get keyboard layout name: {err}");
+            return;
+        }
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        let name = String::from_utf16_lossy(&name[..len]);
+        if self.keyboard_layout.replace(Some(name.clone())).as_ref() != Some(&name) {
+            self.hook(WindowEvent::KeyboardLayoutChanged { name });
+        }
+    }
+    /// The display stays on as long as any window of the thread wants it to.
+    fn set_idle_inhibited(&self, inhibited: bool) {
+        if self.idle_inhibited.replace(inhibited) == inhibited {
+            return;
+        }
+        let count = if inhibited {
+            self.idle_inhibitors.get() + 1
+        } else {
+            self.idle_inhibitors.get() - 1
+        };
+        self.idle_inhibitors.set(count);
+        let state = if count > 0 {
+            ES_CONTINUOUS | ES_DISPLAY_REQUIRED
+        } else {
+            ES_CONTINUOUS
+        };
+        if unsafe { SetThreadExecutionState(state) }.0 == 0 {
+            log::error!("failed to set thread execution state");
+        }
+    }
+    /// Sends `ModifiersChanged` if they differ from the ones last sent.
+    fn check_modifiers(&self) {
+        let modifiers = key_modifiers();
+        if self.modifiers.replace(modifiers) != modifiers {
+            self.hook(WindowEvent::ModifiersChanged(modifiers));
+        }
+    }
+    /// Sends `LockStateChanged` if it differs from the one last sent.
+    fn check_lock_state(&self) {
+        let toggled = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } & 1 != 0;
+        let lock_state = LockState {
+            caps: toggled(VK_CAPITAL),
+            num: toggled(VK_NUMLOCK),
+            scroll: toggled(VK_SCROLL),
+        };
+        if self.lock_state.replace(Some(lock_state)) != Some(lock_state) {
+            self.hook(WindowEvent::LockStateChanged(lock_state));
+        }
+    }
+    /// Sends `Ime::Enabled` or `Ime::Disabled` if input methods became usable or not,
+    /// they are while allowed and focused.
+    fn check_ime_enabled(&self) {
+        let enabled = self.ime_allowed.get() && self.focused.get();
+        if self.ime_enabled.replace(enabled) != enabled {
+            self.hook(WindowEvent::Ime(if enabled {
+                Ime::Enabled
+            } else {
+                Ime::Disabled
+            }));
+        }
+    }
+    fn device_hook(&self, event: DeviceEvent) {
+        self.event_hook.call(WaywinEvent::DeviceEvent(event));
+    }
+    /// Sends `Occluded` if the window was minimized or cloaked, or is neither anymore.
+    fn check_occluded(&self, hwnd: HWND) {
+        let mut cloaked = 0u32;
+        let _ = unsafe {
+            DwmGetWindowAttribute(
+                hwnd,
+                DWMWA_CLOAKED,
+                std::ptr::addr_of_mut!(cloaked) as _,
+                std::mem::size_of::<u32>() as u32,
+            )
+        };
+        let occluded = unsafe { IsIconic(hwnd) }.as_bool() || cloaked != 0;
+        if self.occluded.replace(occluded) != occluded {
+            self.hook(WindowEvent::Occluded(occluded));
+        }
+    }
+    /// Sends `MonitorChanged` if the window is now mostly on another monitor.
+    fn check_monitor(&self, hwnd: HWND) {
+        let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        if self.monitor.replace(monitor) != monitor {
+            self.hook(WindowEvent::MonitorChanged(MonitorId(monitor.0 as usize)));
+        }
+    }
+    /// Applies the overridden or the system's theme, sending `ThemeChanged` if it's a different one.
+    fn update_theme(&self, hwnd: HWND) {
+        let theme = self.theme_override.get().unwrap_or_else(system_theme);
+        if self.theme.replace(theme) != theme {
+            apply_theme(hwnd, theme);
+            self.hook(WindowEvent::ThemeChanged(theme));
+        }
+    }
+    fn apply_taskbar_progress(&self, hwnd: HWND) {
+        let progress = self.taskbar_progress.get();
+        let taskbar = self.taskbar.borrow().clone();
+        let taskbar = match taskbar {
+            Some(taskbar) => taskbar,
+            // nothing to clear without ever having shown progress
+            None if progress.is_none() => return,
+            None => match Taskbar::new() {
+                Ok(taskbar) => {
+                    let taskbar = Rc::new(taskbar);
+                    self.taskbar.replace(Some(taskbar.clone()));
+                    taskbar
+                }
+                Err(err) => {
+                    log::error!("failed to create taskbar list: {err}");
+                    return;
+                }
+            },
+        };
+        // fails until the taskbar button is created
+        if let Err(err) = taskbar.set_progress(hwnd, progress) {
+            log::debug!("failed to set taskbar progress: {err}");
+        }
+    }
+    /// Hides the popup and tells it that it was dismissed.
+    fn dismiss_popup(&self, hwnd: HWND) {
+        // releasing the capture comes back here through `WM_CAPTURECHANGED`
+        if !unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            return;
+        }
+        let _ = unsafe { ShowWindow(hwnd, SW_HIDE) };
+        let _ = unsafe { ReleaseCapture() };
+        self.hook(WindowEvent::CloseRequested);
+    }
+    fn hook(&self, event: WindowEvent) {
+        // not borrowed while it runs, it can be replaced from within
+        let handler = self.event_handler.borrow().clone();
+        match handler {
+            Some(handler) => handler.call(event),
+            None => self.event_hook.call(WaywinEvent::WindowEvent {
+                event,
                 window_id: self.window_id,
-            })
+            }),
         }
     }
 }
 
+/// The data of a window, kept alive while in use even if the window is destroyed meanwhile,
+/// e.g. by a dialog's message loop running from within an event handler.
+fn window_data(hwnd: HWND) -> Option<Rc<WindowData>> {
+    let data = unsafe { GetWindowLongPtrW(hwnd, GWLP_USERDATA) } as *const WindowData;
+    if data.is_null() {
+        return None;
+    }
+    // the window holds on to its own reference
+    unsafe { Rc::increment_strong_count(data) };
+    Some(unsafe { Rc::from_raw(data) })
+}
+
+/// The handle of a window, for others to be dialogs of.
+#[derive(Debug, Clone, Copy)]
+pub struct WindowParent(usize);
+
+/// Calls that only send messages to the window are fine from any thread,
+/// `WindowData` is only touched on the thread that created the window.
 struct SyncHWND(HWND);
 unsafe impl Send for SyncHWND {}
 unsafe impl Sync for SyncHWND {}
@@ -57,29 +426,137 @@ pub struct Window {
     hwnd: SyncHWND,
 }
 impl Window {
-    pub fn new(waywin: &Waywin, title: &str) -> Result<Self, String> {
+    pub fn new(waywin: &mut Waywin, builder: &WindowBuilder) -> Result<Self, Error> {
+        Self::from_context(&waywin.window_creator(), builder)
+    }
+    pub fn from_context(creator: &WindowCreator, builder: &WindowBuilder) -> Result<Self, Error> {
+        let parent = builder
+            .parent
+            .map(|WindowParent(parent)| HWND(parent as *mut _));
         let info = CreateInfo {
-            event_hook: waywin.event_hook.clone(),
-            class: waywin.window_class.clone(),
+            event_hook: creator.event_hook.clone(),
+            raw_hook: creator.raw_hook.clone(),
+            key_repeat: creator.key_repeat.clone(),
+            idle_inhibitors: creator.idle_inhibitors.clone(),
+            title: builder.title.clone(),
+            class: creator.window_class.clone(),
+            popup: false,
+            modal_parent: parent.filter(|_| builder.modal),
         };
 
+        let mut style = WS_CLIPCHILDREN | WS_CLIPSIBLINGS | WS_OVERLAPPEDWINDOW;
+        if builder.visible {
+            style |= WS_VISIBLE;
+        }
+
         let hwnd = unsafe {
             CreateWindowExW(
                 WINDOW_EX_STYLE::default(),
-                waywin.window_class.name(),
-                PWSTRING::from(title).as_pcwstr(),
-                WS_CLIPCHILDREN | WS_CLIPSIBLINGS | WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+                creator.window_class.name(),
+                PWSTRING::from(builder.title.as_str()).as_pcwstr(),
+                style,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
+                // an owner rather than a parent, this isn't a child window
+                parent,
                 None,
+                Some(instance()),
+                Some(std::ptr::addr_of!(info) as _),
+            )
+        }
+        .map_err(|err| Error::WindowCreation(err.to_string()))?;
+
+        if builder.transparent {
+            enable_transparency(hwnd);
+        }
+        // input methods are opt-in
+        let _ = unsafe { ImmAssociateContextEx(hwnd, HIMC::default(), 0) };
+        let drop_target = DropRegistration::new(hwnd, move |event| {
+            if let Some(data) = window_data(hwnd) {
+                data.hook(event);
+            }
+        });
+        match drop_target {
+            Ok(drop_target) => {
+                if let Some(data) = window_data(hwnd) {
+                    data.drop_target.replace(Some(drop_target));
+                }
+            }
+            Err(err) => {
+                log::error!("failed to register drop target, files won't be hovered: {err}");
+                unsafe { DragAcceptFiles(hwnd, true) };
+            }
+        }
+        if let Some(parent) = info.modal_parent {
+            // returns the previous state, not an error
+            let _ = unsafe { EnableWindow(parent, false) };
+        }
+        if let Err(err) = unsafe { RegisterTouchWindow(hwnd, REGISTER_TOUCH_WINDOW_FLAGS(0)) } {
+            log::error!("failed to register window for touch input: {err}");
+        }
+
+        Ok(Self {
+            hwnd: SyncHWND(hwnd),
+        })
+    }
+    pub fn create_popup(
+        &self,
+        anchor: Rect,
+        offset: (i32, i32),
+        size: (f64, f64),
+    ) -> Result<Self, Error> {
+        let parent = self.hwnd();
+        let info = self
+            .with_data(|data| CreateInfo {
+                event_hook: data.event_hook.clone(),
+                raw_hook: data.raw_hook.clone(),
+                key_repeat: data.key_repeat.clone(),
+                idle_inhibitors: data.idle_inhibitors.clone(),
+                title: String::new(),
+                class: data.class.clone(),
+                popup: true,
+                modal_parent: None,
+            })
+            .ok_or_else(|| {
+                Error::WindowCreation(
+                    "a popup has to be created on the thread of its parent".to_owned(),
+                )
+            })?;
+
+        let scale = to_scale_factor(unsafe { GetDpiForWindow(parent) });
+        let mut position = POINT {
+            x: ((anchor.x + offset.0) as f64 * scale).round() as i32,
+            y: ((anchor.y + anchor.height + offset.1) as f64 * scale).round() as i32,
+        };
+        let _ = unsafe { ClientToScreen(parent, &mut position) };
+
+        // owned by the parent so it stays above it, no taskbar button
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOOLWINDOW,
+                info.class.name(),
+                PWSTRING::from("").as_pcwstr(),
+                WS_CLIPCHILDREN | WS_CLIPSIBLINGS | WS_POPUP | WS_VISIBLE,
+                position.x,
+                position.y,
+                (size.0 * scale).round() as i32,
+                (size.1 * scale).round() as i32,
+                Some(parent),
                 None,
                 Some(instance()),
                 Some(std::ptr::addr_of!(info) as _),
             )
         }
-        .map_err(|err| format!("create window: {err}"))?;
+        .map_err(|err| Error::WindowCreation(err.to_string()))?;
+
+        let _ = unsafe { ImmAssociateContextEx(hwnd, HIMC::default(), 0) };
+        if let Err(err) = unsafe { RegisterTouchWindow(hwnd, REGISTER_TOUCH_WINDOW_FLAGS(0)) } {
+            log::error!("failed to register window for touch input: {err}");
+        }
+        // clicks outside of the popup only reach it while it has the capture
+        unsafe { SetCapture(hwnd) };
 
         Ok(Self {
             hwnd: SyncHWND(hwnd),
@@ -91,16 +568,35 @@ impl Window {
     fn hwnd(&self) -> HWND {
         self.hwnd.0
     }
+    pub fn as_parent(&self) -> Option<WindowParent> {
+        Some(WindowParent(self.hwnd().0 as usize))
+    }
+    fn on_owner_thread(&self) -> bool {
+        unsafe { GetWindowThreadProcessId(self.hwnd(), None) == GetCurrentThreadId() }
+    }
+    fn with_data<R>(&self, f: impl FnOnce(&WindowData) -> R) -> Option<R> {
+        // the window procedure could be using it at the same time
+        if !self.on_owner_thread() {
+            log::error!("window state can only be used on the thread that created the window");
+            return None;
+        }
+        window_data(self.hwnd()).map(|data| f(&data))
+    }
     fn get_client_rect(&self) -> RECT {
         let mut rect: RECT = RECT::default();
         unsafe { GetClientRect(self.hwnd(), std::ptr::addr_of_mut!(rect)).unwrap() }
         rect
     }
 
-    pub fn get_size(&self) -> (u32, u32) {
+    pub fn get_physical_size(&self) -> (u32, u32) {
         let (w, h) = get_size(self.get_client_rect());
         (w as u32, h as u32)
     }
+    pub fn get_logical_size(&self) -> (f64, f64) {
+        let (w, h) = self.get_physical_size();
+        let scale = self.get_scale();
+        (w as f64 / scale, h as f64 / scale)
+    }
 
     // fn get_window_rect(&self) -> RECT {
     //     let mut rect: RECT = RECT::default();
@@ -112,21 +608,410 @@ impl Window {
     //     (rect.left, rect.top)
     // }
 
-    pub fn get_scale_factor(&self) -> f64 {
+    pub fn set_title(&self, title: &str) {
+        if let Err(err) = unsafe { SetWindowTextW(self.hwnd(), PWSTRING::from(title).as_pcwstr()) }
+        {
+            log::error!("failed to set window title: {err}");
+            return;
+        }
+        self.with_data(|data| data.title.replace(title.to_owned()));
+    }
+    pub fn set_app_id(&self, _app_id: &str) {}
+    pub fn get_title(&self) -> String {
+        self.with_data(|data| data.title.borrow().clone())
+            .unwrap_or_default()
+    }
+    #[cfg(feature = "present")]
+    pub fn present_buffer(&self, size: (u32, u32), pixels: &[u32]) -> Result<(), Error> {
+        super::present::present_buffer(self.hwnd(), size, pixels)
+    }
+    pub fn set_custom_cursor(&self, image: &CursorImage) -> Result<(), Error> {
+        let cursor = create_cursor(image)?;
+        let old = self
+            .with_data(|data| {
+                // otherwise it only changes once the pointer moves
+                if data.pointer_position.get().is_some() {
+                    unsafe { SetCursor(Some(cursor)) };
+                }
+                data.cursor.replace(Some(cursor))
+            })
+            .flatten();
+        if let Some(old) = old {
+            let _ = unsafe { DestroyCursor(old) };
+        }
+        Ok(())
+    }
+    pub fn pointer_position(&self) -> Option<(f64, f64)> {
+        self.with_data(|data| data.pointer_position.get()).flatten()
+    }
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        let flags = if allowed { IACE_DEFAULT } else { 0 };
+        if !unsafe { ImmAssociateContextEx(self.hwnd(), HIMC::default(), flags) }.as_bool() {
+            log::error!(
+                "failed to set the input context of window: {}",
+                self.hwnd().0 as usize
+            );
+        }
+        self.with_data(|data| {
+            data.ime_allowed.set(allowed);
+            data.check_ime_enabled();
+        });
+    }
+    pub fn set_ime_cursor_area(&self, area: Rect) {
+        let hwnd = self.hwnd();
+        let scale = self.get_scale();
+        let rect = RECT {
+            left: (area.x as f64 * scale) as i32,
+            top: (area.y as f64 * scale) as i32,
+            right: ((area.x + area.width) as f64 * scale) as i32,
+            bottom: ((area.y + area.height) as f64 * scale) as i32,
+        };
+
+        let himc = unsafe { ImmGetContext(hwnd) };
+        if himc.0.is_null() {
+            return;
+        }
+        let candidate = CANDIDATEFORM {
+            dwIndex: 0,
+            dwStyle: CFS_EXCLUDE,
+            ptCurrentPos: POINT {
+                x: rect.left,
+                y: rect.bottom,
+            },
+            rcArea: rect,
+        };
+        let composition = COMPOSITIONFORM {
+            dwStyle: CFS_POINT,
+            ptCurrentPos: POINT {
+                x: rect.left,
+                y: rect.top,
+            },
+            rcArea: rect,
+        };
+        unsafe {
+            let _ = ImmSetCandidateWindow(himc, &candidate);
+            let _ = ImmSetCompositionWindow(himc, &composition);
+            let _ = ImmReleaseContext(hwnd, himc);
+        }
+    }
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
+        let hwnd = self.hwnd();
+        // also when switching between fullscreen modes
+        if let Some(device) = self
+            .with_data(|data| data.changed_display_mode.take())
+            .flatten()
+        {
+            Monitor::reset_video_mode(&device);
+        }
+
+        let Some(fullscreen) = fullscreen else {
+            if let Some((style, rect)) = self
+                .with_data(|data| data.fullscreen_restore.take())
+                .flatten()
+            {
+                unsafe { SetWindowLongPtrW(hwnd, GWL_STYLE, style) };
+                set_window_rect(hwnd, rect);
+            }
+            return;
+        };
+
+        let handle = match &fullscreen {
+            Fullscreen::Borderless(Some(monitor)) => monitor.backend_impl.handle(),
+            Fullscreen::Borderless(None) => unsafe {
+                MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST)
+            },
+            Fullscreen::Exclusive(mode) => HMONITOR(mode.monitor.0 as *mut _),
+        };
+        if let Fullscreen::Exclusive(mode) = &fullscreen {
+            let Some(monitor) = Monitor::from_handle(handle) else {
+                return;
+            };
+            // still goes borderless if the mode can't be set
+            if monitor.set_video_mode(mode) {
+                self.with_data(|data| data.changed_display_mode.set(monitor.name()));
+            }
+        }
+        // the bounds are only right after the mode change
+        let Some(monitor) = Monitor::from_handle(handle) else {
+            return;
+        };
+
+        if !self.get_fullscreen() {
+            let style = unsafe { GetWindowLongPtrW(hwnd, GWL_STYLE) };
+            let mut rect = RECT::default();
+            if let Err(err) = unsafe { GetWindowRect(hwnd, &mut rect) } {
+                log::error!("failed to get window rect: {err}");
+                return;
+            }
+            self.with_data(|data| data.fullscreen_restore.set(Some((style, rect))));
+            let style = (style & !(WS_OVERLAPPEDWINDOW.0 as isize)) | WS_POPUP.0 as isize;
+            unsafe { SetWindowLongPtrW(hwnd, GWL_STYLE, style) };
+        }
+        set_window_rect(hwnd, monitor.rect());
+    }
+    pub fn get_fullscreen(&self) -> bool {
+        self.with_data(|data| data.fullscreen_restore.get().is_some())
+            .unwrap_or_default()
+    }
+    pub fn suggested_max_size(&self) -> Option<(i32, i32)> {
+        // what's left of the monitor next to the taskbar
+        let monitor = unsafe { MonitorFromWindow(self.hwnd(), MONITOR_DEFAULTTONEAREST) };
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool() {
+            log::error!("failed to get monitor info");
+            return None;
+        }
+        let scale = to_scale_factor(unsafe { GetDpiForWindow(self.hwnd()) });
+        let work = info.rcWork;
+        Some((
+            ((work.right - work.left) as f64 / scale).round() as i32,
+            ((work.bottom - work.top) as f64 / scale).round() as i32,
+        ))
+    }
+    pub fn wm_capabilities(&self) -> WmCapabilities {
+        WmCapabilities::ALL
+    }
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        let hwnd = self.hwnd();
+        self.with_data(|data| {
+            data.grab.set(mode);
+            data.apply_grab(hwnd);
+        });
+    }
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.with_data(|data| data.grab.get()).unwrap_or_default()
+    }
+    pub fn set_grab_escape_key(&self, key: Option<PhysicalKey>) {
+        self.with_data(|data| data.grab_escape_key.set(key));
+    }
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.with_data(|data| data.modifiers.get())
+            .unwrap_or_default()
+    }
+    pub fn lock_state(&self) -> LockState {
+        self.with_data(|data| data.lock_state.get())
+            .flatten()
+            .unwrap_or_default()
+    }
+    pub fn size_at_last_event(&self) -> (f64, f64) {
+        self.with_data(|data| data.last_event_size.get())
+            .unwrap_or_default()
+    }
+
+    pub fn get_scale(&self) -> f64 {
         let dpi = unsafe { GetDpiForWindow(self.hwnd()) };
         assert_ne!(dpi, 0);
         to_scale_factor(dpi)
     }
 }
 impl Window {
+    pub fn set_min_size(&self, size: Option<(f64, f64)>) {
+        self.with_data(|data| data.min_size.set(size));
+    }
+    pub fn set_max_size(&self, size: Option<(f64, f64)>) {
+        self.with_data(|data| data.max_size.set(size));
+    }
+    pub fn set_resize_increments(&self, increments: Option<(u32, u32)>) {
+        self.with_data(|data| data.resize_increments.set(increments));
+    }
+    pub fn set_aspect_ratio(&self, ratio: Option<(u32, u32)>) {
+        self.with_data(|data| data.aspect_ratio.set(ratio));
+    }
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        let hwnd = self.hwnd();
+        self.with_data(|data| {
+            data.theme_override.set(theme);
+            data.update_theme(hwnd);
+        });
+    }
+    pub fn theme(&self) -> Option<Theme> {
+        self.with_data(|data| data.theme.get())
+    }
+    pub fn set_corner_preference(&self, preference: CornerPreference) {
+        let preference = match preference {
+            CornerPreference::Default => DWMWCP_DEFAULT,
+            CornerPreference::Round => DWMWCP_ROUND,
+            CornerPreference::RoundSmall => DWMWCP_ROUNDSMALL,
+            CornerPreference::DoNotRound => DWMWCP_DONOTROUND,
+        };
+        // unknown before windows 11
+        if let Err(err) =
+            set_dwm_attribute(self.hwnd(), DWMWA_WINDOW_CORNER_PREFERENCE, &preference)
+        {
+            log::warn!("failed to set corner preference: {err}");
+        }
+    }
+    pub fn set_backdrop(&self, backdrop: Backdrop) {
+        let backdrop = match backdrop {
+            Backdrop::None => DWMSBT_NONE,
+            Backdrop::Mica => DWMSBT_MAINWINDOW,
+            Backdrop::Acrylic => DWMSBT_TRANSIENTWINDOW,
+            Backdrop::Tabbed => DWMSBT_TABBEDWINDOW,
+        };
+        // unknown before windows 11 22H2
+        if let Err(err) = set_dwm_attribute(self.hwnd(), DWMWA_SYSTEMBACKDROP_TYPE, &backdrop) {
+            log::warn!("failed to set backdrop: {err}");
+        }
+    }
+    pub fn set_taskbar_progress(&self, progress: Option<Progress>) {
+        let hwnd = self.hwnd();
+        self.with_data(|data| {
+            data.taskbar_progress.set(progress);
+            data.apply_taskbar_progress(hwnd);
+        });
+    }
+    pub fn set_hittest_handler(
+        &self,
+        handler: impl Fn(f64, f64) -> HitTestResult + Send + 'static,
+    ) {
+        // the old one is dropped once the data isn't borrowed anymore
+        self.with_data(|data| data.hittest.replace(Some(Rc::new(handler))));
+    }
+    pub fn set_content_protected(&self, protected: bool) -> bool {
+        let affinity = if protected {
+            WDA_EXCLUDEFROMCAPTURE
+        } else {
+            WDA_NONE
+        };
+        // excluding needs windows 10 2004
+        match unsafe { SetWindowDisplayAffinity(self.hwnd(), affinity) } {
+            Ok(()) => true,
+            Err(err) => {
+                log::error!("failed to set window display affinity: {err}");
+                false
+            }
+        }
+    }
+    pub fn pre_present(&self) {}
+    pub fn set_opaque_region(&self, _region: Option<&[Rect]>) {}
+    pub fn set_content_geometry(&self, _geometry: Rect) {}
+    pub fn set_visible(&self, visible: bool) {
+        if visible && !self.is_visible() && self.with_data(|data| data.popup).unwrap_or(false) {
+            log::warn!("a hidden popup can't be shown again");
+            return;
+        }
+        let cmd = if visible { SW_SHOW } else { SW_HIDE };
+        // returns the previous visibility, not an error
+        let _ = unsafe { ShowWindow(self.hwnd(), cmd) };
+    }
+    pub fn set_keyboard_grab(&self, grab: bool) {
+        self.with_data(|data| data.keyboard_grab.set(grab));
+    }
+    pub fn set_idle_inhibited(&self, inhibited: bool) {
+        self.with_data(|data| data.set_idle_inhibited(inhibited));
+    }
+    pub fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(self.hwnd()) }.as_bool()
+    }
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        Monitor::from_handle(unsafe { MonitorFromWindow(self.hwnd(), MONITOR_DEFAULTTONEAREST) })
+    }
+    pub fn id(&self) -> WindowId {
+        WindowId(self.hwnd().0 as usize)
+    }
+    pub fn raise(&self) -> bool {
+        self.set_z_order(HWND_TOP)
+    }
+    pub fn lower(&self) -> bool {
+        self.set_z_order(HWND_BOTTOM)
+    }
+    fn set_z_order(&self, insert_after: HWND) -> bool {
+        let flags = SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE;
+        match unsafe { SetWindowPos(self.hwnd(), Some(insert_after), 0, 0, 0, 0, flags) } {
+            Ok(()) => true,
+            Err(err) => {
+                log::error!("failed to change window z order: {err}");
+                false
+            }
+        }
+    }
+    pub fn focus(&self) -> bool {
+        let hwnd = self.hwnd();
+        if unsafe { IsIconic(hwnd) }.as_bool() {
+            let _ = unsafe { ShowWindow(hwnd, SW_RESTORE) };
+        }
+        // refused unless this process may take the foreground, the taskbar button flashes instead
+        unsafe { SetForegroundWindow(hwnd) }.as_bool()
+    }
+    pub fn set_event_handler(&self, handler: impl FnMut(WindowEvent) + Send + 'static) {
+        self.with_data(|data| {
+            data.event_handler
+                .replace(Some(Rc::new(HookCell::new(handler))))
+        });
+    }
+    pub fn raw_hwnd(&self) -> isize {
+        self.hwnd().0 as isize
+    }
+    pub fn raw_hinstance(&self) -> isize {
+        unsafe { GetWindowLongPtrW(self.hwnd(), GWLP_HINSTANCE) }
+    }
     pub fn request_redraw(&self) {
-        if !unsafe { RedrawWindow(Some(self.hwnd()), None, None, RDW_INTERNALPAINT) }.as_bool() {
+        // invalidated as a whole so it can't be mistaken for a partial redraw
+        if !unsafe {
+            RedrawWindow(
+                Some(self.hwnd()),
+                None,
+                None,
+                RDW_INTERNALPAINT | RDW_INVALIDATE,
+            )
+        }
+        .as_bool()
+        {
+            log::error!(
+                "failed to request redraw for window: {}",
+                self.hwnd().0 as usize
+            );
+        }
+    }
+}
+impl Window {
+    pub fn request_redraw_region(&self, rect: Rect) {
+        let scale = self.get_scale();
+        // rounded outwards to cover every partially damaged pixel
+        let rect = RECT {
+            left: (rect.x as f64 * scale).floor() as i32,
+            top: (rect.y as f64 * scale).floor() as i32,
+            right: ((rect.x + rect.width) as f64 * scale).ceil() as i32,
+            bottom: ((rect.y + rect.height) as f64 * scale).ceil() as i32,
+        };
+        if !unsafe {
+            RedrawWindow(
+                Some(self.hwnd()),
+                Some(&rect as *const _),
+                None,
+                RDW_INTERNALPAINT | RDW_INVALIDATE,
+            )
+        }
+        .as_bool()
+        {
             log::error!(
                 "failed to request redraw for window: {}",
                 self.hwnd().0 as usize
             );
         }
     }
+    pub fn request_redraw_at(&self, deadline: Instant) {
+        let hwnd = self.hwnd();
+        self.with_data(|data| {
+            if data
+                .redraw_deadline
+                .get()
+                .is_some_and(|current| current <= deadline)
+            {
+                return;
+            }
+            data.redraw_deadline.set(Some(deadline));
+
+            let elapse = deadline.saturating_duration_since(Instant::now());
+            // replaces the previous timer since the id is the same
+            if unsafe { SetTimer(Some(hwnd), REDRAW_TIMER, elapse.as_millis() as u32, None) } == 0 {
+                log::error!("failed to set redraw timer for window: {}", hwnd.0 as usize);
+            }
+        });
+    }
 }
 impl Drop for Window {
     fn drop(&mut self) {
@@ -137,15 +1022,27 @@ impl Drop for Window {
 
 impl rwh::HasWindowHandle for Window {
     fn window_handle(&self) -> std::result::Result<rwh::WindowHandle<'_>, rwh::HandleError> {
-        let mut window_handle = rwh::Win32WindowHandle::new(
-            std::num::NonZeroIsize::new(self.hwnd().0 as isize).unwrap(),
-        );
-        let hinstance = unsafe { GetWindowLongPtrW(self.hwnd(), GWLP_HINSTANCE) };
-        window_handle.hinstance = std::num::NonZeroIsize::new(hinstance as isize);
+        let mut window_handle =
+            rwh::Win32WindowHandle::new(std::num::NonZeroIsize::new(self.raw_hwnd()).unwrap());
+        window_handle.hinstance = std::num::NonZeroIsize::new(self.raw_hinstance());
 
         Ok(unsafe { rwh::WindowHandle::borrow_raw(rwh::RawWindowHandle::Win32(window_handle)) })
     }
 }
+impl std::fmt::Debug for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Window")
+            .field("id", &self.id())
+            .field("size", &self.get_logical_size())
+            .field("scale", &self.get_scale())
+            // `None` on other threads, where the window data can't be read
+            .field(
+                "fullscreen",
+                &self.on_owner_thread().then(|| self.get_fullscreen()),
+            )
+            .finish_non_exhaustive()
+    }
+}
 impl rwh::HasDisplayHandle for Window {
     fn display_handle(&self) -> std::result::Result<rwh::DisplayHandle<'_>, rwh::HandleError> {
         let display_handle = rwh::RawDisplayHandle::Windows(rwh::WindowsDisplayHandle::new());
@@ -159,7 +1056,7 @@ pub extern "system" fn wndproc(
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
-    let data = unsafe { GetWindowLongPtrW(window, GWLP_USERDATA) } as *mut WindowData;
+    let data = unsafe { GetWindowLongPtrW(window, GWLP_USERDATA) } as *const WindowData;
     let data = match (data.is_null(), message) {
         // called during CreateWindowEx
         (true, WM_NCCREATE) => {
@@ -169,69 +1066,221 @@ pub extern "system" fn wndproc(
                     .as_ref()
                     .unwrap()
             };
-            let data = Box::new(WindowData {
+            let theme = system_theme();
+            apply_theme(window, theme);
+            let data = Rc::new(WindowData {
                 event_hook: info.event_hook.clone(),
-                window_id: window.0 as usize,
-                _class: info.class.clone(),
+                raw_hook: info.raw_hook.clone(),
+                key_repeat: info.key_repeat.clone(),
+                idle_inhibitors: info.idle_inhibitors.clone(),
+                idle_inhibited: Cell::new(false),
+                window_id: WindowId(window.0 as usize),
+                title: RefCell::new(info.title.clone()),
+                min_size: Cell::new(None),
+                max_size: Cell::new(None),
+                resize_increments: Cell::new(None),
+                aspect_ratio: Cell::new(None),
+                redraw_deadline: Cell::new(None),
+                last_event_size: Cell::new((0.0, 0.0)),
+                pointer_position: Cell::new(None),
+                grab_escape_key: Cell::new(None),
+                modifiers: Cell::new(KeyModifiers::empty()),
+                lock_state: Cell::new(None),
+                keyboard_layout: Cell::new(None),
+                focused: Cell::new(false),
+                ime_allowed: Cell::new(false),
+                ime_enabled: Cell::new(false),
+                grab: Cell::new(CursorGrabMode::None),
+                cursor_hidden: Cell::new(false),
+                fullscreen_restore: Cell::new(None),
+                changed_display_mode: Cell::new(None),
+                monitor: Cell::new(HMONITOR::default()),
+                cursor: Cell::new(None),
+                event_handler: RefCell::new(None),
+                theme_override: Cell::new(None),
+                theme: Cell::new(theme),
+                taskbar_progress: Cell::new(None),
+                taskbar: RefCell::new(None),
+                keyboard_grab: Cell::new(false),
+                hittest: RefCell::new(None),
+                clicks: RefCell::default(),
+                scroll_burst: Cell::new(None),
+                gesture: Cell::new(None),
+                occluded: Cell::new(false),
+                drop_target: RefCell::new(None),
+                popup: info.popup,
+                modal_parent: info.modal_parent,
+                class: info.class.clone(),
             });
-            unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, Box::into_raw(data) as isize) };
+            unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, Rc::into_raw(data) as isize) };
             return unsafe { DefWindowProcW(window, message, wparam, lparam) };
         }
         // ready to destroy and free memory
         (false, WAYWIN_DESTROY) => {
-            drop(unsafe { Box::from_raw(data) });
+            // the window's own reference, an outer call of the window procedure
+            // keeps using its own one until it returns
+            let data = unsafe { Rc::from_raw(data) };
+            // don't leave the monitor in a mode nothing is using anymore
+            if let Some(device) = data.changed_display_mode.take() {
+                Monitor::reset_video_mode(&device);
+            }
+            if let Some(cursor) = data.cursor.take() {
+                let _ = unsafe { DestroyCursor(cursor) };
+            }
+            drop(data.drop_target.take());
+            data.set_idle_inhibited(false);
+            // before destroying, otherwise another app gets activated instead of the parent
+            if let Some(parent) = data.modal_parent {
+                let _ = unsafe { EnableWindow(parent, true) };
+            }
+            // the `WM_KILLFOCUS` sent by `DestroyWindow` comes after the data is gone
+            if data.focused.get() {
+                data.release_grab();
+                data.hook(WindowEvent::Focus(false));
+            }
             unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, 0) };
             if let Err(err) = unsafe { DestroyWindow(window) } {
                 log::error!("error during destroy window: {err}");
             }
+            data.hook(WindowEvent::Destroyed);
+            drop(data);
             return unsafe { DefWindowProcW(window, message, wparam, lparam) };
         }
         // sanity check data exists after NCCREATE, if not then return error
         (true, WM_CREATE) => return LRESULT(-1),
         // no data so don't handle anything
         (true, _) => return unsafe { DefWindowProcW(window, message, wparam, lparam) },
-        // yes data and deref it
-        (false, _) => unsafe { &mut (*data) },
+        // yes data, kept alive until this call returns
+        (false, _) => {
+            unsafe { Rc::increment_strong_count(data) };
+            unsafe { Rc::from_raw(data) }
+        }
     };
 
+    if data.raw_hook(RawMessage {
+        hwnd: window.0 as isize,
+        message,
+        wparam: wparam.0,
+        lparam: lparam.0,
+    }) {
+        return LRESULT(0);
+    }
+
     match message {
         WM_CLOSE => {
-            data.hook(Event::Close);
+            data.hook(WindowEvent::CloseRequested);
+            LRESULT(0)
+        }
+        WM_SETFOCUS => {
+            data.focused.set(true);
+            data.apply_grab(window);
+            data.hook(WindowEvent::Focus(true));
+            // modifiers pressed in another window never sent key messages here
+            data.check_modifiers();
+            data.check_lock_state();
+            data.check_keyboard_layout();
+            data.check_ime_enabled();
+            LRESULT(0)
+        }
+        WM_INPUTLANGCHANGE => {
+            data.check_keyboard_layout();
+            unsafe { DefWindowProcW(window, message, wparam, lparam) }
+        }
+        WM_KILLFOCUS => {
+            data.focused.set(false);
+            // the grab stays requested and comes back with the focus
+            data.release_grab();
+            data.hook(WindowEvent::Focus(false));
+            data.check_ime_enabled();
             LRESULT(0)
         }
         WM_SIZE => {
-            let w = loword(lparam.0 as usize);
-            let h = hiword(lparam.0 as usize);
-            data.hook(Event::Resize(w, h));
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            let width = loword(lparam.0 as usize);
+            let height = hiword(lparam.0 as usize);
+            data.last_event_size
+                .set((width as f64 / scale, height as f64 / scale));
+            data.apply_grab(window);
+            data.hook(WindowEvent::Resized { width, height });
+            data.check_occluded(window);
+            data.check_monitor(window);
+            LRESULT(0)
+        }
+        WM_MOVE => {
+            let x = get_x(lparam.0 as usize) as i32;
+            let y = get_y(lparam.0 as usize) as i32;
+            data.apply_grab(window);
+            data.hook(WindowEvent::Moved(x, y));
+            data.check_monitor(window);
             LRESULT(0)
         }
         WM_PAINT => {
+            let mut update = RECT::default();
+            let mut client = RECT::default();
+            // nothing invalidated means an internal paint, which redraws everything
+            let partial = unsafe { GetUpdateRect(window, Some(&mut update as *mut _), false) }
+                .as_bool()
+                && unsafe { GetClientRect(window, &mut client) }.is_ok()
+                && update != client;
             if !unsafe { ValidateRect(Some(window), None) }.as_bool() {
                 log::error!("failed to validate rect for window: {}", window.0 as usize);
             }
-            data.hook(Event::Paint);
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            let damage = partial.then(|| {
+                let left = (update.left as f64 / scale).floor() as i32;
+                let top = (update.top as f64 / scale).floor() as i32;
+                Rect {
+                    x: left,
+                    y: top,
+                    width: (update.right as f64 / scale).ceil() as i32 - left,
+                    height: (update.bottom as f64 / scale).ceil() as i32 - top,
+                }
+            });
+            data.hook(WindowEvent::Paint { damage });
             LRESULT(0)
         }
         WM_DPICHANGED => {
+            let dpi = loword(wparam.0);
             let rect = unsafe { &*(lparam.0 as *const RECT) };
             let (w, h) = get_size(*rect);
             let x = rect.left;
             let y = rect.top;
 
+            // the suggested rect includes the frame
+            let (frame_w, frame_h) = client_to_window_size(window, (0.0, 0.0), 1.0, dpi);
+            data.hook(WindowEvent::ScaleFactorChanged {
+                scale: to_scale_factor(dpi),
+                suggested_size: ((w - frame_w).max(0) as u32, (h - frame_h).max(0) as u32),
+            });
+
             if let Err(err) =
                 unsafe { SetWindowPos(window, None, x, y, w, h, SWP_NOZORDER | SWP_NOACTIVATE) }
             {
                 log::error!("failed to set window position after dpi change: {err}");
             }
-
-            data.hook(Event::NewScaleFactor(to_scale_factor(
-                loword(wparam.0) as u32
-            )));
+            LRESULT(0)
+        }
+        WM_SETCURSOR if loword(lparam.0 as usize) == HTCLIENT && data.cursor.get().is_some() => {
+            unsafe { SetCursor(data.cursor.get()) };
+            LRESULT(1)
+        }
+        WM_MOUSEMOVE if data.cursor_hidden.get() => {
+            // locked, only raw input is reported
+            let rect = client_screen_rect(window);
+            let mut position = POINT {
+                x: get_x(lparam.0 as usize) as i32,
+                y: get_y(lparam.0 as usize) as i32,
+            };
+            let _ = unsafe { ClientToScreen(window, &mut position) };
+            if position != rect_center(rect) {
+                center_cursor(window);
+            }
             LRESULT(0)
         }
         WM_MOUSEMOVE => {
-            let x = get_x(lparam.0 as usize) as i32;
-            let y = get_y(lparam.0 as usize) as i32;
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            let x = get_x(lparam.0 as usize) as f64 / scale;
+            let y = get_y(lparam.0 as usize) as f64 / scale;
 
             // let mods = MODIFIERKEYS_FLAGS(wparam.0 as u32);
 
@@ -245,17 +1294,770 @@ pub extern "system" fn wndproc(
             //     x2btn: mods.contains(MK_XBUTTON2),
             // };
             //
-            data.hook(Event::MouseMoved(x, y));
+            if data.pointer_position.get().is_none() {
+                // ask for a `WM_MOUSELEAVE`, this has to be done again after every leave
+                let mut track = TRACKMOUSEEVENT {
+                    cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                    dwFlags: TME_LEAVE,
+                    hwndTrack: window,
+                    dwHoverTime: 0,
+                };
+                if let Err(err) = unsafe { TrackMouseEvent(&mut track) } {
+                    log::error!("failed to track mouse leave: {err}");
+                }
+                // like on wayland, clicks don't add up across leaving and coming back
+                data.clicks.borrow_mut().reset();
+                data.hook(WindowEvent::PointerEntered);
+            }
+            // windows already merges queued moves but also repeats
+            // the current position, e.g. after the window changed
+            if data.pointer_position.replace(Some((x, y))) != Some((x, y)) {
+                data.hook(WindowEvent::PointerMoved {
+                    x,
+                    y,
+                    timestamp: message_time(),
+                    seat: SEAT,
+                });
+            }
+            LRESULT(0)
+        }
+        WM_INPUT => {
+            let mut raw = RAWINPUT::default();
+            let mut size = std::mem::size_of::<RAWINPUT>() as u32;
+            let read = unsafe {
+                GetRawInputData(
+                    HRAWINPUT(lparam.0 as _),
+                    RID_INPUT,
+                    Some(std::ptr::addr_of_mut!(raw).cast()),
+                    &mut size,
+                    std::mem::size_of::<RAWINPUTHEADER>() as u32,
+                )
+            };
+            if read != u32::MAX && raw.header.dwType == RIM_TYPEMOUSE.0 {
+                let mouse = unsafe { raw.data.mouse };
+                // tablets and remote desktop report absolute positions instead
+                if mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 == 0
+                    && (mouse.lLastX != 0 || mouse.lLastY != 0)
+                {
+                    let delta = (mouse.lLastX as f64, mouse.lLastY as f64);
+                    data.device_hook(DeviceEvent::PointerMoved {
+                        delta,
+                        delta_unaccel: delta,
+                    });
+                }
+            }
+            // lets the system clean up after the raw input
+            unsafe { DefWindowProcW(window, message, wparam, lparam) }
+        }
+        WM_CAPTURECHANGED => {
+            // another window took the mouse away, e.g. after switching apps
+            if data.popup && lparam.0 != window.0 as isize {
+                data.dismiss_popup(window);
+            }
+            LRESULT(0)
+        }
+        WM_MOUSELEAVE => {
+            data.pointer_position.set(None);
+            data.hook(WindowEvent::PointerLeft);
+            LRESULT(0)
+        }
+        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_RBUTTONDOWN | WM_RBUTTONUP | WM_MBUTTONDOWN
+        | WM_MBUTTONUP | WM_XBUTTONDOWN | WM_XBUTTONUP => {
+            let (down, button) = match message {
+                WM_LBUTTONDOWN => (true, PointerButton::Left),
+                WM_LBUTTONUP => (false, PointerButton::Left),
+                WM_RBUTTONDOWN => (true, PointerButton::Right),
+                WM_RBUTTONUP => (false, PointerButton::Right),
+                WM_MBUTTONDOWN => (true, PointerButton::Middle),
+                WM_MBUTTONUP => (false, PointerButton::Middle),
+                _ => (
+                    message == WM_XBUTTONDOWN,
+                    PointerButton::from_xbutton(hiword(wparam.0) as u16),
+                ),
+            };
+
+            if data.popup && down {
+                let x = get_x(lparam.0 as usize) as i32;
+                let y = get_y(lparam.0 as usize) as i32;
+                let mut rect = RECT::default();
+                let _ = unsafe { GetClientRect(window, &mut rect) };
+                if x < rect.left || x >= rect.right || y < rect.top || y >= rect.bottom {
+                    data.dismiss_popup(window);
+                    return LRESULT(0);
+                }
+            }
+
+            // keep getting mouse messages while a button is held outside of the window,
+            // popups keep it until they are dismissed
+            let held = MK_LBUTTON | MK_RBUTTON | MK_MBUTTON | MK_XBUTTON1 | MK_XBUTTON2;
+            if down {
+                unsafe { SetCapture(window) };
+            } else if !data.popup && wparam.0 as u32 & held.0 == 0 {
+                if let Err(err) = unsafe { ReleaseCapture() } {
+                    log::error!("failed to release mouse capture: {err}");
+                }
+            }
+
+            let timestamp = message_time();
+            let click_count = if down {
+                let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+                let x = get_x(lparam.0 as usize) as f64 / scale;
+                let y = get_y(lparam.0 as usize) as f64 / scale;
+                // the metrics are the size of the whole rectangle around the previous press
+                let distance = unsafe {
+                    (
+                        GetSystemMetrics(SM_CXDOUBLECLK) as f64 / 2.0 / scale,
+                        GetSystemMetrics(SM_CYDOUBLECLK) as f64 / 2.0 / scale,
+                    )
+                };
+                let interval = Duration::from_millis(unsafe { GetDoubleClickTime() } as u64);
+                data.clicks
+                    .borrow_mut()
+                    .press(button, timestamp, (x, y), interval, distance)
+            } else {
+                data.clicks.borrow_mut().release(button)
+            };
+            data.hook(WindowEvent::PointerButton {
+                down,
+                button,
+                click_count,
+                timestamp,
+                seat: SEAT,
+            });
+            // the x buttons expect `TRUE`
+            if matches!(message, WM_XBUTTONDOWN | WM_XBUTTONUP) {
+                LRESULT(1)
+            } else {
+                LRESULT(0)
+            }
+        }
+        WM_SETTINGCHANGE => {
+            // names what changed, dark mode is part of the color set
+            let area = PCWSTR(lparam.0 as *const u16);
+            if !area.is_null()
+                && unsafe { area.to_string() }.is_ok_and(|area| area == "ImmersiveColorSet")
+            {
+                data.update_theme(window);
+            }
+            unsafe { DefWindowProcW(window, message, wparam, lparam) }
+        }
+        WM_NCHITTEST => {
+            // the frame keeps working, only the client area is up to the handler
+            let hit = unsafe { DefWindowProcW(window, message, wparam, lparam) };
+            // not borrowed while it runs, it can be replaced from within
+            let hittest = data.hittest.borrow().clone();
+            let Some(hittest) = hittest.filter(|_| hit.0 == HTCLIENT as isize) else {
+                return hit;
+            };
+            let mut point = POINT {
+                x: get_x(lparam.0 as usize) as i32,
+                y: get_y(lparam.0 as usize) as i32,
+            };
+            let _ = unsafe { ScreenToClient(window, &mut point) };
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            let code = match hittest(point.x as f64 / scale, point.y as f64 / scale) {
+                HitTestResult::Client => HTCLIENT,
+                HitTestResult::Caption => HTCAPTION,
+                HitTestResult::Top => HTTOP,
+                HitTestResult::Bottom => HTBOTTOM,
+                HitTestResult::Left => HTLEFT,
+                HitTestResult::Right => HTRIGHT,
+                HitTestResult::TopLeft => HTTOPLEFT,
+                HitTestResult::TopRight => HTTOPRIGHT,
+                HitTestResult::BottomLeft => HTBOTTOMLEFT,
+                HitTestResult::BottomRight => HTBOTTOMRIGHT,
+            };
+            LRESULT(code as isize)
+        }
+        WM_SIZING => {
+            if data.resize_increments.get().is_none() && data.aspect_ratio.get().is_none() {
+                return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+            }
+            let rect = unsafe { &mut *(lparam.0 as *mut RECT) };
+            let edge = wparam.0 as u32;
+            let dpi = unsafe { GetDpiForWindow(window) };
+            let scale = to_scale_factor(dpi);
+            // only the client area is constrained, the frame stays as it is
+            let (frame_w, frame_h) = client_to_window_size(window, (0.0, 0.0), scale, dpi);
+            let (w, h) = get_size(*rect);
+            let (mut w, mut h) = (w - frame_w, h - frame_h);
+            if let Some((inc_w, inc_h)) = data.resize_increments.get() {
+                // the nearest multiple
+                let snap = |size: i32, increment: u32| {
+                    let increment = ((increment as f64 * scale).round() as i32).max(1);
+                    (size as f64 / increment as f64).round().max(1.0) as i32 * increment
+                };
+                (w, h) = (snap(w, inc_w), snap(h, inc_h));
+            }
+            if let Some((ratio_w, ratio_h)) = data.aspect_ratio.get() {
+                let (ratio_w, ratio_h) = (ratio_w.max(1) as f64, ratio_h.max(1) as f64);
+                // corners follow the larger dimension so the other one doesn't jitter
+                let follow_width = match edge {
+                    WMSZ_LEFT | WMSZ_RIGHT => true,
+                    WMSZ_TOP | WMSZ_BOTTOM => false,
+                    _ => w as f64 / ratio_w >= h as f64 / ratio_h,
+                };
+                if follow_width {
+                    h = (w as f64 * ratio_h / ratio_w).round() as i32;
+                } else {
+                    w = (h as f64 * ratio_w / ratio_h).round() as i32;
+                }
+            }
+            let (w, h) = (w + frame_w, h + frame_h);
+            // only move the edges being dragged
+            if matches!(edge, WMSZ_LEFT | WMSZ_TOPLEFT | WMSZ_BOTTOMLEFT) {
+                rect.left = rect.right - w;
+            } else {
+                rect.right = rect.left + w;
+            }
+            if matches!(edge, WMSZ_TOP | WMSZ_TOPLEFT | WMSZ_TOPRIGHT) {
+                rect.top = rect.bottom - h;
+            } else {
+                rect.bottom = rect.top + h;
+            }
+            LRESULT(1)
+        }
+        WM_GETMINMAXINFO => {
+            let info = unsafe { &mut *(lparam.0 as *mut MINMAXINFO) };
+            let dpi = unsafe { GetDpiForWindow(window) };
+            let scale = to_scale_factor(dpi);
+            if let Some(size) = data.min_size.get() {
+                let (w, h) = client_to_window_size(window, size, scale, dpi);
+                info.ptMinTrackSize = POINT { x: w, y: h };
+            }
+            if let Some(size) = data.max_size.get() {
+                let (w, h) = client_to_window_size(window, size, scale, dpi);
+                info.ptMaxTrackSize = POINT { x: w, y: h };
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == REDRAW_TIMER => {
+            let _ = unsafe { KillTimer(Some(window), REDRAW_TIMER) };
+            data.redraw_deadline.set(None);
+            if !unsafe {
+                RedrawWindow(Some(window), None, None, RDW_INTERNALPAINT | RDW_INVALIDATE)
+            }
+            .as_bool()
+            {
+                log::error!("failed to request redraw for window: {}", window.0 as usize);
+            }
+            LRESULT(0)
+        }
+        WM_TIMER if wparam.0 == SCROLL_STOP_TIMER => {
+            let _ = unsafe { KillTimer(Some(window), SCROLL_STOP_TIMER) };
+            data.hook(WindowEvent::Scroll {
+                delta: ScrollDelta::Pixels(0.0, 0.0),
+                source: data.scroll_burst.take(),
+                stop: true,
+                timestamp: message_time(),
+                seat: SEAT,
+            });
             LRESULT(0)
         }
         WM_ERASEBKGND => LRESULT(1),
+        WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+            let wheel_delta = hiword(wparam.0) as i16;
+            // precision touchpads send fractions of a notch,
+            // whole notches in the middle of their scrolling still belong to it
+            let source = if let Some(source) = data.scroll_burst.get() {
+                source
+            } else if wheel_delta % WHEEL_DELTA as i16 == 0 {
+                ScrollSource::Wheel
+            } else {
+                ScrollSource::Finger
+            };
+            let notches = wheel_delta as f64 / WHEEL_DELTA as f64;
+            // positive is up for the vertical wheel but right for the horizontal one.
+            // shift turns a vertical wheel horizontal, up becoming left
+            let shift = loword(wparam.0) & MK_SHIFT.0 != 0;
+            let (x, y) = if message == WM_MOUSEHWHEEL {
+                (-notches, 0.0)
+            } else if shift && source == ScrollSource::Wheel {
+                (notches, 0.0)
+            } else {
+                (0.0, notches)
+            };
+            let delta = if source == ScrollSource::Wheel {
+                ScrollDelta::Lines(x, y)
+            } else {
+                // replaces the running timer, so it only fires once the scrolling pauses
+                let delay = SCROLL_STOP_DELAY.as_millis() as u32;
+                if unsafe { SetTimer(Some(window), SCROLL_STOP_TIMER, delay, None) } == 0 {
+                    log::error!("failed to set scroll stop timer");
+                } else {
+                    data.scroll_burst.set(Some(source));
+                }
+                ScrollDelta::Pixels(x * PIXELS_PER_NOTCH, y * PIXELS_PER_NOTCH)
+            };
+            data.hook(WindowEvent::Scroll {
+                delta,
+                source: Some(source),
+                stop: false,
+                timestamp: message_time(),
+                seat: SEAT,
+            });
+            LRESULT(0)
+        }
+        WM_TOUCH => {
+            let handle = HTOUCHINPUT(lparam.0 as _);
+            let mut inputs = vec![TOUCHINPUT::default(); loword(wparam.0) as usize];
+            let result = unsafe {
+                GetTouchInputInfo(
+                    handle,
+                    &mut inputs,
+                    std::mem::size_of::<TOUCHINPUT>() as i32,
+                )
+            };
+            if let Err(err) = result {
+                log::error!("failed to get touch input: {err}");
+                return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+            }
+
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            for input in inputs {
+                let phase = if input.dwFlags.contains(TOUCHEVENTF_DOWN) {
+                    TouchPhase::Started
+                } else if input.dwFlags.contains(TOUCHEVENTF_UP) {
+                    TouchPhase::Ended
+                } else if input.dwFlags.contains(TOUCHEVENTF_MOVE) {
+                    TouchPhase::Moved
+                } else {
+                    continue;
+                };
+                // in hundredths of a physical pixel in screen coordinates
+                let mut point = POINT {
+                    x: input.x / 100,
+                    y: input.y / 100,
+                };
+                let _ = unsafe { ScreenToClient(window, &mut point) };
+                data.hook(WindowEvent::Touch {
+                    id: input.dwID as u64,
+                    phase,
+                    x: point.x as f64 / scale,
+                    y: point.y as f64 / scale,
+                    seat: SEAT,
+                });
+            }
+
+            let _ = unsafe { CloseTouchInputHandle(handle) };
+            LRESULT(0)
+        }
+        WM_GESTURE => {
+            let handle = HGESTUREINFO(lparam.0 as _);
+            let mut info = GESTUREINFO {
+                cbSize: std::mem::size_of::<GESTUREINFO>() as u32,
+                ..Default::default()
+            };
+            if let Err(err) = unsafe { GetGestureInfo(handle, &mut info) } {
+                log::error!("failed to get gesture: {err}");
+                return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+            }
+            let zoom = match GESTURECONFIG_ID(info.dwID) {
+                GID_ZOOM => true,
+                GID_PAN => false,
+                // also closes the handle
+                _ => return unsafe { DefWindowProcW(window, message, wparam, lparam) },
+            };
+            let _ = unsafe { CloseGestureInfoHandle(handle) };
+
+            let scale = to_scale_factor(unsafe { GetDpiForWindow(window) });
+            let mut point = POINT {
+                x: info.ptsLocation.x as i32,
+                y: info.ptsLocation.y as i32,
+            };
+            let _ = unsafe { ScreenToClient(window, &mut point) };
+            let position = (point.x as f64 / scale, point.y as f64 / scale);
+            let distance = info.ullArguments as f64;
+
+            let (gesture, phase, delta) = if info.dwFlags & GF_BEGIN != 0 {
+                let gesture = Gesture {
+                    zoom,
+                    distance,
+                    position,
+                    scale: 1.0,
+                };
+                (gesture, GesturePhase::Begin, (0.0, 0.0))
+            } else {
+                let Some(gesture) = data.gesture.get().filter(|gesture| gesture.zoom == zoom)
+                else {
+                    return LRESULT(0);
+                };
+                // pans keep going after the fingers are lifted
+                if info.dwFlags & GF_INERTIA != 0 && info.dwFlags & GF_END == 0 {
+                    return LRESULT(0);
+                }
+                let delta = (
+                    position.0 - gesture.position.0,
+                    position.1 - gesture.position.1,
+                );
+                let gesture = Gesture {
+                    position,
+                    scale: if zoom && gesture.distance > 0.0 {
+                        distance / gesture.distance
+                    } else {
+                        gesture.scale
+                    },
+                    ..gesture
+                };
+                if info.dwFlags & GF_END != 0 {
+                    (gesture, GesturePhase::End, (0.0, 0.0))
+                } else {
+                    (gesture, GesturePhase::Update, delta)
+                }
+            };
+            data.gesture
+                .set((phase != GesturePhase::End).then_some(gesture));
+
+            data.hook(if zoom {
+                WindowEvent::PinchGesture {
+                    delta,
+                    scale: gesture.scale,
+                    rotation: 0.0,
+                    phase,
+                    seat: SEAT,
+                }
+            } else {
+                WindowEvent::SwipeGesture {
+                    dx: delta.0,
+                    dy: delta.1,
+                    // one finger moves the pointer
+                    fingers: 2,
+                    phase,
+                    seat: SEAT,
+                }
+            });
+            LRESULT(0)
+        }
+        WM_DROPFILES => {
+            let hdrop = HDROP(wparam.0 as _);
+            for path in drop_target::query_paths(hdrop) {
+                data.hook(WindowEvent::DroppedFile(path));
+            }
+            unsafe { DragFinish(hdrop) };
+            LRESULT(0)
+        }
+        // the app shows the composition itself, from `Ime::Preedit`
+        WM_IME_STARTCOMPOSITION => LRESULT(0),
+        WM_IME_COMPOSITION => {
+            let himc = unsafe { ImmGetContext(window) };
+            if himc.0.is_null() {
+                return unsafe { DefWindowProcW(window, message, wparam, lparam) };
+            }
+            let flags = lparam.0 as u32;
+
+            if flags & GCS_RESULTSTR.0 != 0 {
+                let text = composition_string(himc, GCS_RESULTSTR);
+                data.hook(WindowEvent::Ime(Ime::Preedit {
+                    text: String::new(),
+                    cursor: None,
+                }));
+                data.hook(WindowEvent::Ime(Ime::Commit(String::from_utf16_lossy(
+                    &text,
+                ))));
+            }
+            if flags & GCS_COMPSTR.0 != 0 {
+                let text = composition_string(himc, GCS_COMPSTR);
+                // in utf-16 units
+                let cursor = unsafe { ImmGetCompositionStringW(himc, GCS_CURSORPOS, None, 0) };
+                let cursor = usize::try_from(cursor).ok().map(|cursor| {
+                    let cursor = String::from_utf16_lossy(&text[..cursor.min(text.len())]).len();
+                    (cursor, cursor)
+                });
+                data.hook(WindowEvent::Ime(Ime::Preedit {
+                    text: String::from_utf16_lossy(&text),
+                    cursor,
+                }));
+            }
+
+            let _ = unsafe { ImmReleaseContext(window, himc) };
+            LRESULT(0)
+        }
+        WM_IME_ENDCOMPOSITION => {
+            data.hook(WindowEvent::Ime(Ime::Preedit {
+                text: String::new(),
+                cursor: None,
+            }));
+            LRESULT(0)
+        }
+        WM_KEYDOWN | WM_KEYUP | WM_SYSKEYDOWN | WM_SYSKEYUP => {
+            data.check_modifiers();
+            data.check_lock_state();
+            let modifiers = data.modifiers.get();
+
+            let down = matches!(message, WM_KEYDOWN | WM_SYSKEYDOWN);
+            let flags = hiword(lparam.0 as usize);
+            let scancode = flags & 0xff;
+            let vk = VIRTUAL_KEY(wparam.0 as u16);
+            let physical_key = scancode_to_physical(scancode, flags & KF_EXTENDED != 0);
+
+            // alt combinations type nothing, they're for the window menu
+            let text_raw = if message == WM_KEYDOWN {
+                take_chars(window).unwrap_or_default()
+            } else {
+                SmolStr::default()
+            };
+            // ctrl + alt is alt gr, which types as usual
+            let ctrl =
+                modifiers.contains(KeyModifiers::CTRL) && !modifiers.contains(KeyModifiers::ALT);
+            let text = if message == WM_KEYDOWN && ctrl {
+                text_without_ctrl(vk, scancode)
+            } else {
+                text_raw.clone()
+            };
+
+            let repeat = down && flags & KF_REPEAT != 0;
+            if !repeat || data.key_repeat.get() {
+                data.hook(WindowEvent::Key {
+                    down,
+                    repeat,
+                    physical_key,
+                    logical_key: text_to_logical(vk, physical_key, &text),
+                    text,
+                    text_raw,
+                    logical_key_unmodified: vk_to_logical(vk, physical_key),
+                    location: key_location(physical_key),
+                    timestamp: message_time(),
+                    seat: SEAT,
+                });
+            }
+
+            if down
+                && data.grab.get() != CursorGrabMode::None
+                && data.grab_escape_key.get() == Some(physical_key)
+            {
+                data.grab.set(CursorGrabMode::None);
+                data.release_grab();
+                data.hook(WindowEvent::CursorGrabChanged(false));
+            }
+            // keeps alt+f4 and the window menu working
+            if data.keyboard_grab.get() && matches!(message, WM_SYSKEYDOWN | WM_SYSKEYUP) {
+                LRESULT(0)
+            } else {
+                unsafe { DefWindowProcW(window, message, wparam, lparam) }
+            }
+        }
+        _ if message == taskbar::button_created_message() => {
+            data.apply_taskbar_progress(window);
+            LRESULT(0)
+        }
         _ => unsafe { DefWindowProcW(window, message, wparam, lparam) },
     }
 }
 
-fn to_scale_factor(dpi: u32) -> f64 {
+/// Windows get cloaked without a window message, e.g. when switching virtual desktops,
+/// so the ones of this thread are watched with a win event hook.
+pub struct CloakHook(HWINEVENTHOOK);
+impl CloakHook {
+    pub fn new() -> Option<Self> {
+        let hook = unsafe {
+            SetWinEventHook(
+                EVENT_OBJECT_CLOAKED,
+                EVENT_OBJECT_UNCLOAKED,
+                None,
+                Some(cloak_event),
+                GetCurrentProcessId(),
+                GetCurrentThreadId(),
+                WINEVENT_OUTOFCONTEXT,
+            )
+        };
+        if hook.is_invalid() {
+            log::error!("failed to watch window cloaking, only minimizing occludes windows");
+            return None;
+        }
+        Some(Self(hook))
+    }
+}
+impl Drop for CloakHook {
+    fn drop(&mut self) {
+        let _ = unsafe { UnhookWinEvent(self.0) };
+    }
+}
+unsafe extern "system" fn cloak_event(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    hwnd: HWND,
+    object: i32,
+    child: i32,
+    _thread: u32,
+    _time: u32,
+) {
+    if object != OBJID_WINDOW.0 || child != CHILDID_SELF as i32 {
+        return;
+    }
+    // other windows of the thread keep something else in their user data
+    let proc = unsafe { GetWindowLongPtrW(hwnd, GWLP_WNDPROC) };
+    if proc != wndproc as *const () as isize {
+        return;
+    }
+    if let Some(data) = window_data(hwnd) {
+        data.check_occluded(hwnd);
+    }
+}
+
+/// When the message being handled was posted, in milliseconds since the system started.
+fn message_time() -> Duration {
+    Duration::from_millis(unsafe { GetMessageTime() } as u32 as u64)
+}
+
+/// The client area in screen coordinates.
+fn client_screen_rect(hwnd: HWND) -> RECT {
+    let mut rect = RECT::default();
+    if let Err(err) = unsafe { GetClientRect(hwnd, &mut rect) } {
+        log::error!("failed to get client rect: {err}");
+    }
+    let mut corners = [
+        POINT {
+            x: rect.left,
+            y: rect.top,
+        },
+        POINT {
+            x: rect.right,
+            y: rect.bottom,
+        },
+    ];
+    unsafe { MapWindowPoints(Some(hwnd), None, &mut corners) };
+    RECT {
+        left: corners[0].x,
+        top: corners[0].y,
+        right: corners[1].x,
+        bottom: corners[1].y,
+    }
+}
+fn rect_center(rect: RECT) -> POINT {
+    POINT {
+        x: (rect.left + rect.right) / 2,
+        y: (rect.top + rect.bottom) / 2,
+    }
+}
+fn center_cursor(hwnd: HWND) {
+    let center = rect_center(client_screen_rect(hwnd));
+    if let Err(err) = unsafe { SetCursorPos(center.x, center.y) } {
+        log::error!("failed to center cursor: {err}");
+    }
+}
+
+/// Returns the utf-16 composition string of `kind`.
+fn composition_string(himc: HIMC, kind: IME_COMPOSITION_STRING) -> Vec<u16> {
+    // the length is in bytes
+    let len = unsafe { ImmGetCompositionStringW(himc, kind, None, 0) };
+    let Ok(len) = u32::try_from(len) else {
+        return Vec::new();
+    };
+    let mut buf = vec![0u16; len as usize / 2];
+    unsafe { ImmGetCompositionStringW(himc, kind, Some(buf.as_mut_ptr().cast()), len) };
+    buf
+}
+
+fn key_modifiers() -> KeyModifiers {
+    // the high bit is set while a key is held, the low bit while a lock key is toggled on
+    let held = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } < 0;
+    let toggled = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } & 1 != 0;
+
+    let mut modifiers = KeyModifiers::empty();
+    modifiers.set(KeyModifiers::SHIFT, held(VK_SHIFT));
+    modifiers.set(KeyModifiers::CTRL, held(VK_CONTROL));
+    modifiers.set(KeyModifiers::ALT, held(VK_MENU));
+    modifiers.set(KeyModifiers::SUPER, held(VK_LWIN) || held(VK_RWIN));
+    modifiers.set(KeyModifiers::CAPS_LOCK, toggled(VK_CAPITAL));
+    modifiers.set(KeyModifiers::NUM_LOCK, toggled(VK_NUMLOCK));
+    modifiers
+}
+
+/// The theme apps are set to in the system's settings.
+fn system_theme() -> Theme {
+    let mut light = 1u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut light as *mut u32 as *mut _),
+            Some(&mut size),
+        )
+    };
+    // missing before dark mode existed
+    if result != ERROR_SUCCESS || light != 0 {
+        Theme::Light
+    } else {
+        Theme::Dark
+    }
+}
+/// Colors the title bar and the rest of the frame.
+fn apply_theme(hwnd: HWND, theme: Theme) {
+    let dark = BOOL::from(theme == Theme::Dark);
+    if let Err(err) = set_dwm_attribute(hwnd, DWMWA_USE_IMMERSIVE_DARK_MODE, &dark) {
+        log::error!("failed to set dark mode: {err}");
+    }
+}
+/// `value` has to be of the type dwm expects for `attribute`.
+fn set_dwm_attribute<T>(
+    hwnd: HWND,
+    attribute: DWMWINDOWATTRIBUTE,
+    value: &T,
+) -> windows::core::Result<()> {
+    unsafe {
+        DwmSetWindowAttribute(
+            hwnd,
+            attribute,
+            value as *const T as *const _,
+            std::mem::size_of::<T>() as u32,
+        )
+    }
+}
+/// Lets dwm compose the alpha channel of the client area.
+///
+/// An empty blur region enables alpha blending without actually blurring anything.
+fn enable_transparency(hwnd: HWND) {
+    let region = unsafe { CreateRectRgn(0, 0, -1, -1) };
+    let blur = DWM_BLURBEHIND {
+        dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+        fEnable: true.into(),
+        hRgnBlur: region,
+        fTransitionOnMaximized: false.into(),
+    };
+    if let Err(err) = unsafe { DwmEnableBlurBehindWindow(hwnd, &blur) } {
+        log::error!("failed to enable window transparency: {err}");
+    }
+    let _ = unsafe { DeleteObject(region.into()) };
+}
+pub(super) fn to_scale_factor(dpi: u32) -> f64 {
     dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64
 }
+/// Converts a logical client size to the physical outer size that includes the window frame.
+fn client_to_window_size(window: HWND, size: (f64, f64), scale: f64, dpi: u32) -> (i32, i32) {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: (size.0 * scale).round() as i32,
+        bottom: (size.1 * scale).round() as i32,
+    };
+    let style = WINDOW_STYLE(unsafe { GetWindowLongPtrW(window, GWL_STYLE) } as u32);
+    let ex_style = WINDOW_EX_STYLE(unsafe { GetWindowLongPtrW(window, GWL_EXSTYLE) } as u32);
+    if let Err(err) = unsafe { AdjustWindowRectExForDpi(&mut rect, style, false, ex_style, dpi) } {
+        log::error!("failed to adjust window rect: {err}");
+    }
+    get_size(rect)
+}
+/// Moves and resizes the window to `rect` in screen coordinates, updating the frame.
+fn set_window_rect(hwnd: HWND, rect: RECT) {
+    let (w, h) = get_size(rect);
+    if let Err(err) = unsafe {
+        SetWindowPos(
+            hwnd,
+            None,
+            rect.left,
+            rect.top,
+            w,
+            h,
+            SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+        )
+    } {
+        log::error!("failed to set window position: {err}");
+    }
+}
 fn get_size(rect: RECT) -> (i32, i32) {
     let w = rect.right - rect.left;
     let h = rect.bottom - rect.top;
@@ -0,0 +1,79 @@
+//! Checks that delivering a steady load of events doesn't allocate.
+//!
+//! Its own binary since the counting allocator is global.
+#![cfg(target_os = "linux")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use waywin::{
+    event::{DeviceEvent, WaywinEvent},
+    take_events,
+};
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts allocations of the thread that turned counting on.
+struct CountingAllocator;
+impl CountingAllocator {
+    fn count() {
+        if COUNTING.try_with(Cell::get).unwrap_or(false) {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        }
+    }
+}
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        unsafe { System.alloc(layout) }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// One loop iteration: what the dispatch handlers push, then its delivery.
+fn iteration(pushed: &mut Vec<WaywinEvent>, delivered: &mut Vec<WaywinEvent>) {
+    for i in 0..1000 {
+        pushed.push(WaywinEvent::DeviceEvent(DeviceEvent::PointerMoved {
+            delta: (i as f64, 0.0),
+            delta_unaccel: (i as f64, 0.0),
+        }));
+    }
+    pushed.push(WaywinEvent::AboutToWait);
+    for event in take_events(pushed, delivered) {
+        std::hint::black_box(event);
+    }
+}
+
+#[test]
+fn steady_events_dont_allocate() {
+    let mut pushed = Vec::new();
+    let mut delivered = Vec::new();
+    // each buffer grows during its first delivery
+    iteration(&mut pushed, &mut delivered);
+    iteration(&mut pushed, &mut delivered);
+
+    COUNTING.set(true);
+    for _ in 0..100 {
+        iteration(&mut pushed, &mut delivered);
+    }
+    COUNTING.set(false);
+    assert_eq!(ALLOCATIONS.get(), 0);
+
+    // the counting does see allocations
+    COUNTING.set(true);
+    drop(std::hint::black_box(Vec::<u8>::with_capacity(1)));
+    COUNTING.set(false);
+    assert_eq!(ALLOCATIONS.get(), 1);
+}